@@ -0,0 +1,264 @@
+// tests/api_keys_tests.rs
+
+#[cfg(test)]
+mod api_keys_tests {
+    use std::path::PathBuf;
+    use std::sync::Arc;
+
+    use actix_web::{
+        http::header::AUTHORIZATION,
+        http::StatusCode,
+        test::{self, TestRequest},
+        web, App,
+    };
+    use mfl_manager_lib::{
+        api_keys::{ApiKeyInfo, ApiKeyStore, Permission},
+        app_state::{InMemorySessionStore, SessionStore},
+        circuit_breaker::CircuitBreaker,
+        crypto::SecretCipher,
+        export_cache::{CachePolicy, ExportCache},
+        handler_middleware::{AuthMiddleware, API_KEY_HEADER},
+        handler_models::{CreateApiKeyResponse, LoginRequest, LoginResponse},
+        handlers,
+        mfl_api::MflClientConfig,
+        rate_limit::RateLimiter,
+    };
+    use mfl_mock::{MockMflServer, MOCK_LEAGUE_ID, MOCK_PASSWORD, MOCK_USERNAME};
+    use serde_json::json;
+
+    const KEY: [u8; 32] = [9u8; 32];
+
+    // The mock user commissions the mock league
+    const COMMISSIONER_ABILITIES: &str = r#"{
+        "version": "1.0",
+        "abilities": { "ability": [
+            { "name": "COMMISSIONER", "$t": "1" },
+            { "name": "SUBMIT_LINEUP", "$t": "1" }
+        ]},
+        "encoding": "utf-8"
+    }"#;
+
+    fn temp_db_path() -> PathBuf {
+        std::env::temp_dir().join(format!("mfl_api_keys_{}.db", uuid::Uuid::new_v4()))
+    }
+
+    fn client_config(mock: &MockMflServer) -> MflClientConfig {
+        MflClientConfig {
+            base_url: mock.base_url().to_string(),
+            max_retries: 0,
+            rate_limiter: Arc::new(RateLimiter::default()),
+            circuit_breaker: Arc::new(CircuitBreaker::default()),
+            export_cache: Arc::new(ExportCache::new(CachePolicy::empty())),
+            ..MflClientConfig::default()
+        }
+    }
+
+    macro_rules! init_app {
+        ($mock:expr, $store:expr) => {
+            test::init_service(
+                App::new()
+                    .app_data(web::Data::new(Arc::new(InMemorySessionStore::new()) as SessionStore))
+                    .app_data(web::Data::new(client_config(&$mock)))
+                    .app_data($store.clone())
+                    .service(handlers::login_handler)
+                    .service(
+                        web::scope("")
+                            .wrap(AuthMiddleware)
+                            .service(handlers::logout_handler)
+                            .service(handlers::get_me_handler)
+                            .service(handlers::get_message_board_handler)
+                            .service(handlers::post_message_thread_handler)
+                            .service(handlers::invalidate_cache_handler)
+                            .service(handlers::create_api_key_handler)
+                            .service(handlers::list_api_keys_handler)
+                            .service(handlers::revoke_api_key_handler)
+                    )
+            ).await
+        };
+    }
+
+    macro_rules! login {
+        ($app:expr) => {{
+            let resp = test::call_service(&$app, TestRequest::post()
+                .uri("/login")
+                .set_json(LoginRequest {
+                    username: MOCK_USERNAME.to_string(),
+                    password: MOCK_PASSWORD.to_string(),
+                    league_id: None,
+                    year: "2025".to_string(),
+                    remember_credentials: false,
+                })
+                .to_request()).await;
+            assert_eq!(resp.status(), StatusCode::OK);
+            let login: LoginResponse = test::read_body_json(resp).await;
+            format!("Bearer {}", login.token)
+        }};
+    }
+
+    macro_rules! create_key {
+        ($app:expr, $bearer:expr, $permissions:expr) => {{
+            let body = json!({
+                "name": "Discord bot",
+                "permissions": $permissions,
+                "username": MOCK_USERNAME,
+                "password": MOCK_PASSWORD,
+            });
+            let resp = test::call_service(&$app, TestRequest::post()
+                .uri("/admin/api-keys")
+                .insert_header((AUTHORIZATION, $bearer.clone()))
+                .set_json(body)
+                .to_request()).await;
+            assert_eq!(resp.status(), StatusCode::CREATED);
+            let created: CreateApiKeyResponse = test::read_body_json(resp).await;
+            created
+        }};
+    }
+
+    fn imports(mock: &MockMflServer) -> Vec<mfl_mock::RecordedRequest> {
+        mock.requests().into_iter().filter(|request| request.path.ends_with("/import")).collect()
+    }
+
+    #[actix_web::test]
+    async fn keys_act_for_their_league_within_their_permissions() {
+        let mock = MockMflServer::start().unwrap();
+        mock.set_fixture("abilities", COMMISSIONER_ABILITIES);
+        let store = web::Data::new(ApiKeyStore::open(":memory:", SecretCipher::new(&KEY)).unwrap()
+            .with_client_config(client_config(&mock)));
+        let app = init_app!(mock, store);
+        let bearer = login!(app);
+
+        let read_only = create_key!(app, bearer, ["read"]);
+        assert!(read_only.key.starts_with("mflk_"));
+        assert_eq!(read_only.api_key.league_id, MOCK_LEAGUE_ID);
+        assert_eq!(read_only.api_key.permissions, vec![Permission::Read]);
+        let writer = create_key!(app, bearer, ["read", "messages_write"]);
+
+        // Either header works for reads
+        let resp = test::call_service(&app, TestRequest::get()
+            .uri("/messages")
+            .insert_header((AUTHORIZATION, format!("Bearer {}", read_only.key)))
+            .to_request()).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        let resp = test::call_service(&app, TestRequest::get()
+            .uri("/me")
+            .insert_header((API_KEY_HEADER, read_only.key.clone()))
+            .to_request()).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        // Writes need the permission, and keys never reach admin or session endpoints
+        let message = json!({ "subject": "Waivers", "body": "Run tonight" });
+        let resp = test::call_service(&app, TestRequest::post()
+            .uri("/messages")
+            .insert_header((API_KEY_HEADER, read_only.key.clone()))
+            .set_json(&message)
+            .to_request()).await;
+        assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+        assert!(imports(&mock).is_empty(), "nothing may reach MFL");
+
+        let resp = test::call_service(&app, TestRequest::post()
+            .uri("/messages")
+            .insert_header((API_KEY_HEADER, writer.key.clone()))
+            .set_json(&message)
+            .to_request()).await;
+        assert_eq!(resp.status(), StatusCode::CREATED);
+        assert_eq!(imports(&mock)[0].param("L"), Some(MOCK_LEAGUE_ID));
+
+        for req in [TestRequest::get().uri("/admin/api-keys"), TestRequest::post().uri("/logout")] {
+            let resp = test::call_service(&app, req.insert_header((API_KEY_HEADER, writer.key.clone())).to_request()).await;
+            assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+        }
+
+        // A tampered secret is just an invalid token
+        let tampered = format!("{}x", writer.key);
+        let resp = test::call_service(&app, TestRequest::get()
+            .uri("/messages")
+            .insert_header((API_KEY_HEADER, tampered))
+            .to_request()).await;
+        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+        mock.stop().await;
+    }
+
+    #[actix_web::test]
+    async fn admins_list_and_revoke_keys() {
+        let mock = MockMflServer::start().unwrap();
+        mock.set_fixture("abilities", COMMISSIONER_ABILITIES);
+        let store = web::Data::new(ApiKeyStore::open(":memory:", SecretCipher::new(&KEY)).unwrap()
+            .with_client_config(client_config(&mock)));
+        let app = init_app!(mock, store);
+        let bearer = login!(app);
+        let created = create_key!(app, bearer, ["read"]);
+
+        let resp = test::call_service(&app, TestRequest::get()
+            .uri("/messages")
+            .insert_header((API_KEY_HEADER, created.key.clone()))
+            .to_request()).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        let resp = test::call_service(&app, TestRequest::get()
+            .uri("/admin/api-keys")
+            .insert_header((AUTHORIZATION, bearer.clone()))
+            .to_request()).await;
+        let keys: Vec<ApiKeyInfo> = test::read_body_json(resp).await;
+        assert_eq!(keys.len(), 1);
+        assert_eq!(keys[0].id, created.api_key.id);
+        assert!(keys[0].last_used_at.is_some());
+
+        let revoke = format!("/admin/api-keys/{}", created.api_key.id);
+        let resp = test::call_service(&app, TestRequest::delete()
+            .uri(&revoke)
+            .insert_header((AUTHORIZATION, bearer.clone()))
+            .to_request()).await;
+        assert_eq!(resp.status(), StatusCode::NO_CONTENT);
+        let resp = test::call_service(&app, TestRequest::delete()
+            .uri(&revoke)
+            .insert_header((AUTHORIZATION, bearer.clone()))
+            .to_request()).await;
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+
+        let resp = test::call_service(&app, TestRequest::get()
+            .uri("/messages")
+            .insert_header((API_KEY_HEADER, created.key.clone()))
+            .to_request()).await;
+        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+
+        // Credentials MFL rejects never become a key
+        let resp = test::call_service(&app, TestRequest::post()
+            .uri("/admin/api-keys")
+            .insert_header((AUTHORIZATION, bearer.clone()))
+            .set_json(json!({ "name": "bot", "permissions": ["read"], "username": MOCK_USERNAME, "password": "wrong" }))
+            .to_request()).await;
+        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+        mock.stop().await;
+    }
+
+    #[actix_web::test]
+    async fn keys_are_stored_hashed_and_log_in_again_after_a_restart() {
+        let mock = MockMflServer::start().unwrap();
+        let path = temp_db_path();
+        let key = {
+            let store = ApiKeyStore::open(&path, SecretCipher::new(&KEY)).unwrap().with_client_config(client_config(&mock));
+            store.create("bot", MOCK_LEAGUE_ID, "2025", vec![Permission::Read, Permission::MessagesWrite],
+                (MOCK_USERNAME, MOCK_PASSWORD), None).unwrap().key
+        };
+
+        let raw = std::fs::read(&path).unwrap();
+        let secret = key.rsplit('_').next().unwrap();
+        let contains = |needle: &str| raw.windows(needle.len()).any(|window| window == needle.as_bytes());
+        assert!(!contains(secret), "the secret must not be stored");
+        assert!(!contains(MOCK_PASSWORD), "the MFL password must be encrypted");
+
+        // A fresh store has no MFL cookie, so the first write logs in with the stored credentials
+        let store = web::Data::new(ApiKeyStore::open(&path, SecretCipher::new(&KEY)).unwrap()
+            .with_client_config(client_config(&mock)));
+        let app = init_app!(mock, store);
+        let resp = test::call_service(&app, TestRequest::post()
+            .uri("/messages")
+            .insert_header((API_KEY_HEADER, key.clone()))
+            .set_json(json!({ "subject": "Waivers", "body": "Run tonight" }))
+            .to_request()).await;
+        assert_eq!(resp.status(), StatusCode::CREATED);
+        assert!(mock.requests().iter().any(|request| request.path.ends_with("/login")));
+        std::fs::remove_file(&path).ok();
+        mock.stop().await;
+    }
+}
@@ -0,0 +1,85 @@
+// tests/circuit_breaker_tests.rs
+
+#[cfg(test)]
+mod circuit_breaker_tests {
+    use std::time::Duration;
+
+    use actix_web::{http::header::RETRY_AFTER, http::StatusCode, ResponseError};
+    use mfl_manager_lib::{
+        circuit_breaker::{CircuitBreaker, CircuitState},
+        errors::ServiceError,
+        mfl_api::MflError,
+    };
+
+    #[test]
+    fn opens_after_repeated_failures_and_fails_fast() {
+        let breaker = CircuitBreaker::new(3, Duration::from_secs(60));
+        for _ in 0..2 {
+            breaker.allow_request().unwrap().failure();
+        }
+        assert_eq!(breaker.state(), CircuitState::Closed);
+
+        breaker.allow_request().unwrap().failure();
+        assert_eq!(breaker.state(), CircuitState::Open);
+        assert!(breaker.allow_request().is_err());
+    }
+
+    #[test]
+    fn a_success_resets_the_failure_count() {
+        let breaker = CircuitBreaker::new(2, Duration::from_secs(60));
+        breaker.record_failure();
+        breaker.record_success();
+        breaker.record_failure();
+        assert_eq!(breaker.state(), CircuitState::Closed);
+        assert_eq!(breaker.snapshot().consecutive_failures, 1);
+    }
+
+    #[test]
+    fn half_open_lets_a_single_probe_through() {
+        let breaker = CircuitBreaker::new(1, Duration::from_millis(20));
+        breaker.record_failure();
+        assert!(breaker.allow_request().is_err());
+
+        std::thread::sleep(Duration::from_millis(30));
+        let probe = breaker.allow_request().expect("probe should be allowed after the cool-down");
+        assert_eq!(breaker.state(), CircuitState::HalfOpen);
+        assert!(breaker.allow_request().is_err(), "only one probe at a time");
+
+        // A failed probe re-opens the circuit, a successful one closes it
+        probe.failure();
+        assert_eq!(breaker.state(), CircuitState::Open);
+        std::thread::sleep(Duration::from_millis(30));
+        breaker.allow_request().unwrap().success();
+        assert_eq!(breaker.state(), CircuitState::Closed);
+    }
+
+    #[test]
+    fn an_abandoned_probe_counts_as_failed() {
+        let breaker = CircuitBreaker::new(1, Duration::from_millis(20));
+        breaker.record_failure();
+        std::thread::sleep(Duration::from_millis(30));
+
+        // E.g. the client hung up and the request future was dropped mid-call
+        drop(breaker.allow_request().unwrap());
+        assert_eq!(breaker.state(), CircuitState::Open);
+
+        std::thread::sleep(Duration::from_millis(30));
+        breaker.allow_request().expect("a new probe once the cool-down passes").success();
+        assert_eq!(breaker.state(), CircuitState::Closed);
+    }
+
+    #[test]
+    fn dropping_a_closed_circuit_permit_records_nothing() {
+        let breaker = CircuitBreaker::new(1, Duration::from_secs(60));
+        drop(breaker.allow_request().unwrap());
+        assert_eq!(breaker.state(), CircuitState::Closed);
+        assert_eq!(breaker.snapshot().consecutive_failures, 0);
+    }
+
+    #[test]
+    fn unavailable_errors_become_503_with_retry_after() {
+        let error = ServiceError::MflApiError(MflError::Unavailable { retry_after: Duration::from_secs(12) });
+        assert_eq!(error.status_code(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(error.error_response().headers().get(RETRY_AFTER).unwrap(), "12");
+    }
+}
@@ -0,0 +1,102 @@
+// tests/config_tests.rs
+
+#[cfg(test)]
+mod config_tests {
+    use std::collections::HashMap;
+    use std::time::Duration;
+
+    use mfl_manager_lib::config::{AppConfig, ConfigError, LogFormat, SessionBackendKind};
+
+    // 32 zero bytes, base64 encoded
+    const TEST_KEY: &str = "AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA=";
+
+    fn env(pairs: &[(&str, &str)]) -> impl Fn(&str) -> Option<String> {
+        let vars: HashMap<String, String> = pairs.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+        move |name| vars.get(name).cloned()
+    }
+
+    #[test]
+    fn defaults_are_valid() {
+        let mut config = AppConfig::default();
+        config.validate().unwrap();
+        assert_eq!(config.server.bind, "0.0.0.0:8080");
+        assert_eq!(config.session.backend, SessionBackendKind::Memory);
+        assert_eq!(config.cache.policy().ttl_for("players"), Some(Duration::from_secs(86400)));
+    }
+
+    #[test]
+    fn file_overrides_defaults_and_env_overrides_file() {
+        let mut config = AppConfig::from_toml(r#"
+            [server]
+            bind = "127.0.0.1:9000"
+            cors_origins = ["https://app.example.com"]
+
+            [cache.ttl_secs]
+            liveScoring = 5
+        "#).unwrap();
+        // Exports the file doesn't mention keep their default TTL
+        assert_eq!(config.cache.ttl_secs["liveScoring"], 5);
+        assert_eq!(config.cache.ttl_secs["players"], 86400);
+
+        config.apply_env(env(&[
+            ("BIND_ADDR", "127.0.0.1:9100"),
+            ("LOG_FORMAT", "json"),
+            ("CACHE_TTLS", "players=60, rosters=0"),
+            ("MFL_BASE_URL", "http://localhost:8099/"),
+        ])).unwrap();
+        config.validate().unwrap();
+
+        assert_eq!(config.server.bind, "127.0.0.1:9100");
+        assert_eq!(config.server.cors_origins, vec!["https://app.example.com"]);
+        assert_eq!(config.log.format, LogFormat::Json);
+        assert_eq!(config.mfl.base_url, "http://localhost:8099");
+        let policy = config.cache.policy();
+        assert_eq!(policy.ttl_for("players"), Some(Duration::from_secs(60)));
+        assert_eq!(policy.ttl_for("rosters"), None);
+        assert_eq!(policy.ttl_for("liveScoring"), Some(Duration::from_secs(5)));
+    }
+
+    #[test]
+    fn unknown_keys_and_bad_env_values_are_rejected() {
+        assert!(AppConfig::from_toml("[server]\nport = 8080\n").is_err());
+
+        let mut config = AppConfig::default();
+        let err = config.apply_env(env(&[("WORKERS", "lots")])).unwrap_err();
+        assert!(matches!(err, ConfigError::Env { ref name, .. } if name == "WORKERS"), "{}", err);
+    }
+
+    #[test]
+    fn validation_reports_every_problem() {
+        let mut config = AppConfig::default();
+        config.apply_env(env(&[
+            ("BIND_ADDR", "localhost"),
+            ("WORKERS", "0"),
+            ("CORS_ORIGINS", "https://app.example.com/path"),
+            ("MFL_BASE_URL", "ftp://mfl"),
+            ("MFL_READ_TIMEOUT_SECS", "0"),
+            ("SESSION_BACKEND", "sqlite"),
+            ("API_KEYS_DB_PATH", "api_keys.db"),
+        ])).unwrap();
+
+        let ConfigError::Invalid(problems) = config.validate().unwrap_err() else {
+            panic!("expected validation problems");
+        };
+        assert_eq!(problems.len(), 7, "{:?}", problems);
+        assert!(problems.iter().any(|problem| problem.contains("SESSION_ENCRYPTION_KEY")));
+        assert!(problems.iter().any(|problem| problem.starts_with("api_keys.db_path")));
+    }
+
+    #[test]
+    fn printed_config_hides_the_encryption_key() {
+        let mut config = AppConfig::default();
+        config.apply_env(env(&[("SESSION_BACKEND", "sqlite"), ("SESSION_ENCRYPTION_KEY", TEST_KEY)])).unwrap();
+        config.validate().unwrap();
+
+        let printed = config.to_toml_redacted();
+        assert!(!printed.contains(TEST_KEY));
+        assert!(printed.contains("backend = \"sqlite\""));
+        // What is printed loads back as the same settings
+        let reloaded = AppConfig::from_toml(&printed).unwrap();
+        assert_eq!(reloaded.session.backend, SessionBackendKind::Sqlite);
+    }
+}
@@ -0,0 +1,144 @@
+// tests/errors_tests.rs
+
+#[cfg(test)]
+mod errors_tests {
+    use std::time::Duration;
+
+    use actix_web::{body::to_bytes, http::StatusCode, ResponseError};
+    use mfl_manager_lib::{
+        api_keys::ApiKeyError,
+        app_state::SessionStoreError,
+        errors::{ErrorCode, ErrorResponse, ServiceError, UpstreamError},
+        mfl_api::MflError,
+        redact::redact,
+        tokens::TokenError,
+    };
+
+    async fn error_body(error: &ServiceError) -> ErrorResponse {
+        let bytes = to_bytes(error.error_response().into_body()).await.unwrap();
+        serde_json::from_slice(&bytes).unwrap()
+    }
+
+    // Status, code and retryable flag, as sent to the client
+    async fn check(error: ServiceError, status: StatusCode, code: ErrorCode, retryable: bool) -> ErrorResponse {
+        assert_eq!(error.status_code(), status, "{}", error);
+        let body = error_body(&error).await;
+        assert_eq!(body.code, code, "{}", error);
+        assert_eq!(body.retryable, retryable, "{}", error);
+        assert_eq!(body.message, redact(&error.to_string()));
+        body
+    }
+
+    fn api_status(status: u16, body: &str) -> MflError {
+        MflError::ApiStatusError { status: reqwest::StatusCode::from_u16(status).unwrap(), body: body.to_string() }
+    }
+
+    // Real transport errors: a listener that never answers, and a port nobody listens on
+    async fn network_errors() -> (reqwest::Error, reqwest::Error) {
+        let silent = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let client = reqwest::Client::builder().timeout(Duration::from_millis(50)).build().unwrap();
+        let timeout = client.get(format!("http://{}/", silent.local_addr().unwrap())).send().await.unwrap_err();
+
+        let closed = std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap();
+        let refused = client.get(format!("http://{}/", closed)).send().await.unwrap_err();
+        (timeout, refused)
+    }
+
+    #[actix_web::test]
+    async fn local_errors_keep_their_status_and_are_not_retryable() {
+        use ErrorCode::*;
+        let cases = [
+            (ServiceError::InternalServerError, StatusCode::INTERNAL_SERVER_ERROR, InternalError),
+            (ServiceError::BadRequest("no name".to_string()), StatusCode::BAD_REQUEST, BadRequest),
+            (ServiceError::Unauthorized("no token".to_string()), StatusCode::UNAUTHORIZED, Unauthorized),
+            (ServiceError::Forbidden("owners only".to_string()), StatusCode::FORBIDDEN, Forbidden),
+            (ServiceError::SessionExpired, StatusCode::UNAUTHORIZED, SessionExpired),
+            (ServiceError::NotFound("no such key".to_string()), StatusCode::NOT_FOUND, NotFound),
+            (ServiceError::SessionStoreError(SessionStoreError::LockPoisoned), StatusCode::INTERNAL_SERVER_ERROR, InternalError),
+            (ServiceError::ApiKeyError(ApiKeyError::LockPoisoned), StatusCode::INTERNAL_SERVER_ERROR, InternalError),
+            (ServiceError::TokenError(TokenError::NoKeys), StatusCode::INTERNAL_SERVER_ERROR, InternalError),
+        ];
+        for (error, status, code) in cases {
+            let body = check(error, status, code, false).await;
+            assert!(body.upstream.is_none());
+            assert!(body.retry_after_secs.is_none());
+        }
+    }
+
+    #[actix_web::test]
+    async fn mfl_failures_map_to_gateway_statuses() {
+        use ErrorCode::*;
+        let (timeout, refused) = network_errors().await;
+        let cases = [
+            (MflError::Network(timeout), StatusCode::GATEWAY_TIMEOUT, MflTimeout, true),
+            (MflError::Network(refused), StatusCode::BAD_GATEWAY, MflBadGateway, true),
+            (MflError::RequestFailed("connection reset".to_string()), StatusCode::BAD_GATEWAY, MflBadGateway, true),
+            (api_status(404, "Not Found"), StatusCode::BAD_GATEWAY, MflBadGateway, false),
+            (api_status(429, "Slow down"), StatusCode::TOO_MANY_REQUESTS, MflRateLimited, true),
+            (api_status(500, "Oops"), StatusCode::BAD_GATEWAY, MflBadGateway, true),
+            (api_status(503, "Maintenance"), StatusCode::SERVICE_UNAVAILABLE, MflUnavailable, true),
+            (api_status(504, "Gateway Timeout"), StatusCode::GATEWAY_TIMEOUT, MflTimeout, true),
+            (MflError::JsonParse(serde_json::from_str::<u32>("<html>").unwrap_err()), StatusCode::BAD_GATEWAY, MflBadGateway, false),
+            (MflError::LeagueHostNotFound, StatusCode::BAD_GATEWAY, MflBadGateway, false),
+            (MflError::RateLimited { retry_after: Duration::from_secs(30) }, StatusCode::TOO_MANY_REQUESTS, MflRateLimited, true),
+            (MflError::Unavailable { retry_after: Duration::from_secs(10) }, StatusCode::SERVICE_UNAVAILABLE, MflUnavailable, true),
+            (MflError::ReauthenticationRequired("no stored credentials".to_string()), StatusCode::UNAUTHORIZED, MflReauthenticationRequired, false),
+            (MflError::ImportRejected("Message body is required".to_string()), StatusCode::UNPROCESSABLE_ENTITY, MflImportRejected, false),
+            (MflError::PermissionDenied("API requires logged in user".to_string()), StatusCode::FORBIDDEN, MflPermissionDenied, false),
+            (MflError::UnexpectedReply("expected <status> or <error>, got <html>".to_string()), StatusCode::BAD_GATEWAY, MflBadGateway, false),
+            (MflError::LoginFailed("Invalid Password".to_string()), StatusCode::UNAUTHORIZED, LoginFailed, false),
+            (MflError::LoginCookieNotFound, StatusCode::UNAUTHORIZED, LoginFailed, false),
+            (MflError::Regex(regex::Error::Syntax("unclosed group".to_string())), StatusCode::INTERNAL_SERVER_ERROR, InternalError, false),
+            (MflError::InvalidHeaderValue(reqwest::header::HeaderValue::from_str("\n").unwrap_err()), StatusCode::INTERNAL_SERVER_ERROR, InternalError, false),
+            (MflError::ClientInitializationFailed("no TLS backend".to_string()), StatusCode::INTERNAL_SERVER_ERROR, InternalError, false),
+            (MflError::FixtureNotFound("GET /2025/export".to_string()), StatusCode::INTERNAL_SERVER_ERROR, InternalError, false),
+        ];
+        for (error, status, code, retryable) in cases {
+            check(ServiceError::MflApiError(error), status, code, retryable).await;
+        }
+
+        // Logging in fails the same way when MFL itself is the problem
+        check(ServiceError::MflLoginError(api_status(503, "Maintenance")), StatusCode::SERVICE_UNAVAILABLE, MflUnavailable, true).await;
+        check(ServiceError::MflLoginError(MflError::LoginFailed("Invalid Password".to_string())), StatusCode::UNAUTHORIZED, LoginFailed, false).await;
+    }
+
+    #[actix_web::test]
+    async fn bodies_carry_what_mfl_replied() {
+        let body = error_body(&ServiceError::MflApiError(api_status(404, "<error>No such league</error>"))).await;
+        assert_eq!(body.upstream, Some(UpstreamError { status: Some(404), details: "<error>No such league</error>".to_string() }));
+
+        let (timeout, _) = network_errors().await;
+        let body = error_body(&ServiceError::MflApiError(MflError::Network(timeout))).await;
+        let upstream = body.upstream.expect("transport errors are reported");
+        assert_eq!(upstream.status, None);
+        assert!(upstream.details.contains("timed out"), "{}", upstream.details);
+
+        let body = error_body(&ServiceError::MflApiError(MflError::ImportRejected("Message body is required".to_string()))).await;
+        assert_eq!(body.upstream.unwrap().details, "Message body is required");
+
+        // Long replies are cut short and secrets never leave
+        let reply = format!("<status MFL_USER_ID=\"c00kie\">{}</status>", "x".repeat(2000));
+        let upstream = error_body(&ServiceError::MflApiError(api_status(500, &reply))).await.upstream.unwrap();
+        assert!(upstream.details.len() < 600);
+        assert!(!upstream.details.contains("c00kie"));
+
+        // Limits we apply ourselves say when to come back, but MFL never saw the request
+        let body = error_body(&ServiceError::MflApiError(MflError::RateLimited { retry_after: Duration::from_secs(30) })).await;
+        assert_eq!(body.retry_after_secs, Some(30));
+        assert!(body.upstream.is_none());
+    }
+
+    #[actix_web::test]
+    async fn messages_never_carry_the_mfl_cookie() {
+        let login_reply = r#"<status MFL_USER_ID="c00kie">OK</status>"#;
+        for error in [
+            ServiceError::MflLoginError(api_status(500, login_reply)),
+            ServiceError::MflApiError(api_status(200, &format!("{}{}", login_reply, "x".repeat(2000)))),
+        ] {
+            let body = error_body(&error).await;
+            assert!(error.to_string().contains("c00kie"));
+            assert!(!body.message.contains("c00kie"), "{}", body.message);
+            assert!(body.message.len() < 600);
+        }
+    }
+}
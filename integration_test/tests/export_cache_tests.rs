@@ -0,0 +1,209 @@
+// tests/export_cache_tests.rs
+
+#[cfg(test)]
+mod export_cache_tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use mfl_manager_lib::{
+        circuit_breaker::CircuitBreaker,
+        export_cache::{CacheKey, CachePolicy, DataSource, ExportCache, Freshness},
+        mfl_api::{MflApi, MflClientConfig, MflError, MflReply},
+        offline_store::OfflineStore,
+        rate_limit::RateLimiter,
+    };
+    use mfl_mock::{MockMflServer, MOCK_LEAGUE_ID, MOCK_PASSWORD, MOCK_USERNAME};
+    use reqwest::StatusCode;
+
+    fn reply(status: StatusCode, body: &str) -> MflReply {
+        MflReply {
+            status,
+            url: "https://www48.myfantasyleague.com/2025/export".parse().unwrap(),
+            body: body.to_string(),
+            freshness: Freshness::live(),
+        }
+    }
+
+    fn key(args: &str) -> CacheKey {
+        CacheKey::from_args("12345", "2025", args)
+    }
+
+    async fn fetch_counted(cache: &ExportCache, key: &CacheKey, calls: &AtomicUsize, status: StatusCode) -> Result<MflReply, MflError> {
+        cache.get_or_fetch(key, || async {
+            calls.fetch_add(1, Ordering::SeqCst);
+            Ok(reply(status, "{}"))
+        }).await
+    }
+
+    #[test]
+    fn keys_ignore_parameter_order_and_json_flag() {
+        let a = key("TYPE=players&L=12345&PLAYERS=1,2&DETAILS=1&JSON=1");
+        let b = key("TYPE=players&DETAILS=1&PLAYERS=1,2&L=12345");
+        assert_eq!(a, b);
+        assert_eq!(a.export, "players");
+        assert_eq!(a.params, "DETAILS=1&PLAYERS=1,2");
+        assert_ne!(a, key("TYPE=players&L=12345&PLAYERS=3"));
+    }
+
+    #[test]
+    fn keys_tell_users_apart_without_holding_their_name() {
+        let anonymous = key("TYPE=players&L=12345");
+        let alice = key("TYPE=players&L=12345").for_user(Some("alice"));
+        assert_eq!(anonymous.user, "");
+        assert_ne!(alice, anonymous);
+        assert_ne!(alice, key("TYPE=players&L=12345").for_user(Some("bob")));
+        assert_eq!(alice, key("TYPE=players&L=12345").for_user(Some("alice")));
+        assert!(!alice.user.contains("alice"));
+    }
+
+    #[actix_web::test]
+    async fn fresh_replies_are_served_from_cache() {
+        let cache = ExportCache::new(CachePolicy::empty().with_ttl("players", Duration::from_secs(60)));
+        let calls = AtomicUsize::new(0);
+        let key = key("TYPE=players&L=12345&JSON=1");
+
+        let first = fetch_counted(&cache, &key, &calls, StatusCode::OK).await.unwrap();
+        let second = fetch_counted(&cache, &key, &calls, StatusCode::OK).await.unwrap();
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+        assert_eq!(first.freshness.source, DataSource::Live);
+        assert_eq!(second.freshness.source, DataSource::Cache);
+
+        let stats = cache.stats();
+        assert_eq!((stats.hits, stats.misses, stats.entries), (1, 1, 1));
+    }
+
+    #[actix_web::test]
+    async fn expired_failed_and_uncached_replies_go_upstream() {
+        let cache = ExportCache::new(CachePolicy::empty().with_ttl("liveScoring", Duration::from_millis(20)));
+        let calls = AtomicUsize::new(0);
+
+        // Expired
+        let live = key("TYPE=liveScoring&L=12345&JSON=1");
+        fetch_counted(&cache, &live, &calls, StatusCode::OK).await.unwrap();
+        actix_web::rt::time::sleep(Duration::from_millis(30)).await;
+        fetch_counted(&cache, &live, &calls, StatusCode::OK).await.unwrap();
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+
+        // Error replies are never stored
+        let failing = key("TYPE=liveScoring&L=12345&W=3");
+        fetch_counted(&cache, &failing, &calls, StatusCode::BAD_GATEWAY).await.unwrap();
+        fetch_counted(&cache, &failing, &calls, StatusCode::BAD_GATEWAY).await.unwrap();
+        assert_eq!(calls.load(Ordering::SeqCst), 4);
+
+        // No TTL for this export
+        let rosters = key("TYPE=rosters&L=12345");
+        fetch_counted(&cache, &rosters, &calls, StatusCode::OK).await.unwrap();
+        fetch_counted(&cache, &rosters, &calls, StatusCode::OK).await.unwrap();
+        assert_eq!(calls.load(Ordering::SeqCst), 6);
+    }
+
+    #[actix_web::test]
+    async fn concurrent_misses_share_one_fetch() {
+        let cache = Arc::new(ExportCache::new(CachePolicy::default()));
+        let calls = Arc::new(AtomicUsize::new(0));
+        let key = key("TYPE=players&L=12345&JSON=1");
+
+        let handles: Vec<_> = (0..5).map(|_| {
+            let (cache, calls, key) = (cache.clone(), calls.clone(), key.clone());
+            actix_web::rt::spawn(async move {
+                cache.get_or_fetch(&key, || async {
+                    calls.fetch_add(1, Ordering::SeqCst);
+                    actix_web::rt::time::sleep(Duration::from_millis(20)).await;
+                    Ok(reply(StatusCode::OK, "{}"))
+                }).await
+            })
+        }).collect();
+        for handle in handles {
+            handle.await.unwrap().unwrap();
+        }
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[actix_web::test]
+    async fn invalidation_can_target_an_export_and_league() {
+        let cache = ExportCache::new(CachePolicy::default());
+        let calls = AtomicUsize::new(0);
+        for args in ["TYPE=players&L=12345", "TYPE=messageBoard&L=12345", "TYPE=league&L=12345"] {
+            fetch_counted(&cache, &key(args), &calls, StatusCode::OK).await.unwrap();
+        }
+        let other_league = CacheKey::from_args("67890", "2025", "TYPE=messageBoard&L=67890");
+        fetch_counted(&cache, &other_league, &calls, StatusCode::OK).await.unwrap();
+
+        assert_eq!(cache.invalidate(Some("messageBoard"), Some("12345")), 1);
+        assert_eq!(cache.invalidate(None, Some("12345")), 2);
+        assert_eq!(cache.stats().entries, 1);
+    }
+
+    fn api(mock: &MockMflServer, cache: &Arc<ExportCache>, store: Option<&Arc<OfflineStore>>) -> MflApi {
+        let config = MflClientConfig {
+            base_url: mock.base_url().to_string(),
+            max_retries: 0,
+            rate_limiter: Arc::new(RateLimiter::default()),
+            circuit_breaker: Arc::new(CircuitBreaker::default()),
+            export_cache: cache.clone(),
+            offline_store: store.cloned(),
+            ..MflClientConfig::default()
+        };
+        MflApi::with_config("2025".to_string(), &config).unwrap()
+    }
+
+    #[actix_web::test]
+    async fn one_users_exports_are_never_served_to_another() {
+        let mock = MockMflServer::start().unwrap();
+        let cache = Arc::new(ExportCache::new(CachePolicy::default()));
+        let owner = api(&mock, &cache, None);
+        owner.login(MOCK_USERNAME, MOCK_PASSWORD).await.unwrap();
+        let visitor = api(&mock, &cache, None);
+
+        owner.get_message_board(MOCK_LEAGUE_ID).await.unwrap();
+        let board = visitor.get_message_board(MOCK_LEAGUE_ID).await.unwrap();
+        assert_eq!(board.freshness.source, DataSource::Live);
+        assert_eq!(owner.get_message_board(MOCK_LEAGUE_ID).await.unwrap().freshness.source, DataSource::Cache);
+        assert_eq!(mock.export_count("messageBoard"), 2);
+        mock.stop().await;
+    }
+
+    #[actix_web::test]
+    async fn a_renewed_cookie_keeps_the_users_cached_exports() {
+        let mock = MockMflServer::start().unwrap();
+        let cache = Arc::new(ExportCache::new(CachePolicy::default()));
+        let mfl = api(&mock, &cache, None);
+        mfl.login(MOCK_USERNAME, MOCK_PASSWORD).await.unwrap();
+
+        mfl.get_message_board(MOCK_LEAGUE_ID).await.unwrap();
+        mfl.set_mfl_user_id_cookie(Some("renewed-cookie".to_string()));
+        assert_eq!(mfl.get_message_board(MOCK_LEAGUE_ID).await.unwrap().freshness.source, DataSource::Cache);
+        assert_eq!(mock.export_count("messageBoard"), 1);
+        mock.stop().await;
+    }
+
+    #[actix_web::test]
+    async fn error_documents_are_neither_cached_nor_kept_offline() {
+        let mock = MockMflServer::start().unwrap();
+        mock.set_fixture("messageBoard", r#"{"error": {"$t": "Invalid league ID"}}"#);
+        let cache = Arc::new(ExportCache::new(CachePolicy::default()));
+        let store = Arc::new(OfflineStore::open(":memory:").unwrap());
+        let mfl = api(&mock, &cache, Some(&store));
+
+        for _ in 0..2 {
+            match mfl.get_message_board(MOCK_LEAGUE_ID).await {
+                Err(MflError::ApiStatusError { status, body }) => {
+                    assert_eq!(status, StatusCode::OK);
+                    assert!(body.contains("Invalid league ID"));
+                }
+                other => panic!("expected MFL's error, got {:?}", other),
+            }
+        }
+        assert_eq!(mock.export_count("messageBoard"), 2);
+        assert_eq!(cache.stats().entries, 0);
+        assert_eq!(store.count().unwrap(), 0);
+
+        // Nor are XML ones
+        mock.set_fixture("messageBoard", "<error>Invalid league ID</error>");
+        let error = mfl.get_message_board(MOCK_LEAGUE_ID).await.unwrap_err();
+        assert!(matches!(error, MflError::ApiStatusError { .. }), "{:?}", error);
+        assert_eq!((cache.stats().entries, store.count().unwrap()), (0, 0));
+        mock.stop().await;
+    }
+}
@@ -0,0 +1,132 @@
+// tests/exports_tests.rs
+
+#[cfg(test)]
+mod exports_tests {
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use mfl_manager_lib::{
+        circuit_breaker::CircuitBreaker,
+        export_cache::{CachePolicy, DataSource, ExportCache},
+        mfl_api::{FreeAgentsExport, MflApi, MflClientConfig, MflError, MflExport, PlayersExport},
+        rate_limit::RateLimiter,
+    };
+    use mfl_mock::{MockMflServer, MOCK_LEAGUE_ID};
+    use serde::Deserialize;
+
+    // An export the library doesn't know about, declared the way a new one would be
+    #[derive(Deserialize, Debug)]
+    struct ProjectedScoresResponse {
+        #[serde(rename = "projectedScores")]
+        projected_scores: ProjectedScores,
+    }
+
+    #[derive(Deserialize, Debug)]
+    struct ProjectedScores {
+        week: String,
+        #[serde(rename = "playerScore")]
+        player_score: Vec<PlayerScore>,
+    }
+
+    #[derive(Deserialize, Debug)]
+    struct PlayerScore {
+        id: String,
+        score: String,
+    }
+
+    struct ProjectedScoresExport;
+
+    impl MflExport for ProjectedScoresExport {
+        const TYPE: &'static str = "projectedScores";
+        type Params = u8;
+        type Response = ProjectedScoresResponse;
+
+        fn query(week: &u8) -> Vec<(&'static str, String)> {
+            vec![("W", week.to_string())]
+        }
+    }
+
+    const PROJECTED_SCORES: &str = r#"{
+        "version": "1.0",
+        "projectedScores": { "week": "3", "playerScore": [
+            { "id": "13604", "score": "17.5" },
+            { "id": "15247", "score": "9.25" }
+        ]},
+        "encoding": "utf-8"
+    }"#;
+
+    fn api(mock: &MockMflServer, policy: CachePolicy) -> MflApi {
+        let config = MflClientConfig {
+            base_url: mock.base_url().to_string(),
+            max_retries: 0,
+            rate_limiter: Arc::new(RateLimiter::default()),
+            circuit_breaker: Arc::new(CircuitBreaker::default()),
+            export_cache: Arc::new(ExportCache::new(policy)),
+            ..MflClientConfig::default()
+        };
+        MflApi::with_config("2025".to_string(), &config).unwrap()
+    }
+
+    #[actix_web::test]
+    async fn declared_exports_are_sent_decoded_and_cached() {
+        let mock = MockMflServer::start().unwrap();
+        mock.set_fixture("projectedScores", PROJECTED_SCORES);
+        let api = api(&mock, CachePolicy::empty().with_ttl("projectedScores", Duration::from_secs(60)));
+
+        let fetched = api.export::<ProjectedScoresExport>(MOCK_LEAGUE_ID, &3).await.unwrap();
+        assert_eq!(fetched.freshness.source, DataSource::Live);
+        assert_eq!(fetched.data.projected_scores.week, "3");
+        let scores: Vec<(&str, &str)> = fetched.data.projected_scores.player_score.iter()
+            .map(|score| (score.id.as_str(), score.score.as_str()))
+            .collect();
+        assert_eq!(scores, [("13604", "17.5"), ("15247", "9.25")]);
+
+        let request = mock.requests().into_iter().rfind(|request| request.path.ends_with("/export")).unwrap();
+        assert_eq!(request.param("L"), Some(MOCK_LEAGUE_ID));
+        assert_eq!(request.param("W"), Some("3"));
+        assert_eq!(request.param("JSON"), Some("1"));
+
+        // Same export and parameters: the cache answers
+        let cached = api.export::<ProjectedScoresExport>(MOCK_LEAGUE_ID, &3).await.unwrap();
+        assert_eq!(cached.freshness.source, DataSource::Cache);
+        assert_eq!(mock.export_count("projectedScores"), 1);
+        mock.stop().await;
+    }
+
+    #[actix_web::test]
+    async fn failed_exports_report_status_and_decoding_errors() {
+        let mock = MockMflServer::start().unwrap();
+        let api = api(&mock, CachePolicy::empty());
+
+        // The mock answers unknown exports with an error object instead of the export
+        match api.export::<ProjectedScoresExport>(MOCK_LEAGUE_ID, &3).await {
+            Err(MflError::ApiStatusError { body, .. }) => assert!(body.contains("Invalid export type"), "{}", body),
+            other => panic!("expected MFL's error, got {:?}", other.map(|fetched| fetched.data)),
+        }
+
+        mock.set_fixture("projectedScores", r#"{"projectedScores": {"playerScore": "#);
+        match api.export::<ProjectedScoresExport>(MOCK_LEAGUE_ID, &3).await {
+            Err(MflError::JsonParse(_)) => {}
+            other => panic!("expected a JSON error, got {:?}", other.map(|fetched| fetched.data)),
+        }
+
+        mock.set_down(true);
+        match api.export::<PlayersExport>(MOCK_LEAGUE_ID, "13604").await {
+            Err(MflError::ApiStatusError { status, .. }) => assert_eq!(status.as_u16(), 503),
+            other => panic!("expected a status error, got {:?}", other.map(|fetched| fetched.data)),
+        }
+        mock.stop().await;
+    }
+
+    #[actix_web::test]
+    async fn parameters_are_escaped_so_they_cannot_add_their_own() {
+        let mock = MockMflServer::start().unwrap();
+        let api = api(&mock, CachePolicy::empty());
+
+        api.export::<FreeAgentsExport>(MOCK_LEAGUE_ID, &Some("RB+WR&L=1".to_string())).await.unwrap();
+        let request = mock.requests().into_iter().rfind(|request| request.path.ends_with("/export")).unwrap();
+        assert_eq!(request.param("POSITION"), Some("RB+WR&L=1"));
+        assert_eq!(request.param("L"), Some(MOCK_LEAGUE_ID));
+        mock.stop().await;
+    }
+}
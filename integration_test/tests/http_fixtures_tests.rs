@@ -0,0 +1,57 @@
+// tests/http_fixtures_tests.rs
+
+#[cfg(test)]
+mod http_fixtures_tests {
+    use std::sync::Arc;
+
+    use mfl_manager_lib::{
+        circuit_breaker::CircuitBreaker,
+        export_cache::{CachePolicy, ExportCache},
+        http_fixtures::HttpFixtures,
+        mfl_api::{MflApi, MflClientConfig, MflError},
+        rate_limit::RateLimiter,
+    };
+    use mfl_mock::{MockMflServer, MOCK_COOKIE, MOCK_LEAGUE_ID, MOCK_PASSWORD, MOCK_USERNAME};
+
+    fn config(base_url: &str, fixtures: HttpFixtures) -> MflClientConfig {
+        MflClientConfig {
+            base_url: base_url.to_string(),
+            max_retries: 0,
+            rate_limiter: Arc::new(RateLimiter::default()),
+            circuit_breaker: Arc::new(CircuitBreaker::default()),
+            export_cache: Arc::new(ExportCache::new(CachePolicy::empty())),
+            fixtures: Some(Arc::new(fixtures)),
+            ..MflClientConfig::default()
+        }
+    }
+
+    #[actix_web::test]
+    async fn recorded_traffic_is_scrubbed_and_replays_without_mfl() {
+        let dir = std::env::temp_dir().join(format!("mfl_fixtures_{}", uuid::Uuid::new_v4()));
+        let mock = MockMflServer::start().unwrap();
+
+        let api = MflApi::with_config("2025".to_string(), &config(mock.base_url(), HttpFixtures::record(&dir).unwrap())).unwrap();
+        api.login(MOCK_USERNAME, MOCK_PASSWORD).await.unwrap();
+        let recorded = api.get_message_board(MOCK_LEAGUE_ID).await.unwrap();
+        mock.stop().await;
+
+        for entry in std::fs::read_dir(&dir).unwrap() {
+            let contents = std::fs::read_to_string(entry.unwrap().path()).unwrap();
+            assert!(!contents.contains(MOCK_PASSWORD), "password leaked into {}", contents);
+            assert!(!contents.contains(MOCK_USERNAME), "username leaked into {}", contents);
+            assert!(!contents.contains(MOCK_COOKIE), "cookie leaked into {}", contents);
+        }
+
+        // The mock is gone: everything below comes from the fixture files
+        let api = MflApi::with_config("2025".to_string(), &config("http://127.0.0.1:9", HttpFixtures::replay(&dir).unwrap())).unwrap();
+        api.login("anyone", "anything").await.unwrap();
+        let replayed = api.get_message_board(MOCK_LEAGUE_ID).await.unwrap();
+        assert_eq!(replayed.data.thread.len(), recorded.data.thread.len());
+
+        match api.get_message_board_thread(MOCK_LEAGUE_ID, "5432100").await {
+            Err(MflError::FixtureNotFound(request)) => assert!(request.contains("messageBoardThread"), "{}", request),
+            other => panic!("expected an unmatched fixture error, got {:?}", other.map(|thread| thread.data)),
+        }
+        std::fs::remove_dir_all(&dir).ok();
+    }
+}
@@ -0,0 +1,95 @@
+// tests/league_host_tests.rs
+
+#[cfg(test)]
+mod league_host_tests {
+    use std::sync::Arc;
+
+    use mfl_manager_lib::{
+        circuit_breaker::CircuitBreaker,
+        export_cache::{CachePolicy, ExportCache},
+        mfl_api::{MflApi, MflClientConfig, MflError},
+        rate_limit::RateLimiter,
+    };
+    use mfl_mock::{MockMflServer, MOCK_LEAGUE_ID, MOCK_PASSWORD, MOCK_USERNAME};
+
+    // `api` is the api host; its league export sends the league to `league_host`
+    async fn logged_in_api(api: &MockMflServer, league_host: &MockMflServer) -> MflApi {
+        api.set_fixture("league", &format!(
+            r#"{{"version": "1.0", "league": {{"id": "{{{{league_id}}}}", "name": "Mock Dynasty League", "baseURL": "{}"}}, "encoding": "utf-8"}}"#,
+            league_host.base_url()
+        ));
+        let config = MflClientConfig {
+            base_url: api.base_url().to_string(),
+            max_retries: 0,
+            rate_limiter: Arc::new(RateLimiter::default()),
+            circuit_breaker: Arc::new(CircuitBreaker::default()),
+            export_cache: Arc::new(ExportCache::new(CachePolicy::empty())),
+            ..MflClientConfig::default()
+        };
+        let mfl = MflApi::with_config("2025".to_string(), &config).unwrap();
+        mfl.login(MOCK_USERNAME, MOCK_PASSWORD).await.unwrap();
+        mfl
+    }
+
+    fn imports(mock: &MockMflServer) -> usize {
+        mock.requests().iter().filter(|request| request.path.ends_with("/import")).count()
+    }
+
+    #[actix_web::test]
+    async fn league_requests_follow_the_league_to_its_new_host() {
+        let (api, old_host, new_host) = (MockMflServer::start().unwrap(), MockMflServer::start().unwrap(), MockMflServer::start().unwrap());
+        let mfl = logged_in_api(&api, &old_host).await;
+        old_host.set_redirect(Some(new_host.base_url()));
+
+        // The redirected reply is used as is, and the new host remembered
+        let board = mfl.get_message_board(MOCK_LEAGUE_ID).await.unwrap();
+        assert_eq!(board.data.thread.len(), 2);
+        assert_eq!(mfl.cached_league_host(MOCK_LEAGUE_ID).as_deref(), Some(new_host.base_url()));
+        assert_eq!((old_host.export_count("messageBoard"), new_host.export_count("messageBoard")), (1, 1));
+
+        mfl.get_message_board(MOCK_LEAGUE_ID).await.unwrap();
+        assert_eq!((old_host.export_count("messageBoard"), new_host.export_count("messageBoard")), (1, 2));
+        assert_eq!(api.export_count("messageBoard"), 0);
+        assert_eq!(api.export_count("league"), 1);
+
+        // Imports go straight to the new host too
+        mfl.post_message(MOCK_LEAGUE_ID, None, "Waivers", "Run tonight", None).await.unwrap();
+        assert_eq!((imports(&old_host), imports(&new_host), imports(&api)), (0, 1, 0));
+
+        for mock in [api, old_host, new_host] {
+            mock.stop().await;
+        }
+    }
+
+    #[actix_web::test]
+    async fn exports_fall_back_to_the_api_host_when_the_league_host_fails() {
+        let (api, league_host) = (MockMflServer::start().unwrap(), MockMflServer::start().unwrap());
+        let mfl = logged_in_api(&api, &league_host).await;
+        league_host.set_down(true);
+
+        let board = mfl.get_message_board(MOCK_LEAGUE_ID).await.unwrap();
+        assert_eq!(board.data.thread.len(), 2);
+        assert_eq!((league_host.export_count("messageBoard"), api.export_count("messageBoard")), (1, 1));
+        // The failed host is forgotten and looked up again next time
+        assert_eq!(mfl.cached_league_host(MOCK_LEAGUE_ID), None);
+
+        api.stop().await;
+        league_host.stop().await;
+    }
+
+    #[actix_web::test]
+    async fn failed_imports_are_never_sent_again() {
+        let (api, league_host) = (MockMflServer::start().unwrap(), MockMflServer::start().unwrap());
+        let mfl = logged_in_api(&api, &league_host).await;
+        league_host.set_down(true);
+
+        match mfl.post_message(MOCK_LEAGUE_ID, Some("5432100"), "Re: Trade", "Accepted", None).await {
+            Err(MflError::ApiStatusError { status, .. }) => assert_eq!(status.as_u16(), 503),
+            other => panic!("expected the league host's 503, got {:?}", other),
+        }
+        assert_eq!((imports(&league_host), imports(&api)), (1, 0), "the post may reach MFL at most once");
+
+        api.stop().await;
+        league_host.stop().await;
+    }
+}
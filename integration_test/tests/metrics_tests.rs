@@ -0,0 +1,109 @@
+// tests/metrics_tests.rs
+
+#[cfg(test)]
+mod metrics_tests {
+    use std::sync::Arc;
+
+    use actix_web::{
+        http::header::AUTHORIZATION,
+        http::StatusCode,
+        test::{self, TestRequest},
+        web, App,
+    };
+    use mfl_manager_lib::{
+        app_state::{InMemorySessionStore, SessionStore},
+        circuit_breaker::CircuitBreaker,
+        export_cache::{CachePolicy, ExportCache},
+        handler_middleware::{AuthMiddleware, RequestMetrics},
+        handler_models::{LoginRequest, LoginResponse},
+        handlers,
+        metrics::Metrics,
+        mfl_api::MflClientConfig,
+        rate_limit::RateLimiter,
+    };
+    use mfl_mock::{MockMflServer, MOCK_PASSWORD, MOCK_USERNAME};
+
+    fn login_request(password: &str) -> LoginRequest {
+        LoginRequest {
+            username: MOCK_USERNAME.to_string(),
+            password: password.to_string(),
+            league_id: None,
+            year: "2025".to_string(),
+            remember_credentials: false,
+        }
+    }
+
+    // The value of the sample with exactly this name and label set
+    fn sample(metrics: &str, series: &str) -> Option<f64> {
+        metrics.lines()
+            .find_map(|line| line.strip_prefix(series).and_then(|rest| rest.strip_prefix(' ')))
+            .and_then(|value| value.trim().parse().ok())
+    }
+
+    #[actix_web::test]
+    async fn inbound_outbound_cache_session_and_error_metrics_are_exposed() {
+        let mock = MockMflServer::start().unwrap();
+        let metrics = Arc::new(Metrics::new());
+        let config = MflClientConfig {
+            base_url: mock.base_url().to_string(),
+            max_retries: 0,
+            rate_limiter: Arc::new(RateLimiter::default()),
+            circuit_breaker: Arc::new(CircuitBreaker::default()),
+            export_cache: Arc::new(ExportCache::new(CachePolicy::default())),
+            metrics: metrics.clone(),
+            ..MflClientConfig::default()
+        };
+        let store = Arc::new(InMemorySessionStore::new());
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(store.clone() as SessionStore))
+                .app_data(web::Data::new(config))
+                .wrap(RequestMetrics::new(metrics))
+                .service(handlers::login_handler)
+                .service(handlers::metrics_handler)
+                .service(
+                    web::scope("")
+                        .wrap(AuthMiddleware)
+                        .service(handlers::get_free_agents_handler)
+                )
+        ).await;
+
+        let resp = test::call_service(&app, TestRequest::post().uri("/login").set_json(login_request("wrong")).to_request()).await;
+        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+        let resp = test::call_service(&app, TestRequest::post().uri("/login").set_json(login_request(MOCK_PASSWORD)).to_request()).await;
+        let login: LoginResponse = test::read_body_json(resp).await;
+
+        // The second read is answered from the export cache
+        for _ in 0..2 {
+            let resp = test::call_service(&app, TestRequest::get()
+                .uri("/free-agents/WR")
+                .insert_header((AUTHORIZATION, format!("Bearer {}", login.token)))
+                .to_request()).await;
+            assert_eq!(resp.status(), StatusCode::OK);
+        }
+        let resp = test::call_service(&app, TestRequest::get().uri("/free-agents/RB").to_request()).await;
+        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+
+        let resp = test::call_service(&app, TestRequest::get().uri("/metrics").to_request()).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        let body = String::from_utf8(test::read_body(resp).await.to_vec()).unwrap();
+
+        let route = r#"method="GET",route="/free-agents/{position}""#;
+        assert_eq!(sample(&body, &format!(r#"http_requests_total{{{},status="200"}}"#, route)), Some(2.0), "{}", body);
+        assert_eq!(sample(&body, &format!(r#"http_requests_total{{{},status="401"}}"#, route)), Some(1.0));
+        assert_eq!(sample(&body, &format!(r#"http_request_duration_seconds_count{{{},status="200"}}"#, route)), Some(2.0));
+
+        assert_eq!(sample(&body, r#"mfl_requests_total{export="login",status="200"}"#), Some(2.0));
+        assert_eq!(sample(&body, r#"mfl_requests_total{export="freeAgents",status="200"}"#), Some(1.0));
+        assert_eq!(sample(&body, r#"mfl_request_duration_seconds_count{export="freeAgents"}"#), Some(1.0));
+
+        // MFL answers a bad password with <error>Invalid Password</error>
+        assert_eq!(sample(&body, r#"mfl_errors_total{variant="LoginFailed"}"#), Some(1.0));
+        assert!(sample(&body, "mfl_cache_hits_total").unwrap() >= 1.0);
+        assert!(sample(&body, "mfl_cache_hit_ratio").unwrap() > 0.0);
+        assert_eq!(sample(&body, "active_sessions"), Some(1.0));
+
+        store.clear().unwrap();
+        mock.stop().await;
+    }
+}
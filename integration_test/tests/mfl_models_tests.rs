@@ -0,0 +1,112 @@
+// tests/mfl_models_tests.rs
+
+#[cfg(test)]
+mod mfl_models_tests {
+    use std::sync::Arc;
+
+    use mfl_manager_lib::{
+        circuit_breaker::CircuitBreaker,
+        export_cache::{CachePolicy, ExportCache},
+        mfl_api::{MflApi, MflClientConfig},
+        rate_limit::RateLimiter,
+    };
+    use mfl_mock::{MockMflServer, MOCK_LEAGUE_ID, MOCK_PASSWORD, MOCK_USERNAME};
+
+    // No cache, so every call sees the fixture just set
+    async fn logged_in_api(mock: &MockMflServer) -> MflApi {
+        let config = MflClientConfig {
+            base_url: mock.base_url().to_string(),
+            max_retries: 0,
+            rate_limiter: Arc::new(RateLimiter::default()),
+            circuit_breaker: Arc::new(CircuitBreaker::default()),
+            export_cache: Arc::new(ExportCache::new(CachePolicy::empty())),
+            ..MflClientConfig::default()
+        };
+        let api = MflApi::with_config("2025".to_string(), &config).unwrap();
+        api.login(MOCK_USERNAME, MOCK_PASSWORD).await.unwrap();
+        api
+    }
+
+    // Zero, one and many results, as MFL sends them: no key, a bare object, an array
+    const FREE_AGENTS: [(&str, &[&str]); 5] = [
+        (r#"{"version": "1.0", "freeAgents": {}, "encoding": "utf-8"}"#, &[]),
+        (r#"{"version": "1.0", "freeAgents": {"leagueUnit": {"unit": "LEAGUE"}}, "encoding": "utf-8"}"#, &[]),
+        (r#"{"version": "1.0", "freeAgents": {"leagueUnit": {"unit": "LEAGUE", "player": {"id": "13116", "salary": "1.00", "contractStatus": ""}}}, "encoding": "utf-8"}"#, &["13116"]),
+        (r#"{"version": "1.0", "freeAgents": {"leagueUnit": {"unit": "LEAGUE", "player": [
+            {"id": "13116", "salary": "1.00", "contractStatus": ""}, {"id": "14836", "salary": "0.50", "contractStatus": ""}]}}, "encoding": "utf-8"}"#, &["13116", "14836"]),
+        // Leagues split into units list each unit's free agents
+        (r#"{"version": "1.0", "freeAgents": {"leagueUnit": [
+            {"unit": "DIVISION00", "player": {"id": "13116", "salary": "1.00", "contractStatus": ""}},
+            {"unit": "DIVISION01", "player": [{"id": "14836", "salary": "1.00", "contractStatus": ""}, {"id": "15281", "salary": "1.00", "contractStatus": ""}]}]},
+            "encoding": "utf-8"}"#, &["13116", "14836", "15281"]),
+    ];
+
+    const PLAYERS: [(&str, &[&str]); 3] = [
+        (r#"{"version": "1.0", "players": {"timestamp": "1727740800"}, "encoding": "utf-8"}"#, &[]),
+        (r#"{"version": "1.0", "players": {"timestamp": "1727740800", "player": {"position": "WR", "name": "Receiver, Mock", "id": "13116", "team": "BUF"}}, "encoding": "utf-8"}"#, &["13116"]),
+        (r#"{"version": "1.0", "players": {"timestamp": "1727740800", "player": [
+            {"position": "WR", "name": "Receiver, Mock", "id": "13116", "team": "BUF"}, {"name": "Wideout, Sample", "id": "14836"}]}, "encoding": "utf-8"}"#, &["13116", "14836"]),
+    ];
+
+    const MY_LEAGUES: [(&str, &[&str]); 3] = [
+        (r#"{"version": "1.0", "leagues": {}, "encoding": "utf-8"}"#, &[]),
+        (r#"{"version": "1.0", "leagues": {"league": {"league_id": "12345", "name": "Mock Dynasty League"}}, "encoding": "utf-8"}"#, &["12345"]),
+        (r#"{"version": "1.0", "leagues": {"league": [
+            {"league_id": "12345", "name": "Mock Dynasty League"}, {"league_id": "67890", "name": "Mock Redraft League"}]}, "encoding": "utf-8"}"#, &["12345", "67890"]),
+    ];
+
+    const MESSAGE_BOARD: [(&str, &[&str]); 3] = [
+        (r#"{"version": "1.0", "messageBoard": {}, "encoding": "utf-8"}"#, &[]),
+        (r#"{"version": "1.0", "messageBoard": {"thread": {"id": "5432100", "subject": "Trade deadline reminder"}}, "encoding": "utf-8"}"#, &["5432100"]),
+        (r#"{"version": "1.0", "messageBoard": {"thread": [
+            {"id": "5432100", "subject": "Trade deadline reminder"}, {"id": "5432101", "subject": "Week 4 recap"}]}, "encoding": "utf-8"}"#, &["5432100", "5432101"]),
+    ];
+
+    const MESSAGE_BOARD_THREAD: [(&str, &[&str]); 3] = [
+        (r#"{"version": "1.0", "messageBoardThread": {"id": "5432100", "subject": "Trade deadline reminder"}, "encoding": "utf-8"}"#, &[]),
+        (r#"{"version": "1.0", "messageBoardThread": {"id": "5432100", "post": {"id": "1", "franchise": "0001", "$t": "Deadline is Thursday night."}}, "encoding": "utf-8"}"#, &["1"]),
+        (r#"{"version": "1.0", "messageBoardThread": {"id": "5432100", "post": [
+            {"id": "1", "franchise": "0001", "$t": "Deadline is Thursday night."}, {"id": "2", "franchise": "0002", "$t": "Thanks."}]}, "encoding": "utf-8"}"#, &["1", "2"]),
+    ];
+
+    #[actix_web::test]
+    async fn league_exports_parse_zero_one_and_many_results() {
+        let mock = MockMflServer::start().unwrap();
+        let api = logged_in_api(&mock).await;
+
+        for (body, expected) in FREE_AGENTS {
+            mock.set_fixture("freeAgents", body);
+            let players = api.get_free_agents(MOCK_LEAGUE_ID, None).await.unwrap().data;
+            assert_eq!(players.iter().map(|player| player.id.as_str()).collect::<Vec<_>>(), expected, "{}", body);
+        }
+        for (body, expected) in PLAYERS {
+            mock.set_fixture("players", body);
+            let players = api.get_players(MOCK_LEAGUE_ID, "13116,14836").await.unwrap().data;
+            assert_eq!(players.player.iter().map(|player| player.id.as_str()).collect::<Vec<_>>(), expected, "{}", body);
+        }
+        for (body, expected) in MESSAGE_BOARD {
+            mock.set_fixture("messageBoard", body);
+            let board = api.get_message_board(MOCK_LEAGUE_ID).await.unwrap().data;
+            assert_eq!(board.thread.iter().map(|thread| thread.id.as_str()).collect::<Vec<_>>(), expected, "{}", body);
+        }
+        for (body, expected) in MESSAGE_BOARD_THREAD {
+            mock.set_fixture("messageBoardThread", body);
+            let thread = api.get_message_board_thread(MOCK_LEAGUE_ID, "5432100").await.unwrap().data;
+            assert_eq!(thread.post.iter().map(|post| post.id.as_str()).collect::<Vec<_>>(), expected, "{}", body);
+        }
+        mock.stop().await;
+    }
+
+    #[actix_web::test]
+    async fn my_leagues_parses_zero_one_and_many_results() {
+        let mock = MockMflServer::start().unwrap();
+        let api = logged_in_api(&mock).await;
+
+        for (body, expected) in MY_LEAGUES {
+            mock.set_fixture("myleagues", body);
+            let leagues = api.get_my_leagues().await.unwrap();
+            assert_eq!(leagues.iter().map(|league| league.league_id.as_str()).collect::<Vec<_>>(), expected, "{}", body);
+        }
+        mock.stop().await;
+    }
+}
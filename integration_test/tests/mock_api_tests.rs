@@ -0,0 +1,304 @@
+// tests/mock_api_tests.rs
+// End-to-end tests against the mfl_mock server; no network access or MFL account needed.
+
+#[cfg(test)]
+mod mock_api_tests {
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use actix_web::{
+        http::header::AUTHORIZATION,
+        http::StatusCode,
+        test::{self, TestRequest},
+        web, App,
+    };
+    use mfl_manager_lib::{
+        app_state::{InMemorySessionStore, SessionBackend, SessionStore},
+        circuit_breaker::CircuitBreaker,
+        errors::{ErrorCode, ErrorResponse},
+        export_cache::{CachePolicy, DataSource, ExportCache},
+        handler_middleware::AuthMiddleware,
+        handler_models::{FreeAgentsResponse, LoginRequest, LoginResponse, MessageBoardResponse, MessageThreadResponse},
+        handlers,
+        mfl_api::MflClientConfig,
+        offline_store::OfflineStore,
+        rate_limit::RateLimiter,
+    };
+    use mfl_mock::{MockMflServer, MOCK_LEAGUE_ID, MOCK_PASSWORD, MOCK_USERNAME};
+
+    // Each test gets its own limiter, breaker and cache so tests can't see each other's state
+    fn client_config(mock: &MockMflServer) -> MflClientConfig {
+        MflClientConfig {
+            base_url: mock.base_url().to_string(),
+            max_retries: 0,
+            rate_limiter: Arc::new(RateLimiter::default()),
+            circuit_breaker: Arc::new(CircuitBreaker::default()),
+            export_cache: Arc::new(ExportCache::new(CachePolicy::default())),
+            ..MflClientConfig::default()
+        }
+    }
+
+    fn login_request(password: &str) -> LoginRequest {
+        LoginRequest {
+            username: MOCK_USERNAME.to_string(),
+            password: password.to_string(),
+            league_id: None,
+            year: "2025".to_string(),
+            remember_credentials: false,
+        }
+    }
+
+    macro_rules! init_app {
+        ($config:expr, $store:expr) => {
+            test::init_service(
+                App::new()
+                    .app_data(web::Data::new($store.clone() as SessionStore))
+                    .app_data(web::Data::new($config))
+                    .wrap(AuthMiddleware)
+                    .service(handlers::login_handler)
+                    .service(handlers::get_free_agents_handler)
+                    .service(handlers::get_message_board_handler)
+                    .service(handlers::get_message_thread_handler)
+                    .service(handlers::post_message_thread_handler)
+                    .service(handlers::reply_message_handler)
+            ).await
+        };
+    }
+
+    macro_rules! login {
+        ($app:expr) => {{
+            let resp = test::call_service(&$app, TestRequest::post()
+                .uri("/login")
+                .set_json(login_request(MOCK_PASSWORD))
+                .to_request()).await;
+            assert_eq!(resp.status(), StatusCode::OK);
+            let login: LoginResponse = test::read_body_json(resp).await;
+            login
+        }};
+    }
+
+    #[actix_web::test]
+    async fn login_and_read_free_agents_from_the_mock() {
+        let mock = MockMflServer::start().unwrap();
+        let store = Arc::new(InMemorySessionStore::new());
+        let app = init_app!(client_config(&mock), store);
+
+        let login = login!(app);
+        assert_eq!(login.league_id, MOCK_LEAGUE_ID);
+        assert_eq!(login.franchise_id.as_deref(), Some("0001"));
+
+        let resp = test::call_service(&app, TestRequest::get()
+            .uri("/free-agents/WR")
+            .insert_header((AUTHORIZATION, format!("Bearer {}", login.token)))
+            .to_request()).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(resp.headers().get("X-Data-Source").unwrap(), "live");
+        let free_agents: FreeAgentsResponse = test::read_body_json(resp).await;
+        assert_eq!(free_agents.freshness.source, DataSource::Live);
+        assert_eq!(free_agents.players.len(), 3);
+        assert!(free_agents.players.iter().all(|player| player.position == "WR"));
+
+        // The session cookie from login went along with every later request
+        let requests = mock.requests();
+        assert!(requests.iter().skip(1).all(|request| request.cookie.as_deref() == Some(mfl_mock::MOCK_COOKIE)));
+
+        store.clear().unwrap();
+        mock.stop().await;
+    }
+
+    #[actix_web::test]
+    async fn login_selects_the_requested_league_only_if_the_user_has_it() {
+        let mock = MockMflServer::start().unwrap();
+        let store = Arc::new(InMemorySessionStore::new());
+        let app = init_app!(client_config(&mock), store);
+
+        // The mock user's second league, with its own franchise
+        let resp = test::call_service(&app, TestRequest::post()
+            .uri("/login")
+            .set_json(LoginRequest { league_id: Some("67890".to_string()), ..login_request(MOCK_PASSWORD) })
+            .to_request()).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        let login: LoginResponse = test::read_body_json(resp).await;
+        assert_eq!(login.league_id, "67890");
+        assert_eq!(login.franchise_id.as_deref(), Some("0007"));
+        assert_eq!(store.count().unwrap(), 1);
+
+        let resp = test::call_service(&app, TestRequest::post()
+            .uri("/login")
+            .set_json(LoginRequest { league_id: Some("99999".to_string()), ..login_request(MOCK_PASSWORD) })
+            .to_request()).await;
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+        let error: ErrorResponse = test::read_body_json(resp).await;
+        assert_eq!(error.code, ErrorCode::NotFound);
+        assert_eq!(store.count().unwrap(), 1, "no session for a league the user isn't in");
+
+        store.clear().unwrap();
+        mock.stop().await;
+    }
+
+    #[actix_web::test]
+    async fn wrong_password_is_rejected() {
+        let mock = MockMflServer::start().unwrap();
+        let store = Arc::new(InMemorySessionStore::new());
+        let app = init_app!(client_config(&mock), store);
+
+        let resp = test::call_service(&app, TestRequest::post()
+            .uri("/login")
+            .set_json(login_request("not-the-password"))
+            .to_request()).await;
+        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+        let error: ErrorResponse = test::read_body_json(resp).await;
+        assert_eq!(error.code, ErrorCode::LoginFailed);
+        assert_eq!(error.upstream.unwrap().details, "Invalid Password");
+        mock.stop().await;
+    }
+
+    #[actix_web::test]
+    async fn message_board_reads_are_cached_until_a_reply_is_posted() {
+        let mock = MockMflServer::start().unwrap();
+        let store = Arc::new(InMemorySessionStore::new());
+        let app = init_app!(client_config(&mock), store);
+        let login = login!(app);
+        let bearer = format!("Bearer {}", login.token);
+
+        for _ in 0..2 {
+            let resp = test::call_service(&app, TestRequest::get()
+                .uri("/messages")
+                .insert_header((AUTHORIZATION, bearer.clone()))
+                .to_request()).await;
+            let board: MessageBoardResponse = test::read_body_json(resp).await;
+            assert_eq!(board.threads.len(), 2);
+        }
+        assert_eq!(mock.export_count("messageBoard"), 1);
+
+        let resp = test::call_service(&app, TestRequest::get()
+            .uri("/messages/5432100")
+            .insert_header((AUTHORIZATION, bearer.clone()))
+            .to_request()).await;
+        let thread: MessageThreadResponse = test::read_body_json(resp).await;
+        assert_eq!(thread.posts[0].body.as_deref(), Some("Deadline is Thursday night."));
+
+        let resp = test::call_service(&app, TestRequest::post()
+            .uri("/messages/5432100")
+            .insert_header((AUTHORIZATION, bearer.clone()))
+            .set_json(serde_json::json!({ "body": "Counter offer sent" }))
+            .to_request()).await;
+        assert_eq!(resp.status(), StatusCode::CREATED);
+        let import = mock.requests().into_iter().find(|request| request.path.ends_with("/import")).unwrap();
+        assert_eq!(import.param("THREAD"), Some("5432100"));
+        assert_eq!(import.param("BODY"), Some("Counter offer sent"));
+
+        // Posting invalidated the cached board
+        test::call_service(&app, TestRequest::get()
+            .uri("/messages")
+            .insert_header((AUTHORIZATION, bearer))
+            .to_request()).await;
+        assert_eq!(mock.export_count("messageBoard"), 2);
+
+        store.clear().unwrap();
+        mock.stop().await;
+    }
+
+    #[actix_web::test]
+    async fn new_threads_are_posted_and_blank_messages_never_reach_mfl() {
+        let mock = MockMflServer::start().unwrap();
+        let store = Arc::new(InMemorySessionStore::new());
+        let app = init_app!(client_config(&mock), store);
+        let login = login!(app);
+        let bearer = format!("Bearer {}", login.token);
+        let imports = || mock.requests().into_iter().filter(|request| request.path.ends_with("/import")).collect::<Vec<_>>();
+
+        let blank = [
+            ("/messages", serde_json::json!({ "subject": "  ", "body": "Run tonight" })),
+            ("/messages", serde_json::json!({ "subject": "Waivers", "body": "" })),
+            ("/messages/5432100", serde_json::json!({ "body": " \n " })),
+        ];
+        for (uri, message) in blank {
+            let resp = test::call_service(&app, TestRequest::post()
+                .uri(uri)
+                .insert_header((AUTHORIZATION, bearer.clone()))
+                .set_json(&message)
+                .to_request()).await;
+            assert_eq!(resp.status(), StatusCode::BAD_REQUEST, "{}", message);
+        }
+        assert!(imports().is_empty());
+
+        let resp = test::call_service(&app, TestRequest::post()
+            .uri("/messages")
+            .insert_header((AUTHORIZATION, bearer.clone()))
+            .set_json(serde_json::json!({ "subject": "Waivers", "body": "Run tonight" }))
+            .to_request()).await;
+        assert_eq!(resp.status(), StatusCode::CREATED);
+        let import = &imports()[0];
+        assert_eq!(import.param("TYPE"), Some("messageBoard"));
+        assert_eq!(import.param("L"), Some(MOCK_LEAGUE_ID));
+        assert_eq!(import.param("SUBJECT"), Some("Waivers"));
+        assert_eq!(import.param("THREAD"), None);
+
+        // Thread ids are escaped on the way to MFL
+        let resp = test::call_service(&app, TestRequest::get()
+            .uri("/messages/54321%26L%3D1")
+            .insert_header((AUTHORIZATION, bearer))
+            .to_request()).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        let export = mock.requests().into_iter().rfind(|request| request.path.ends_with("/export")).unwrap();
+        assert_eq!(export.param("THREAD"), Some("54321&L=1"));
+        assert_eq!(export.param("L"), Some(MOCK_LEAGUE_ID));
+
+        store.clear().unwrap();
+        mock.stop().await;
+    }
+
+    #[actix_web::test]
+    async fn outage_serves_the_last_good_reply_and_refreshes_it_later() {
+        let mock = MockMflServer::start().unwrap();
+        let store = Arc::new(InMemorySessionStore::new());
+        let offline = OfflineStore::open(":memory:").unwrap().with_revalidation(Duration::from_millis(50), 20);
+        let config = MflClientConfig {
+            offline_store: Some(Arc::new(offline)),
+            export_cache: Arc::new(ExportCache::new(CachePolicy::empty())),
+            ..client_config(&mock)
+        };
+        let app = init_app!(config, store);
+        let login = login!(app);
+        let bearer = format!("Bearer {}", login.token);
+        let messages = || TestRequest::get()
+            .uri("/messages")
+            .insert_header((AUTHORIZATION, bearer.clone()))
+            .to_request();
+
+        let resp = test::call_service(&app, messages()).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        mock.set_down(true);
+        let resp = test::call_service(&app, messages()).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(resp.headers().get("X-Data-Source").unwrap(), "stale");
+        assert!(resp.headers().contains_key("Age"));
+        let board: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(board["freshness"]["source"], "stale");
+        assert_eq!(board["threads"].as_array().unwrap().len(), 2);
+
+        // Once MFL is back the background refresh fetches the board again by itself
+        let before = mock.export_count("messageBoard");
+        mock.set_down(false);
+        actix_web::rt::time::sleep(Duration::from_millis(300)).await;
+        assert!(mock.export_count("messageBoard") > before);
+
+        // Free agents say so in the body as well
+        let free_agents = || TestRequest::get()
+            .uri("/free-agents/WR")
+            .insert_header((AUTHORIZATION, bearer.clone()))
+            .to_request();
+        assert_eq!(test::call_service(&app, free_agents()).await.status(), StatusCode::OK);
+        mock.set_down(true);
+        let resp = test::call_service(&app, free_agents()).await;
+        assert_eq!(resp.headers().get("X-Data-Source").unwrap(), "stale");
+        let free_agents: FreeAgentsResponse = test::read_body_json(resp).await;
+        assert_eq!(free_agents.freshness.source, DataSource::Stale);
+        assert_eq!(free_agents.players.len(), 3);
+
+        store.clear().unwrap();
+        mock.stop().await;
+    }
+}
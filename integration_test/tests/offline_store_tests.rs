@@ -0,0 +1,81 @@
+// tests/offline_store_tests.rs
+
+#[cfg(test)]
+mod offline_store_tests {
+    use mfl_manager_lib::{
+        app_state::unix_now,
+        export_cache::{CacheKey, DataSource, Freshness},
+        mfl_api::MflReply,
+        offline_store::OfflineStore,
+    };
+    use reqwest::StatusCode;
+
+    fn reply(body: &str) -> MflReply {
+        MflReply {
+            status: StatusCode::OK,
+            url: "https://www48.myfantasyleague.com/2025/export?TYPE=players".parse().unwrap(),
+            body: body.to_string(),
+            freshness: Freshness::live(),
+        }
+    }
+
+    #[test]
+    fn last_good_reply_survives_a_restart_and_is_marked_stale() {
+        let path = std::env::temp_dir().join(format!("offline-{}.db", uuid::Uuid::new_v4()));
+        let key = CacheKey::from_args("12345", "2025", "TYPE=players&L=12345&JSON=1");
+
+        let store = OfflineStore::open(&path).unwrap();
+        store.save_at(&key, &reply(r#"{"players":{}}"#), unix_now() - 600).unwrap();
+        drop(store);
+
+        let reopened = OfflineStore::open(&path).unwrap();
+        let stale = reopened.load(&key).unwrap().expect("reply should be on disk");
+        assert_eq!(stale.body, r#"{"players":{}}"#);
+        assert_eq!(stale.freshness.source, DataSource::Stale);
+        assert!((600..610).contains(&stale.freshness.age_secs), "age was {}", stale.freshness.age_secs);
+
+        let other = CacheKey::from_args("67890", "2025", "TYPE=players&L=67890&JSON=1");
+        assert!(reopened.load(&other).unwrap().is_none());
+        std::fs::remove_file(&path).ok();
+    }
+
+    #[test]
+    fn newer_replies_replace_older_ones() {
+        let store = OfflineStore::open(":memory:").unwrap();
+        let key = CacheKey::from_args("12345", "2025", "TYPE=messageBoard&L=12345");
+        store.save(&key, &reply("old")).unwrap();
+        store.save(&key, &reply("new")).unwrap();
+        assert_eq!(store.load(&key).unwrap().unwrap().body, "new");
+    }
+
+    #[test]
+    fn replies_past_their_retention_are_removed() {
+        let store = OfflineStore::open(":memory:").unwrap();
+        let old = CacheKey::from_args("12345", "2025", "TYPE=rosters&L=12345");
+        let recent = CacheKey::from_args("12345", "2025", "TYPE=players&L=12345");
+        store.save_at(&old, &reply("old"), unix_now() - 3_600).unwrap();
+        store.save(&recent, &reply("recent")).unwrap();
+
+        assert_eq!(store.remove_older_than(unix_now() - 60).unwrap(), 1);
+        assert!(store.load(&old).unwrap().is_none());
+        assert_eq!(store.load(&recent).unwrap().unwrap().body, "recent");
+    }
+
+    #[test]
+    fn only_one_background_refresh_per_key() {
+        let store = OfflineStore::open(":memory:").unwrap();
+        let key = CacheKey::from_args("12345", "2025", "TYPE=rosters&L=12345");
+        assert!(store.begin_revalidation(&key));
+        assert!(!store.begin_revalidation(&key));
+        store.end_revalidation(&key);
+        assert!(store.begin_revalidation(&key));
+    }
+
+    #[test]
+    fn combined_freshness_reports_the_oldest_source() {
+        let cached = Freshness { source: DataSource::Cache, age_secs: 40 };
+        let stale = Freshness { source: DataSource::Stale, age_secs: 5 };
+        assert_eq!(Freshness::live().combine(cached), cached);
+        assert_eq!(cached.combine(stale), Freshness { source: DataSource::Stale, age_secs: 40 });
+    }
+}
@@ -0,0 +1,62 @@
+// tests/openapi_tests.rs
+
+#[cfg(test)]
+mod openapi_tests {
+    use regex::Regex;
+    use serde_json::Value;
+
+    use mfl_manager_lib::openapi::api_doc;
+
+    // Routes are registered with actix attribute macros, so the handler source lists them all
+    const HANDLERS_SOURCE: &str = include_str!("../../mfl_manager_lib/src/handlers.rs");
+    // ...apart from the ones main.rs registers with web::resource
+    const MAIN_SOURCE: &str = include_str!("../../mfl_manager/src/main.rs");
+
+    // (method, path) for every route the server registers
+    fn registered_routes() -> Vec<(String, String)> {
+        let route_macro = Regex::new(r#"#\[(get|post|put|delete|patch)\("([^"]+)"[^\]]*\)\]"#).unwrap();
+        let resource = Regex::new(r#"web::resource\("([^"]+)"\)\.route\(web::(get|post|put|delete|patch)\(\)"#).unwrap();
+
+        let from_macros = route_macro.captures_iter(HANDLERS_SOURCE)
+            .map(|caps| (caps[1].to_string(), caps[2].to_string()));
+        let from_resources = resource.captures_iter(MAIN_SOURCE)
+            .map(|caps| (caps[2].to_string(), caps[1].to_string()));
+        from_macros.chain(from_resources).collect()
+    }
+
+    fn spec() -> Value {
+        serde_json::to_value(api_doc()).unwrap()
+    }
+
+    #[test]
+    fn every_registered_route_is_documented() {
+        let spec = spec();
+        let routes = registered_routes();
+        assert!(routes.len() >= 12, "found only {:?}; did the route macros change shape?", routes);
+
+        let missing: Vec<String> = routes.iter()
+            .filter(|(method, path)| spec["paths"][path.as_str()][method.as_str()].is_null())
+            .map(|(method, path)| format!("{} {}", method.to_uppercase(), path))
+            .collect();
+        assert!(missing.is_empty(), "routes missing from the OpenAPI spec (add them to openapi::ApiDoc): {:?}", missing);
+    }
+
+    #[test]
+    fn spec_describes_models_errors_and_bearer_auth() {
+        let spec = spec();
+        assert!(spec["openapi"].as_str().unwrap().starts_with("3."));
+
+        let schemas = &spec["components"]["schemas"];
+        for name in ["LoginRequest", "LoginResponse", "PlayerResponse", "ErrorResponse"] {
+            assert!(schemas[name].is_object(), "schema {} missing", name);
+        }
+        assert_eq!(spec["components"]["securitySchemes"]["bearer_auth"]["scheme"], "bearer");
+
+        let free_agents = &spec["paths"]["/free-agents/{position}"]["get"];
+        assert!(free_agents["security"][0]["bearer_auth"].is_array());
+        assert_eq!(free_agents["responses"]["401"]["content"]["application/json"]["schema"]["$ref"],
+                   "#/components/schemas/ErrorResponse");
+        // Logging in is the one call that needs no token
+        assert!(spec["paths"]["/login"]["post"]["security"].is_null());
+    }
+}
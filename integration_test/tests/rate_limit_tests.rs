@@ -0,0 +1,62 @@
+// tests/rate_limit_tests.rs
+
+#[cfg(test)]
+mod rate_limit_tests {
+    use std::time::Duration;
+
+    use actix_web::{http::header::RETRY_AFTER, http::StatusCode, ResponseError};
+    use mfl_manager_lib::{
+        errors::ServiceError,
+        mfl_api::MflError,
+        rate_limit::{RateLimit, RateLimiter},
+    };
+
+    fn limiter() -> RateLimiter {
+        RateLimiter::new(
+            RateLimit { burst: 2.0, per_second: 1.0 },
+            RateLimit { burst: 10.0, per_second: 10.0 },
+            Duration::from_secs(5),
+        )
+    }
+
+    #[test]
+    fn requests_within_the_burst_go_straight_through() {
+        let limiter = limiter();
+        assert_eq!(limiter.reserve("api.myfantasyleague.com", None), Ok(Duration::ZERO));
+        assert_eq!(limiter.reserve("api.myfantasyleague.com", None), Ok(Duration::ZERO));
+
+        // The third has to wait for the bucket to refill, other hosts are unaffected
+        let wait = limiter.reserve("api.myfantasyleague.com", None).unwrap();
+        assert!(wait > Duration::ZERO && wait <= Duration::from_secs(1), "unexpected wait {:?}", wait);
+        assert_eq!(limiter.reserve("www48.myfantasyleague.com", None), Ok(Duration::ZERO));
+    }
+
+    #[test]
+    fn waits_longer_than_the_limit_are_refused() {
+        let limiter = limiter();
+        for _ in 0..7 {
+            limiter.reserve("api.myfantasyleague.com", None).unwrap();
+        }
+        // Seven queued requests at one per second puts the next one past max_wait
+        assert!(limiter.reserve("api.myfantasyleague.com", None).is_err());
+    }
+
+    #[test]
+    fn throttle_replies_block_the_host_and_user() {
+        let limiter = limiter();
+        limiter.throttled("api.myfantasyleague.com", Some("cookie-a"), Duration::from_secs(60));
+
+        assert!(limiter.reserve("api.myfantasyleague.com", None).is_err());
+        assert!(limiter.reserve("www48.myfantasyleague.com", Some("cookie-a")).is_err());
+        assert_eq!(limiter.reserve("www48.myfantasyleague.com", Some("cookie-b")), Ok(Duration::ZERO));
+    }
+
+    #[test]
+    fn rate_limited_errors_become_429_with_retry_after() {
+        let error = ServiceError::MflApiError(MflError::RateLimited { retry_after: Duration::from_secs(42) });
+        assert_eq!(error.status_code(), StatusCode::TOO_MANY_REQUESTS);
+
+        let response = error.error_response();
+        assert_eq!(response.headers().get(RETRY_AFTER).unwrap(), "42");
+    }
+}
@@ -0,0 +1,171 @@
+// tests/readiness_tests.rs
+
+#[cfg(test)]
+mod readiness_tests {
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use actix_web::{
+        http::StatusCode,
+        test::{self, TestRequest},
+        web, App,
+    };
+    use mfl_manager_lib::{
+        app_state::{InMemorySessionStore, SessionBackend, SessionPolicy, SessionStore, SessionStoreError},
+        circuit_breaker::CircuitBreaker,
+        export_cache::{CachePolicy, ExportCache},
+        handler_models::{DependencyStatus, ReadinessResponse, SessionData},
+        handlers,
+        mfl_api::MflClientConfig,
+        offline_store::OfflineStore,
+        rate_limit::RateLimiter,
+        readiness::MflProbeCache,
+    };
+    use mfl_mock::MockMflServer;
+
+    // A session backend whose database has gone away
+    struct BrokenSessionStore;
+
+    impl SessionBackend for BrokenSessionStore {
+        fn get(&self, _: &str) -> Result<Option<SessionData>, SessionStoreError> {
+            Err(SessionStoreError::LockPoisoned)
+        }
+        fn insert(&self, _: &str, _: SessionData) -> Result<(), SessionStoreError> {
+            Err(SessionStoreError::LockPoisoned)
+        }
+        fn remove(&self, _: &str) -> Result<bool, SessionStoreError> {
+            Err(SessionStoreError::LockPoisoned)
+        }
+        fn touch(&self, _: &str, _: u64) -> Result<(), SessionStoreError> {
+            Err(SessionStoreError::LockPoisoned)
+        }
+        fn update_cookie(&self, _: &str, _: &str, _: u64) -> Result<(), SessionStoreError> {
+            Err(SessionStoreError::LockPoisoned)
+        }
+        fn remove_expired(&self, _: &SessionPolicy, _: u64) -> Result<usize, SessionStoreError> {
+            Err(SessionStoreError::LockPoisoned)
+        }
+        fn was_expired(&self, _: &str) -> Result<bool, SessionStoreError> {
+            Err(SessionStoreError::LockPoisoned)
+        }
+        fn count(&self) -> Result<usize, SessionStoreError> {
+            Err(SessionStoreError::LockPoisoned)
+        }
+    }
+
+    fn client_config(base_url: &str) -> MflClientConfig {
+        MflClientConfig {
+            base_url: base_url.to_string(),
+            max_retries: 0,
+            rate_limiter: Arc::new(RateLimiter::default()),
+            // Opens on the first failure
+            circuit_breaker: Arc::new(CircuitBreaker::new(1, Duration::from_secs(60))),
+            export_cache: Arc::new(ExportCache::new(CachePolicy::empty())),
+            ..MflClientConfig::default()
+        }
+    }
+
+    macro_rules! probe {
+        ($app:expr, $uri:expr) => {{
+            let resp = test::call_service(&$app, TestRequest::get().uri($uri).to_request()).await;
+            let status = resp.status();
+            let body: ReadinessResponse = test::read_body_json(resp).await;
+            (status, body)
+        }};
+    }
+
+    macro_rules! app {
+        ($config:expr, $store:expr) => {
+            test::init_service(
+                App::new()
+                    .app_data(web::Data::new($store))
+                    .app_data(web::Data::new($config))
+                    .service(web::resource("/health").route(web::get().to(handlers::health_check)))
+                    .service(handlers::readiness_handler)
+            ).await
+        };
+    }
+
+    #[actix_web::test]
+    async fn ready_reports_every_dependency_and_503s_once_mfl_is_down() {
+        let mock = MockMflServer::start().unwrap();
+        let app = app!(client_config(mock.base_url()), Arc::new(InMemorySessionStore::new()) as SessionStore);
+
+        let (status, ready) = probe!(app, "/ready");
+        assert_eq!(status, StatusCode::OK);
+        assert!(ready.ready);
+        let names: Vec<&str> = ready.checks.keys().map(String::as_str).collect();
+        assert_eq!(names, ["circuit_breaker", "export_cache", "mfl", "session_store"]);
+        assert!(ready.checks.values().all(|check| check.status == DependencyStatus::Up));
+        assert!(ready.checks["mfl"].latency_ms.is_some());
+        assert_eq!(mock.export_count("nflByeWeeks"), 1);
+
+        mock.set_down(true);
+        let (status, ready) = probe!(app, "/ready");
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        assert!(!ready.ready);
+        assert_eq!(ready.checks["mfl"].status, DependencyStatus::Down);
+        assert!(ready.checks["mfl"].critical);
+
+        // The failed probe opened the breaker, so MFL isn't asked again until it closes
+        let (status, ready) = probe!(app, "/ready");
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(ready.checks["circuit_breaker"].status, DependencyStatus::Down);
+        assert_eq!(mock.export_count("nflByeWeeks"), 2);
+
+        // Liveness doesn't care
+        let resp = test::call_service(&app, TestRequest::get().uri("/health").to_request()).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        mock.stop().await;
+    }
+
+    #[actix_web::test]
+    async fn mfl_is_probed_at_most_once_per_cache_period() {
+        let mock = MockMflServer::start().unwrap();
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(Arc::new(InMemorySessionStore::new()) as SessionStore))
+                .app_data(web::Data::new(client_config(mock.base_url())))
+                .app_data(web::Data::new(MflProbeCache::new(Duration::from_secs(60))))
+                .service(handlers::readiness_handler)
+        ).await;
+
+        for _ in 0..3 {
+            let (status, ready) = probe!(app, "/ready");
+            assert_eq!(status, StatusCode::OK);
+            assert_eq!(ready.checks["mfl"].status, DependencyStatus::Up);
+        }
+        assert_eq!(mock.export_count("nflByeWeeks"), 1);
+        mock.stop().await;
+    }
+
+    #[actix_web::test]
+    async fn a_broken_session_store_is_critical() {
+        let mock = MockMflServer::start().unwrap();
+        let app = app!(client_config(mock.base_url()), Arc::new(BrokenSessionStore) as SessionStore);
+
+        let (status, ready) = probe!(app, "/ready");
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        let sessions = &ready.checks["session_store"];
+        assert_eq!(sessions.status, DependencyStatus::Down);
+        assert!(sessions.detail.as_deref().is_some_and(|detail| detail.contains("lock")), "{:?}", sessions.detail);
+        assert_eq!(ready.checks["mfl"].status, DependencyStatus::Up);
+        mock.stop().await;
+    }
+
+    #[actix_web::test]
+    async fn mfl_outages_are_tolerated_with_an_offline_store() {
+        // Nothing listens here
+        let mut config = client_config("http://127.0.0.1:9");
+        config.offline_store = Some(Arc::new(OfflineStore::open(":memory:").unwrap()));
+        let app = app!(config, Arc::new(InMemorySessionStore::new()) as SessionStore);
+
+        let (status, ready) = probe!(app, "/ready");
+        assert_eq!(status, StatusCode::OK, "{:?}", ready);
+        assert!(ready.ready);
+        let mfl = &ready.checks["mfl"];
+        assert_eq!(mfl.status, DependencyStatus::Down);
+        assert!(!mfl.critical);
+        assert_eq!(ready.checks["offline_store"].status, DependencyStatus::Up);
+    }
+}
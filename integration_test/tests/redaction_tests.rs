@@ -0,0 +1,164 @@
+// tests/redaction_tests.rs
+
+#[cfg(test)]
+mod redaction_tests {
+    use std::io::Write;
+    use std::sync::{Arc, Mutex};
+
+    use actix_web::{
+        http::header::AUTHORIZATION,
+        http::StatusCode,
+        test::{self, TestRequest},
+        web, App,
+    };
+    use mfl_manager_lib::{
+        app_state::{InMemorySessionStore, SessionStore},
+        circuit_breaker::CircuitBreaker,
+        export_cache::{CachePolicy, ExportCache},
+        handler_middleware::{AuthMiddleware, RequestTracing},
+        handler_models::{LoginRequest, LoginResponse},
+        handlers,
+        mfl_api::MflClientConfig,
+        rate_limit::RateLimiter,
+        redact::{redact, RedactingMakeWriter, REDACTED},
+    };
+    use mfl_mock::{MockMflServer, MOCK_COOKIE, MOCK_PASSWORD, MOCK_USERNAME};
+
+    #[derive(Clone, Default)]
+    struct CapturedLogs(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for CapturedLogs {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl CapturedLogs {
+        fn text(&self) -> String {
+            String::from_utf8(self.0.lock().unwrap().clone()).unwrap()
+        }
+    }
+
+    // The same writer main.rs installs, at the most verbose level, for this thread only
+    fn capture_logs(json: bool) -> (CapturedLogs, tracing::subscriber::DefaultGuard) {
+        let logs = CapturedLogs::default();
+        let writer = logs.clone();
+        let builder = tracing_subscriber::fmt()
+            .with_max_level(tracing::Level::TRACE)
+            .with_writer(RedactingMakeWriter::new(move || writer.clone()));
+        let guard = if json {
+            tracing::subscriber::set_default(builder.json().with_span_list(true).finish())
+        } else {
+            tracing::subscriber::set_default(builder.finish())
+        };
+        (logs, guard)
+    }
+
+    #[test]
+    fn secrets_are_replaced_and_everything_else_kept() {
+        let cases = [
+            ("GET /2025/login?USERNAME=bob&PASSWORD=hunter2&XML=1", "hunter2"),
+            ("body: USERNAME=bob&password=hunter2", "hunter2"),
+            (r#"<status cookie_name="MFL_USER_ID" cookie_value="c00kie" MFL_USER_ID="c00kie">OK</status>"#, "c00kie"),
+            (r#"{"message":"<status MFL_USER_ID=\"c00kie\">OK</status>"}"#, "c00kie"),
+            (r#"LoginRequest { username: "bob", password: "hunter2" }"#, "hunter2"),
+            ("authorization: Bearer 6f1c2d3e-token", "6f1c2d3e-token"),
+            (r#"{"authorization": "Bearer 6f1c2d3e-token"}"#, "6f1c2d3e-token"),
+            ("sent with Bearer 6f1c2d3e-token", "6f1c2d3e-token"),
+            ("cookie: MFL_USER_ID=c00kie", "c00kie"),
+            ("Set-Cookie: MFL_USER_ID=c00kie; path=/", "c00kie"),
+            ("https://api.myfantasyleague.com/2025/export?TYPE=league&APIKEY=k3y", "k3y"),
+            ("x-api-key: k3y", "k3y"),
+            ("key mflk_0123456789abcdef0123456789abcdef_s3cr3t-Part_2 rejected", "s3cr3t-Part_2"),
+        ];
+        for (line, secret) in cases {
+            let redacted = redact(line);
+            assert!(!redacted.contains(secret), "{} -> {}", line, redacted);
+            assert!(redacted.contains(REDACTED), "{} -> {}", line, redacted);
+        }
+
+        let harmless = "Listing free agents for league 12345 (TYPE=freeAgents&L=12345&POSITION=WR)";
+        assert_eq!(redact(harmless), harmless);
+        // JSON log lines stay parseable
+        let line = r#"{"fields":{"message":"login reply <status MFL_USER_ID=\"c00kie\">OK</status>"}}"#;
+        assert!(serde_json::from_str::<serde_json::Value>(&redact(line)).is_ok());
+    }
+
+    #[test]
+    fn the_writer_redacts_every_module() {
+        for json in [false, true] {
+            let (logs, _guard) = capture_logs(json);
+            tracing::info!(target: "some_dependency", "GET /2025/login?USERNAME=bob&PASSWORD=hunter2");
+            tracing::debug!(cookie = "MFL_USER_ID=c00kie", authorization = "Bearer 6f1c2d3e-token", "request headers");
+            tracing::warn!("{:?}", reqwest::header::HeaderValue::from_static("Bearer 6f1c2d3e-token"));
+
+            let text = logs.text();
+            assert_eq!(text.lines().count(), 3, "{}", text);
+            for secret in ["hunter2", "c00kie", "6f1c2d3e-token"] {
+                assert!(!text.contains(secret), "{} leaked: {}", secret, text);
+            }
+        }
+    }
+
+    #[actix_web::test]
+    async fn login_and_requests_log_no_credentials_cookies_or_tokens() {
+        let (logs, _guard) = capture_logs(true);
+        let mock = MockMflServer::start().unwrap();
+        let config = MflClientConfig {
+            base_url: mock.base_url().to_string(),
+            max_retries: 0,
+            rate_limiter: Arc::new(RateLimiter::default()),
+            circuit_breaker: Arc::new(CircuitBreaker::default()),
+            export_cache: Arc::new(ExportCache::new(CachePolicy::empty())),
+            ..MflClientConfig::default()
+        };
+        let store = Arc::new(InMemorySessionStore::new());
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(store.clone() as SessionStore))
+                .app_data(web::Data::new(config))
+                .wrap(RequestTracing)
+                .service(handlers::login_handler)
+                .service(web::scope("").wrap(AuthMiddleware).service(handlers::get_free_agents_handler))
+        ).await;
+
+        let login_request = |password: &str| LoginRequest {
+            username: MOCK_USERNAME.to_string(),
+            password: password.to_string(),
+            league_id: None,
+            year: "2025".to_string(),
+            remember_credentials: false,
+        };
+        let resp = test::call_service(&app, TestRequest::post().uri("/login").set_json(login_request("wrong-password")).to_request()).await;
+        assert!(!resp.status().is_success());
+        let resp = test::call_service(&app, TestRequest::post().uri("/login").set_json(login_request(MOCK_PASSWORD)).to_request()).await;
+        let login: LoginResponse = test::read_body_json(resp).await;
+        let resp = test::call_service(&app, TestRequest::get()
+            .uri("/free-agents/WR")
+            .insert_header((AUTHORIZATION, format!("Bearer {}", login.token)))
+            .to_request()).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        // Credentials travel in the form body, never in the URL
+        let logins: Vec<_> = mock.requests().into_iter().filter(|request| request.path.ends_with("/login")).collect();
+        assert_eq!(logins.len(), 2);
+        for request in &logins {
+            assert_eq!(request.method, "POST");
+            assert_eq!(request.param("USERNAME"), Some(MOCK_USERNAME));
+        }
+
+        let text = logs.text();
+        assert!(text.contains("Logging in to MFL"), "{}", text);
+        for secret in [MOCK_PASSWORD, "wrong-password", MOCK_COOKIE, login.token.as_str()] {
+            assert!(!text.contains(secret), "{} leaked into the logs: {}", secret, text);
+        }
+
+        store.clear().unwrap();
+        mock.stop().await;
+    }
+}
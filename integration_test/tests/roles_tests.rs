@@ -0,0 +1,185 @@
+// tests/roles_tests.rs
+
+#[cfg(test)]
+mod roles_tests {
+    use std::sync::Arc;
+
+    use actix_web::{
+        http::header::AUTHORIZATION,
+        http::StatusCode,
+        test::{self, TestRequest},
+        web, App,
+    };
+    use mfl_manager_lib::{
+        app_state::{InMemorySessionStore, SessionStore},
+        circuit_breaker::CircuitBreaker,
+        export_cache::{CachePolicy, ExportCache},
+        handler_middleware::AuthMiddleware,
+        handler_models::{LoginRequest, LoginResponse, MeResponse, Role},
+        handlers,
+        mfl_api::MflClientConfig,
+        rate_limit::RateLimiter,
+    };
+    use mfl_mock::{MockMflServer, MOCK_LEAGUE_ID, MOCK_PASSWORD, MOCK_USERNAME};
+    use serde_json::json;
+
+    const COMMISSIONER_ABILITIES: &str = r#"{
+        "version": "1.0",
+        "abilities": { "ability": [
+            { "name": "COMMISSIONER", "$t": "1" },
+            { "name": "SUBMIT_LINEUP", "$t": "1" }
+        ]},
+        "encoding": "utf-8"
+    }"#;
+
+    const OWNER_ABILITIES: &str = r#"{"version": "1.0", "abilities": {"ability": {"name": "SUBMIT_LINEUP", "$t": "1"}}, "encoding": "utf-8"}"#;
+
+    // MFL's commissioner franchise, but without the ability that makes it one
+    const FRANCHISE_0000_LEAGUES: &str = r#"{
+        "version": "1.0",
+        "leagues": { "league": [
+            { "league_id": "{{league_id}}", "name": "Mock Dynasty League", "url": "{{base_url}}/{{year}}/home/{{league_id}}",
+              "franchise_id": "0000", "franchise_name": "Commissioner" }
+        ]},
+        "encoding": "utf-8"
+    }"#;
+
+    macro_rules! init_app {
+        ($mock:expr) => {
+            test::init_service(
+                App::new()
+                    .app_data(web::Data::new(Arc::new(InMemorySessionStore::new()) as SessionStore))
+                    .app_data(web::Data::new(MflClientConfig {
+                        base_url: $mock.base_url().to_string(),
+                        max_retries: 0,
+                        rate_limiter: Arc::new(RateLimiter::default()),
+                        circuit_breaker: Arc::new(CircuitBreaker::default()),
+                        export_cache: Arc::new(ExportCache::new(CachePolicy::empty())),
+                        ..MflClientConfig::default()
+                    }))
+                    .service(handlers::login_handler)
+                    .service(
+                        web::scope("")
+                            .wrap(AuthMiddleware)
+                            .service(handlers::get_me_handler)
+                            .service(handlers::set_active_league_handler)
+                            .service(handlers::post_message_thread_handler)
+                            .service(handlers::invalidate_cache_handler)
+                    )
+            ).await
+        };
+    }
+
+    macro_rules! login {
+        ($app:expr) => {{
+            let resp = test::call_service(&$app, TestRequest::post()
+                .uri("/login")
+                .set_json(LoginRequest {
+                    username: MOCK_USERNAME.to_string(),
+                    password: MOCK_PASSWORD.to_string(),
+                    league_id: None,
+                    year: "2025".to_string(),
+                    remember_credentials: false,
+                })
+                .to_request()).await;
+            assert_eq!(resp.status(), StatusCode::OK);
+            let login: LoginResponse = test::read_body_json(resp).await;
+            (login.role, format!("Bearer {}", login.token))
+        }};
+    }
+
+    macro_rules! call {
+        ($app:expr, $req:expr, $bearer:expr) => {
+            test::call_service(&$app, $req.insert_header((AUTHORIZATION, $bearer.clone())).to_request()).await
+        };
+    }
+
+    fn imports(mock: &MockMflServer) -> Vec<mfl_mock::RecordedRequest> {
+        mock.requests().into_iter().filter(|request| request.path.ends_with("/import")).collect()
+    }
+
+    #[actix_web::test]
+    async fn owners_are_kept_out_of_commissioner_operations() {
+        let mock = MockMflServer::start().unwrap();
+        let app = init_app!(mock);
+        let (role, bearer) = login!(app);
+        assert_eq!(role, Role::Owner);
+
+        let resp = call!(app, TestRequest::delete().uri("/admin/cache"), bearer);
+        assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+
+        let message = json!({ "subject": "Trade", "body": "Posted as someone else", "franchise_id": "0003" });
+        let resp = call!(app, TestRequest::post().uri("/messages").set_json(&message), bearer);
+        assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+        assert!(imports(&mock).is_empty(), "nothing may reach MFL");
+
+        // Posting as themselves is fine
+        let message = json!({ "subject": "Trade", "body": "Anyone need a WR?" });
+        let resp = call!(app, TestRequest::post().uri("/messages").set_json(&message), bearer);
+        assert_eq!(resp.status(), StatusCode::CREATED);
+        assert_eq!(imports(&mock)[0].param("FRANCHISE_ID"), None);
+
+        // Without a session the role check can't even start
+        let resp = test::call_service(&app, TestRequest::delete().uri("/admin/cache").to_request()).await;
+        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+        mock.stop().await;
+    }
+
+    #[actix_web::test]
+    async fn roles_come_from_mfl_abilities_not_the_franchise() {
+        let mock = MockMflServer::start().unwrap();
+        mock.set_fixture("myleagues", FRANCHISE_0000_LEAGUES);
+        let app = init_app!(mock);
+        let (role, bearer) = login!(app);
+        assert_eq!(role, Role::Owner);
+        let resp = call!(app, TestRequest::delete().uri("/admin/cache"), bearer);
+        assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+
+        let abilities = mock.requests().into_iter().find(|request| request.param("TYPE") == Some("abilities")).unwrap();
+        assert_eq!(abilities.param("L"), Some(MOCK_LEAGUE_ID));
+        assert_eq!(abilities.cookie.as_deref(), Some(mfl_mock::MOCK_COOKIE));
+        mock.stop().await;
+    }
+
+    #[actix_web::test]
+    async fn the_commissioner_can_administer_and_act_for_franchises() {
+        let mock = MockMflServer::start().unwrap();
+        mock.set_fixture("abilities", COMMISSIONER_ABILITIES);
+        let app = init_app!(mock);
+        let (role, bearer) = login!(app);
+        assert_eq!(role, Role::Commissioner);
+
+        // They own a team in the league as well as running it
+        let resp = call!(app, TestRequest::get().uri("/me"), bearer);
+        let me: MeResponse = test::read_body_json(resp).await;
+        assert_eq!(me.role, Role::Commissioner);
+        assert_eq!(me.franchise_id.as_deref(), Some("0001"));
+
+        let resp = call!(app, TestRequest::delete().uri("/admin/cache"), bearer);
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        let message = json!({ "subject": "Lineup fix", "body": "Set for you", "franchise_id": "0003" });
+        let resp = call!(app, TestRequest::post().uri("/messages").set_json(&message), bearer);
+        assert_eq!(resp.status(), StatusCode::CREATED);
+        let import = &imports(&mock)[0];
+        assert_eq!(import.param("FRANCHISE_ID"), Some("0003"));
+        assert_eq!(import.param("L"), Some(MOCK_LEAGUE_ID));
+
+        let message = json!({ "subject": "Lineup fix", "body": "Set for you", "franchise_id": "3&L=1" });
+        let resp = call!(app, TestRequest::post().uri("/messages").set_json(&message), bearer);
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+        // The role belongs to the league: in the other one they are an ordinary owner
+        mock.set_fixture("abilities", OWNER_ABILITIES);
+        let resp = call!(app, TestRequest::put().uri("/me/active-league").set_json(json!({ "league_id": "67890" })), bearer);
+        let me: MeResponse = test::read_body_json(resp).await;
+        assert_eq!(me.role, Role::Owner);
+        // Tokens carry their league, so switching hands out a token for the new one
+        let redraft_bearer = format!("Bearer {}", me.token.expect("switching leagues issues a token"));
+        let resp = call!(app, TestRequest::delete().uri("/admin/cache"), redraft_bearer);
+        assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+        let resp = call!(app, TestRequest::delete().uri("/admin/cache"), bearer);
+        assert_eq!(resp.status(), StatusCode::OK);
+        mock.stop().await;
+    }
+}
@@ -0,0 +1,154 @@
+// tests/session_lifecycle_tests.rs
+
+#[cfg(test)]
+mod session_lifecycle_tests {
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use actix_web::{
+        http::header::AUTHORIZATION,
+        http::StatusCode,
+        test::{self, TestRequest},
+        web, App,
+    };
+    use mfl_manager_lib::{
+        app_state::{unix_now, InMemorySessionStore, SessionBackend, SessionPolicy, SessionStore},
+        circuit_breaker::CircuitBreaker,
+        errors::{ErrorCode, ErrorResponse},
+        export_cache::{CachePolicy, ExportCache},
+        handler_middleware::AuthMiddleware,
+        handler_models::{LoginRequest, LoginResponse},
+        handlers,
+        mfl_api::MflClientConfig,
+        rate_limit::RateLimiter,
+        tokens::TokenKeys,
+    };
+    use mfl_mock::{MockMflServer, MOCK_PASSWORD, MOCK_USERNAME};
+
+    const POLICY: SessionPolicy = SessionPolicy {
+        idle_timeout: Duration::from_secs(60 * 60),
+        max_lifetime: Duration::from_secs(24 * 60 * 60),
+    };
+
+    fn client_config(mock: &MockMflServer) -> MflClientConfig {
+        MflClientConfig {
+            base_url: mock.base_url().to_string(),
+            max_retries: 0,
+            rate_limiter: Arc::new(RateLimiter::default()),
+            circuit_breaker: Arc::new(CircuitBreaker::default()),
+            export_cache: Arc::new(ExportCache::new(CachePolicy::empty())),
+            ..MflClientConfig::default()
+        }
+    }
+
+    macro_rules! init_app {
+        ($mock:expr, $store:expr, $keys:expr) => {
+            test::init_service(
+                App::new()
+                    .app_data(web::Data::new($store.clone() as SessionStore))
+                    .app_data(web::Data::new(POLICY))
+                    .app_data($keys.clone())
+                    .app_data(web::Data::new(client_config(&$mock)))
+                    .service(handlers::login_handler)
+                    .service(
+                        web::scope("")
+                            .wrap(AuthMiddleware)
+                            .service(handlers::logout_handler)
+                            .service(handlers::refresh_session_handler)
+                            .service(handlers::get_me_handler)
+                    )
+            ).await
+        };
+    }
+
+    macro_rules! login {
+        ($app:expr) => {{
+            let resp = test::call_service(&$app, TestRequest::post()
+                .uri("/login")
+                .set_json(LoginRequest {
+                    username: MOCK_USERNAME.to_string(),
+                    password: MOCK_PASSWORD.to_string(),
+                    league_id: None,
+                    year: "2025".to_string(),
+                    remember_credentials: false,
+                })
+                .to_request()).await;
+            assert_eq!(resp.status(), StatusCode::OK);
+            let login: LoginResponse = test::read_body_json(resp).await;
+            login.token
+        }};
+    }
+
+    macro_rules! me {
+        ($app:expr, $token:expr) => {
+            test::call_service(&$app, TestRequest::get()
+                .uri("/me")
+                .insert_header((AUTHORIZATION, format!("Bearer {}", $token)))
+                .to_request()).await
+        };
+    }
+
+    // Moves a stored session's timestamps into the past
+    fn age_session(store: &InMemorySessionStore, sid: &str, created_ago: u64, seen_ago: u64) {
+        let mut session = store.get(sid).unwrap().unwrap();
+        session.created_at = unix_now() - created_ago;
+        session.last_seen = unix_now() - seen_ago;
+        store.insert(sid, session).unwrap();
+    }
+
+    #[actix_web::test]
+    async fn refreshing_never_extends_the_session_lifetime() {
+        let mock = MockMflServer::start().unwrap();
+        let store = Arc::new(InMemorySessionStore::new());
+        let keys = web::Data::new(TokenKeys::generate());
+        let app = init_app!(mock, store, keys);
+        let token = login!(app);
+        let sid = keys.verify(&token).unwrap().sid;
+        age_session(&store, &sid, 20 * 60 * 60, 60);
+
+        let resp = test::call_service(&app, TestRequest::post()
+            .uri("/session/refresh")
+            .insert_header((AUTHORIZATION, format!("Bearer {}", token)))
+            .to_request()).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        let refreshed: LoginResponse = test::read_body_json(resp).await;
+
+        // The new token ends when the session does, four hours from now
+        let claims = keys.verify(&refreshed.token).unwrap();
+        let created_at = store.get(&claims.sid).unwrap().unwrap().created_at;
+        assert_eq!(claims.exp, created_at + POLICY.max_lifetime.as_secs());
+        assert!(claims.exp - claims.iat <= 4 * 60 * 60);
+        assert!(store.get(&claims.sid).unwrap().unwrap().last_seen >= unix_now() - 1);
+
+        assert_eq!(me!(app, refreshed.token).status(), StatusCode::OK);
+        assert_eq!(me!(app, token).status(), StatusCode::UNAUTHORIZED);
+        mock.stop().await;
+    }
+
+    #[actix_web::test]
+    async fn swept_sessions_still_report_that_they_expired() {
+        let mock = MockMflServer::start().unwrap();
+        let store = Arc::new(InMemorySessionStore::new());
+        let keys = web::Data::new(TokenKeys::generate());
+        let app = init_app!(mock, store, keys);
+
+        let idle = login!(app);
+        age_session(&store, &keys.verify(&idle).unwrap().sid, 2 * 60 * 60, 2 * 60 * 60);
+        assert_eq!(store.remove_expired(&POLICY, unix_now()).unwrap(), 1);
+        let resp = me!(app, idle);
+        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+        let error: ErrorResponse = test::read_body_json(resp).await;
+        assert_eq!(error.code, ErrorCode::SessionExpired);
+
+        // Logged out is not expired
+        let logged_out = login!(app);
+        let resp = test::call_service(&app, TestRequest::post()
+            .uri("/logout")
+            .insert_header((AUTHORIZATION, format!("Bearer {}", logged_out)))
+            .to_request()).await;
+        assert_eq!(resp.status(), StatusCode::NO_CONTENT);
+        let error: ErrorResponse = test::read_body_json(me!(app, logged_out)).await;
+        assert_eq!(error.code, ErrorCode::Unauthorized);
+        mock.stop().await;
+    }
+}
@@ -0,0 +1,179 @@
+// tests/session_store_tests.rs
+
+#[cfg(test)]
+mod session_store_tests {
+    use std::path::PathBuf;
+
+    use mfl_manager_lib::{
+        app_state::{InMemorySessionStore, SessionBackend, SessionPolicy, SqliteSessionStore},
+        crypto::{CredentialVault, SecretCipher},
+        handler_models::{Role, SessionData, UserLeague},
+        mfl_api::MflApi,
+    };
+
+    fn temp_db_path() -> PathBuf {
+        std::env::temp_dir().join(format!("mfl_sessions_{}.db", uuid::Uuid::new_v4()))
+    }
+
+    fn sample_session(cookie: &str) -> SessionData {
+        let mfl_api = MflApi::new("2025".to_string()).expect("client should build");
+        mfl_api.set_mfl_user_id_cookie(Some(cookie.to_string()));
+        mfl_api.set_mfl_username(Some("test-user".to_string()));
+        SessionData {
+            mfl_api,
+            league_id: "74560".to_string(),
+            year: "2025".to_string(),
+            franchise_id: Some("0003".to_string()),
+            role: Role::Owner,
+            leagues: vec![UserLeague {
+                league_id: "74560".to_string(),
+                name: "Test League".to_string(),
+                franchise_id: Some("0003".to_string()),
+                franchise_name: Some("Test Franchise".to_string()),
+            }],
+            created_at: 1_000,
+            last_seen: 1_000,
+        }
+    }
+
+    #[test]
+    fn sqlite_sessions_survive_reopening_the_store() {
+        let path = temp_db_path();
+        let key = [7u8; 32];
+
+        {
+            let store = SqliteSessionStore::open(&path, SecretCipher::new(&key)).unwrap();
+            store.insert("token-1", sample_session("secret-cookie-value")).unwrap();
+        }
+
+        // A fresh store has nothing cached in memory, so this reads from disk.
+        let store = SqliteSessionStore::open(&path, SecretCipher::new(&key)).unwrap();
+        let session = store.get("token-1").unwrap().expect("session should be persisted");
+        assert_eq!(session.league_id, "74560");
+        assert_eq!(session.year, "2025");
+        assert_eq!(session.franchise_id.as_deref(), Some("0003"));
+        assert_eq!(session.role, Role::Owner);
+        assert_eq!(session.leagues.len(), 1);
+        assert_eq!(session.mfl_api.mfl_user_id_cookie().as_deref(), Some("secret-cookie-value"));
+        assert_eq!(session.mfl_api.mfl_username().as_deref(), Some("test-user"));
+
+        assert!(store.get("unknown").unwrap().is_none());
+        assert!(store.remove("token-1").unwrap());
+        assert!(store.get("token-1").unwrap().is_none());
+
+        std::fs::remove_file(&path).ok();
+    }
+
+    #[test]
+    fn sqlite_store_never_writes_the_cookie_in_plaintext() {
+        let path = temp_db_path();
+        {
+            let store = SqliteSessionStore::open(&path, SecretCipher::new(&[1u8; 32])).unwrap();
+            store.insert("token-2", sample_session("plaintext-cookie-marker")).unwrap();
+        }
+
+        let raw = std::fs::read(&path).unwrap();
+        let needle = b"plaintext-cookie-marker";
+        assert!(!raw.windows(needle.len()).any(|window| window == needle),
+                "MFL cookie was stored unencrypted");
+
+        // Opening with a different key must not yield the session
+        let store = SqliteSessionStore::open(&path, SecretCipher::new(&[2u8; 32])).unwrap();
+        assert!(store.get("token-2").is_err());
+
+        std::fs::remove_file(&path).ok();
+    }
+
+    #[test]
+    fn expired_sessions_are_swept_from_both_backends() {
+        let policy = SessionPolicy {
+            idle_timeout: std::time::Duration::from_secs(60),
+            max_lifetime: std::time::Duration::from_secs(600),
+        };
+        let path = temp_db_path();
+        let sqlite = SqliteSessionStore::open(&path, SecretCipher::new(&[3u8; 32])).unwrap();
+        let memory = InMemorySessionStore::new();
+        let stores: [&dyn SessionBackend; 2] = [&sqlite, &memory];
+
+        for store in stores {
+            store.insert("idle", sample_session("a")).unwrap();
+            store.insert("active", sample_session("b")).unwrap();
+            store.touch("active", 1_050).unwrap();
+
+            // "idle" was last seen at 1_000 and times out after 60s; "active" is still fresh
+            assert!(policy.is_expired(&store.get("idle").unwrap().unwrap(), 1_100));
+            assert_eq!(store.remove_expired(&policy, 1_100).unwrap(), 1);
+            assert!(store.get("idle").unwrap().is_none());
+            assert!(store.get("active").unwrap().is_some());
+            // Swept sessions are told apart from ones that never existed or were logged out
+            assert!(store.was_expired("idle").unwrap());
+            assert!(!store.was_expired("active").unwrap());
+            assert!(!store.was_expired("unknown").unwrap());
+
+            // Past the absolute lifetime even an active session goes
+            store.touch("active", 1_700).unwrap();
+            assert_eq!(store.remove_expired(&policy, 1_700).unwrap(), 1);
+            assert!(store.get("active").unwrap().is_none());
+            assert!(store.was_expired("active").unwrap());
+            // Once no token for it can be valid, "idle" (created at 1_000) is forgotten
+            assert!(!store.was_expired("idle").unwrap());
+        }
+
+        std::fs::remove_file(&path).ok();
+    }
+
+    #[test]
+    fn sqlite_store_restores_the_credential_vault() {
+        let path = temp_db_path();
+        let key = [4u8; 32];
+        {
+            let store = SqliteSessionStore::open(&path, SecretCipher::new(&key)).unwrap();
+            let mut session = sample_session("cookie");
+            let vault = CredentialVault::seal(SecretCipher::new(&key), "vault-user", "vault-password").unwrap();
+            session.mfl_api.set_credential_vault(Some(vault));
+            store.insert("token-3", session).unwrap();
+        }
+
+        let raw = std::fs::read(&path).unwrap();
+        let needle = b"vault-password";
+        assert!(!raw.windows(needle.len()).any(|window| window == needle),
+                "Vault password was stored unencrypted");
+
+        let store = SqliteSessionStore::open(&path, SecretCipher::new(&key)).unwrap();
+        let session = store.get("token-3").unwrap().unwrap();
+        let (username, password) = session.mfl_api.credential_vault().expect("vault should be restored").open().unwrap();
+        assert_eq!(username, "vault-user");
+        assert_eq!(password, "vault-password");
+
+        std::fs::remove_file(&path).ok();
+    }
+
+    #[test]
+    fn replicas_only_adopt_a_newer_mfl_cookie() {
+        let path = temp_db_path();
+        let cookie = |store: &SqliteSessionStore| store.get("token-4").unwrap().unwrap().mfl_api.mfl_user_id_cookie();
+        let first = SqliteSessionStore::open(&path, SecretCipher::new(&[6u8; 32])).unwrap();
+        let second = SqliteSessionStore::open(&path, SecretCipher::new(&[6u8; 32])).unwrap();
+        first.insert("token-4", sample_session("cookie-1")).unwrap();
+        let session = second.get("token-4").unwrap().unwrap();
+        let issued_at = session.mfl_api.mfl_user_id_cookie_issued_at();
+        assert!(issued_at > 0);
+
+        // The first replica logs in to MFL again
+        first.update_cookie("token-4", "cookie-2", issued_at + 1_000).unwrap();
+        assert_eq!(cookie(&second).as_deref(), Some("cookie-2"));
+
+        // The second renews it once more; the older cookie on disk must not replace it
+        session.mfl_api.restore_mfl_user_id_cookie(Some("cookie-3".to_string()), issued_at + 2_000);
+        assert_eq!(cookie(&second).as_deref(), Some("cookie-3"));
+
+        // Renewals stored out of order keep the newest
+        second.update_cookie("token-4", "cookie-3", issued_at + 2_000).unwrap();
+        first.update_cookie("token-4", "cookie-2", issued_at + 1_000).unwrap();
+        let reopened = SqliteSessionStore::open(&path, SecretCipher::new(&[6u8; 32])).unwrap();
+        assert_eq!(cookie(&reopened).as_deref(), Some("cookie-3"));
+        assert_eq!(reopened.get("token-4").unwrap().unwrap().mfl_api.mfl_user_id_cookie_issued_at(), issued_at + 2_000);
+
+        std::fs::remove_file(&path).ok();
+    }
+}
@@ -0,0 +1,211 @@
+// tests/tokens_tests.rs
+
+#[cfg(test)]
+mod tokens_tests {
+    use std::path::PathBuf;
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use actix_web::{
+        http::header::AUTHORIZATION,
+        http::StatusCode,
+        test::{self, TestRequest},
+        web, App,
+    };
+    use mfl_manager_lib::{
+        app_state::{SessionBackend, SessionStore, SqliteSessionStore},
+        circuit_breaker::CircuitBreaker,
+        crypto::SecretCipher,
+        export_cache::{CachePolicy, ExportCache},
+        handler_middleware::AuthMiddleware,
+        handler_models::{LoginRequest, LoginResponse, MeResponse, Role},
+        handlers,
+        mfl_api::MflClientConfig,
+        rate_limit::RateLimiter,
+        tokens::{AccessClaims, TokenError, TokenKeys, TOKEN_ISSUER},
+    };
+    use mfl_mock::{MockMflServer, MOCK_LEAGUE_ID, MOCK_PASSWORD, MOCK_USERNAME};
+
+    // 32 byte HS256 secrets, base64url encoded
+    fn jwks(keys: &[(&str, char)]) -> String {
+        let keys: Vec<String> = keys.iter()
+            .map(|(kid, fill)| format!(r#"{{"kty": "oct", "kid": "{}", "k": "{}"}}"#, kid, fill.to_string().repeat(43)))
+            .collect();
+        format!(r#"{{"keys": [{}]}}"#, keys.join(", "))
+    }
+
+    fn claims(exp: u64) -> AccessClaims {
+        AccessClaims {
+            sid: "session-1".to_string(),
+            league_id: "12345".to_string(),
+            year: "2025".to_string(),
+            franchise_id: Some("0003".to_string()),
+            role: Role::Owner,
+            iss: TOKEN_ISSUER.to_string(),
+            iat: 0,
+            exp,
+        }
+    }
+
+    fn temp_db_path() -> PathBuf {
+        std::env::temp_dir().join(format!("mfl_tokens_{}.db", uuid::Uuid::new_v4()))
+    }
+
+    #[test]
+    fn rotated_keys_keep_verifying_until_removed() {
+        let far_future = 4_000_000_000;
+        let old = TokenKeys::from_jwks(&jwks(&[("2025-09", 'A')])).unwrap();
+        let token = old.issue(&claims(far_future)).unwrap();
+
+        // A new key goes first: it signs, and the old one still verifies
+        let rotated = TokenKeys::from_jwks(&jwks(&[("2025-10", 'E'), ("2025-09", 'A')])).unwrap();
+        assert_eq!(rotated.signing_kid(), "2025-10");
+        assert_eq!(rotated.verify(&token).unwrap(), claims(far_future));
+        assert!(matches!(old.verify(&rotated.issue(&claims(far_future)).unwrap()),
+            Err(TokenError::UnknownKey(Some(kid))) if kid == "2025-10"));
+
+        let retired = TokenKeys::from_jwks(&jwks(&[("2025-10", 'E')])).unwrap();
+        assert!(matches!(retired.verify(&token), Err(TokenError::UnknownKey(Some(kid))) if kid == "2025-09"));
+
+        // Same kid, different secret: the signature doesn't match
+        let impostor = TokenKeys::from_jwks(&jwks(&[("2025-09", 'E')])).unwrap();
+        assert!(matches!(old.verify(&impostor.issue(&claims(far_future)).unwrap()), Err(TokenError::Invalid(_))));
+        assert!(matches!(old.verify(&old.issue(&claims(1_000)).unwrap()), Err(TokenError::Expired)));
+        assert!(matches!(old.verify("not-a-jwt"), Err(TokenError::Invalid(_))));
+    }
+
+    #[test]
+    fn unusable_key_sets_are_rejected() {
+        assert!(matches!(TokenKeys::from_jwks(r#"{"keys": []}"#), Err(TokenError::NoKeys)));
+        assert!(matches!(TokenKeys::from_jwks("{"), Err(TokenError::KeySet(_))));
+        // Too short, no kid
+        for jwks in [r#"{"keys": [{"kty": "oct", "kid": "short", "k": "c2VjcmV0"}]}"#,
+                     r#"{"keys": [{"kty": "oct", "k": "AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA"}]}"#] {
+            assert!(matches!(TokenKeys::from_jwks(jwks), Err(TokenError::BadKey { .. })), "{}", jwks);
+        }
+    }
+
+    fn client_config(mock: &MockMflServer) -> MflClientConfig {
+        MflClientConfig {
+            base_url: mock.base_url().to_string(),
+            max_retries: 0,
+            rate_limiter: Arc::new(RateLimiter::default()),
+            circuit_breaker: Arc::new(CircuitBreaker::default()),
+            export_cache: Arc::new(ExportCache::new(CachePolicy::empty())),
+            ..MflClientConfig::default()
+        }
+    }
+
+    // One replica: its own session store handle on the shared database, and the shared keys
+    macro_rules! init_replica {
+        ($mock:expr, $path:expr, $jwks:expr) => {{
+            let sessions: SessionStore = Arc::new(SqliteSessionStore::open(&$path, SecretCipher::new(&[5u8; 32])).unwrap()
+                .with_client_config(client_config(&$mock)));
+            test::init_service(
+                App::new()
+                    .app_data(web::Data::new(sessions))
+                    .app_data(web::Data::new(TokenKeys::from_jwks(&$jwks).unwrap()))
+                    .app_data(web::Data::new(client_config(&$mock)))
+                    .app_data(web::Data::new(SecretCipher::new(&[5u8; 32])))
+                    .service(handlers::login_handler)
+                    .service(
+                        web::scope("")
+                            .wrap(AuthMiddleware)
+                            .service(handlers::logout_handler)
+                            .service(handlers::get_me_handler)
+                            .service(handlers::get_message_board_handler)
+                    )
+            ).await
+        }};
+    }
+
+    #[actix_web::test]
+    async fn tokens_from_one_replica_work_on_another() {
+        let mock = MockMflServer::start().unwrap();
+        let path = temp_db_path();
+        let keys = jwks(&[("2025-10", 'E')]);
+        let first = init_replica!(mock, path, keys);
+        let second = init_replica!(mock, path, keys);
+        let stranger = init_replica!(mock, path, jwks(&[("2025-10", 'I')]));
+
+        let resp = test::call_service(&first, TestRequest::post()
+            .uri("/login")
+            .set_json(LoginRequest {
+                username: MOCK_USERNAME.to_string(),
+                password: MOCK_PASSWORD.to_string(),
+                league_id: None,
+                year: "2025".to_string(),
+                remember_credentials: false,
+            })
+            .to_request()).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        let login: LoginResponse = test::read_body_json(resp).await;
+
+        let claims = TokenKeys::from_jwks(&keys).unwrap().verify(&login.token).unwrap();
+        assert_eq!(claims.league_id, MOCK_LEAGUE_ID);
+        assert_eq!(claims.year, "2025");
+        assert_eq!(claims.franchise_id, login.franchise_id);
+        assert_eq!(claims.role, login.role);
+        assert_eq!(claims.exp - claims.iat, Duration::from_secs(24 * 60 * 60).as_secs());
+
+        // The second replica never saw the login, yet finds the MFL cookie by the token's sid
+        let bearer = format!("Bearer {}", login.token);
+        let resp = test::call_service(&second, TestRequest::get().uri("/me").insert_header((AUTHORIZATION, bearer.clone())).to_request()).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        let me: MeResponse = test::read_body_json(resp).await;
+        assert_eq!(me.league_id, MOCK_LEAGUE_ID);
+        let resp = test::call_service(&second, TestRequest::get().uri("/messages").insert_header((AUTHORIZATION, bearer.clone())).to_request()).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        let export = mock.requests().into_iter().rfind(|request| request.path.ends_with("/export")).unwrap();
+        assert!(export.cookie.is_some(), "the MFL cookie comes from the shared store");
+
+        // Without the keys the token is worthless
+        let resp = test::call_service(&stranger, TestRequest::get().uri("/me").insert_header((AUTHORIZATION, bearer.clone())).to_request()).await;
+        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+
+        // Logging out anywhere ends the session everywhere
+        let resp = test::call_service(&second, TestRequest::post().uri("/logout").insert_header((AUTHORIZATION, bearer.clone())).to_request()).await;
+        assert_eq!(resp.status(), StatusCode::NO_CONTENT);
+        let resp = test::call_service(&first, TestRequest::get().uri("/me").insert_header((AUTHORIZATION, bearer.clone())).to_request()).await;
+        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+
+        std::fs::remove_file(&path).ok();
+        mock.stop().await;
+    }
+
+    #[actix_web::test]
+    async fn a_cookie_renewed_during_a_request_is_stored_for_other_replicas() {
+        let mock = MockMflServer::start().unwrap();
+        let path = temp_db_path();
+        let keys = jwks(&[("2025-10", 'E')]);
+        let app = init_replica!(mock, path, keys);
+        let resp = test::call_service(&app, TestRequest::post()
+            .uri("/login")
+            .set_json(LoginRequest {
+                username: MOCK_USERNAME.to_string(),
+                password: MOCK_PASSWORD.to_string(),
+                league_id: None,
+                year: "2025".to_string(),
+                remember_credentials: true,
+            })
+            .to_request()).await;
+        let login: LoginResponse = test::read_body_json(resp).await;
+        let sid = TokenKeys::from_jwks(&keys).unwrap().verify(&login.token).unwrap().sid;
+        let issued_at = || SqliteSessionStore::open(&path, SecretCipher::new(&[5u8; 32])).unwrap()
+            .get(&sid).unwrap().unwrap()
+            .mfl_api.mfl_user_id_cookie_issued_at();
+        let before = issued_at();
+
+        // MFL says the cookie is no good, so the request logs in again from the vault
+        mock.set_fixture("messageBoard", r#"{"error": {"$t": "API requires logged in user"}}"#);
+        test::call_service(&app, TestRequest::get()
+            .uri("/messages")
+            .insert_header((AUTHORIZATION, format!("Bearer {}", login.token)))
+            .to_request()).await;
+        assert_eq!(mock.requests().iter().filter(|request| request.path.ends_with("/login")).count(), 2);
+        assert!(issued_at() > before, "the renewed cookie should be in the database");
+
+        std::fs::remove_file(&path).ok();
+        mock.stop().await;
+    }
+}
@@ -0,0 +1,161 @@
+// tests/tracing_tests.rs
+
+#[cfg(test)]
+mod tracing_tests {
+    use std::io::Write;
+    use std::sync::{Arc, Mutex};
+
+    use actix_web::{
+        http::header::AUTHORIZATION,
+        http::StatusCode,
+        test::{self, TestRequest},
+        web, App,
+    };
+    use mfl_manager_lib::{
+        app_state::{InMemorySessionStore, SessionStore},
+        circuit_breaker::CircuitBreaker,
+        errors::ErrorResponse,
+        export_cache::{CachePolicy, ExportCache},
+        handler_middleware::{AuthMiddleware, RequestTracing},
+        handler_models::{LoginRequest, LoginResponse},
+        handlers,
+        mfl_api::MflClientConfig,
+        rate_limit::RateLimiter,
+    };
+    use mfl_mock::{MockMflServer, MOCK_PASSWORD, MOCK_USERNAME};
+    use serde_json::Value;
+
+    // Collects JSON log lines written by the test's subscriber
+    #[derive(Clone, Default)]
+    struct CapturedLogs(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for CapturedLogs {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl CapturedLogs {
+        fn lines(&self) -> Vec<Value> {
+            let bytes = self.0.lock().unwrap().clone();
+            String::from_utf8(bytes).unwrap()
+                .lines()
+                .map(|line| serde_json::from_str(line).unwrap())
+                .collect()
+        }
+    }
+
+    // Installs a JSON subscriber for the current thread only, so tests don't see each other's logs
+    fn capture_logs() -> (CapturedLogs, tracing::subscriber::DefaultGuard) {
+        let logs = CapturedLogs::default();
+        let writer = logs.clone();
+        let subscriber = tracing_subscriber::fmt()
+            .json()
+            .with_span_list(true)
+            .with_writer(move || writer.clone())
+            .finish();
+        (logs, tracing::subscriber::set_default(subscriber))
+    }
+
+    fn span<'a>(line: &'a Value, name: &str) -> Option<&'a Value> {
+        line["spans"].as_array()?.iter().find(|span| span["name"] == name)
+    }
+
+    #[actix_web::test]
+    async fn request_ids_are_accepted_or_generated_and_returned() {
+        let store = Arc::new(InMemorySessionStore::new());
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(store as SessionStore))
+                .wrap(RequestTracing)
+                .service(web::scope("").wrap(AuthMiddleware).service(handlers::get_me_handler))
+        ).await;
+
+        let resp = test::call_service(&app, TestRequest::get()
+            .uri("/me")
+            .insert_header(("X-Request-Id", "client-id-42"))
+            .to_request()).await;
+        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+        assert_eq!(resp.headers().get("X-Request-Id").unwrap(), "client-id-42");
+        let body: ErrorResponse = test::read_body_json(resp).await;
+        assert_eq!(body.request_id.as_deref(), Some("client-id-42"));
+
+        // Ids that would be awkward in logs or headers are replaced
+        let resp = test::call_service(&app, TestRequest::get()
+            .uri("/me")
+            .insert_header(("X-Request-Id", "has spaces\tand tabs"))
+            .to_request()).await;
+        let generated = resp.headers().get("X-Request-Id").unwrap().to_str().unwrap().to_string();
+        assert!(uuid::Uuid::parse_str(&generated).is_ok(), "{}", generated);
+        let body: ErrorResponse = test::read_body_json(resp).await;
+        assert_eq!(body.request_id, Some(generated));
+    }
+
+    #[actix_web::test]
+    async fn mfl_calls_are_logged_inside_the_request_span() {
+        let (logs, _guard) = capture_logs();
+        let mock = MockMflServer::start().unwrap();
+        let config = MflClientConfig {
+            base_url: mock.base_url().to_string(),
+            max_retries: 0,
+            rate_limiter: Arc::new(RateLimiter::default()),
+            circuit_breaker: Arc::new(CircuitBreaker::default()),
+            export_cache: Arc::new(ExportCache::new(CachePolicy::empty())),
+            ..MflClientConfig::default()
+        };
+        let store = Arc::new(InMemorySessionStore::new());
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(store.clone() as SessionStore))
+                .app_data(web::Data::new(config))
+                .wrap(RequestTracing)
+                .service(handlers::login_handler)
+                .service(web::scope("").wrap(AuthMiddleware).service(handlers::get_free_agents_handler))
+        ).await;
+
+        let resp = test::call_service(&app, TestRequest::post()
+            .uri("/login")
+            .set_json(LoginRequest {
+                username: MOCK_USERNAME.to_string(),
+                password: MOCK_PASSWORD.to_string(),
+                league_id: None,
+                year: "2025".to_string(),
+                remember_credentials: false,
+            })
+            .to_request()).await;
+        let login: LoginResponse = test::read_body_json(resp).await;
+
+        let resp = test::call_service(&app, TestRequest::get()
+            .uri("/free-agents/WR")
+            .insert_header((AUTHORIZATION, format!("Bearer {}", login.token)))
+            .insert_header(("X-Request-Id", "trace-me"))
+            .to_request()).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(resp.headers().get("X-Request-Id").unwrap(), "trace-me");
+
+        // request span -> handler span -> one span per MFL call
+        let lines = logs.lines();
+        let mfl_calls: Vec<&Value> = lines.iter()
+            .filter(|line| line["fields"]["message"] == "MFL replied")
+            .filter(|line| span(line, "http_request").is_some_and(|span| span["request_id"] == "trace-me"))
+            .collect();
+        assert_eq!(mfl_calls.len(), 2, "expected freeAgents and players calls: {:#?}", lines);
+        for line in &mfl_calls {
+            assert!(span(line, "get_free_agents_handler").is_some_and(|span| span["position"] == "WR"));
+            let call = span(line, "mfl_call").unwrap();
+            assert_eq!(call["host"], "127.0.0.1");
+            assert_eq!(call["status"], 200);
+            assert!(call["duration_ms"].is_u64());
+        }
+        let exports: Vec<&Value> = mfl_calls.iter().map(|line| &span(line, "mfl_call").unwrap()["export"]).collect();
+        assert_eq!(exports, ["freeAgents", "players"]);
+
+        store.clear().unwrap();
+        mock.stop().await;
+    }
+}
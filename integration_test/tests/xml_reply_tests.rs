@@ -0,0 +1,50 @@
+// tests/xml_reply_tests.rs
+
+#[cfg(test)]
+mod xml_reply_tests {
+    use mfl_manager_lib::mfl_api::{parse_xml_reply, MflError};
+
+    #[test]
+    fn status_replies_become_import_results() {
+        let result = parse_xml_reply("<status>OK</status>").unwrap();
+        assert_eq!(result.status, "OK");
+        assert!(result.attributes.is_empty());
+
+        let login = r#"<?xml version="1.0" encoding="utf-8"?>
+            <status cookie_name="MFL_USER_ID" cookie_value="c00kie=" MFL_USER_ID="c00kie=">OK</status>"#;
+        let result = parse_xml_reply(login).unwrap();
+        assert_eq!(result.attribute("MFL_USER_ID"), Some("c00kie="));
+        assert_eq!(result.attribute("cookie_name"), Some("MFL_USER_ID"));
+
+        // Entities are decoded, attribute order doesn't matter
+        let result = parse_xml_reply(r#"<status note="a &amp; b" MFL_USER_ID="x">  ok </status>"#).unwrap();
+        assert_eq!(result.attribute("note"), Some("a & b"));
+        assert_eq!(result.status, "ok");
+    }
+
+    #[test]
+    fn error_replies_become_specific_errors() {
+        let cases = [
+            ("<error>Message body is required</error>", "ImportRejected", "Message body is required"),
+            ("<error>Invalid Password</error>", "ImportRejected", "Invalid Password"),
+            ("<error>API requires logged in user</error>", "PermissionDenied", "API requires logged in user"),
+            ("<error>You do not have permission to post as that franchise</error>", "PermissionDenied", "permission"),
+            ("<status>Thread is locked</status>", "ImportRejected", "Thread is locked"),
+            ("<error><![CDATA[Subject & body < 5 chars]]></error>", "ImportRejected", "Subject & body < 5 chars"),
+        ];
+        for (body, variant, message) in cases {
+            let error = parse_xml_reply(body).unwrap_err();
+            assert_eq!(error.variant_name(), variant, "{}", body);
+            assert!(error.to_string().contains(message), "{} -> {}", body, error);
+        }
+        assert!(matches!(parse_xml_reply("<error>Too many requests</error>"), Err(MflError::RateLimited { .. })));
+    }
+
+    #[test]
+    fn anything_else_is_an_unexpected_reply() {
+        for body in ["", "{\"error\": {\"$t\": \"oops\"}}", "<html><body>Bad Gateway</body></html>", "<status>OK", "<error attr=>x</error>"] {
+            let error = parse_xml_reply(body).unwrap_err();
+            assert!(matches!(error, MflError::UnexpectedReply(_)), "{:?} -> {:?}", body, error);
+        }
+    }
+}
@@ -3,55 +3,277 @@ use actix_cors::Cors;
 use actix_web::{web, App, HttpServer};
 // Import *from the library crate*
 use mfl_manager_lib::{
+    api_keys,
     app_state,
+    config::{AppConfig, FixtureModeSetting, LogFormat, SessionBackendKind},
+    crypto,
+    export_cache,
     handler_middleware,
-    handler_models,
     handlers,
+    http_fixtures,
+    mfl_api,
+    offline_store,
+    openapi,
+    readiness,
+    redact,
+    tokens,
 };
-use std::collections::HashMap;
-use std::sync::{Arc, Mutex};
-use log::info;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::info;
+use tracing_subscriber::EnvFilter;
+use utoipa_swagger_ui::SwaggerUi;
+
+// Encrypts secrets at rest (session cookies, credential vaults); optional unless sqlite is used
+fn build_cipher(config: &AppConfig) -> std::io::Result<Option<crypto::SecretCipher>> {
+    config.session.encryption_key.as_deref()
+        .map(|key| crypto::SecretCipher::from_base64_key(key).map_err(std::io::Error::other))
+        .transpose()
+}
+
+fn build_client_config(config: &AppConfig) -> std::io::Result<mfl_api::MflClientConfig> {
+    let mfl = &config.mfl;
+    let offline_store = match &mfl.offline_store_path {
+        Some(path) => {
+            let store = offline_store::OfflineStore::open(path).map_err(std::io::Error::other)?;
+            info!("Serving stale MFL data from {} when MFL is down", path);
+            Some(Arc::new(store))
+        }
+        None => None,
+    };
+    let fixtures = match mfl.fixture_mode {
+        Some(mode) => {
+            let fixtures = match mode {
+                FixtureModeSetting::Record => http_fixtures::HttpFixtures::record(&mfl.fixture_dir),
+                FixtureModeSetting::Replay => http_fixtures::HttpFixtures::replay(&mfl.fixture_dir),
+            }.map_err(std::io::Error::other)?;
+            info!("MFL fixtures: {:?} in {}", mode, mfl.fixture_dir);
+            Some(Arc::new(fixtures))
+        }
+        None => None,
+    };
+    Ok(mfl_api::MflClientConfig {
+        base_url: mfl.base_url.clone(),
+        connect_timeout: Duration::from_secs(mfl.connect_timeout_secs),
+        read_timeout: Duration::from_secs(mfl.read_timeout_secs),
+        max_retries: mfl.max_retries,
+        export_cache: Arc::new(export_cache::ExportCache::new(config.cache.policy())),
+        offline_store,
+        fixtures,
+        ..mfl_api::MflClientConfig::default()
+    })
+}
+
+fn build_session_store(
+    config: &AppConfig,
+    cipher: Option<crypto::SecretCipher>,
+    client_config: &mfl_api::MflClientConfig,
+) -> std::io::Result<app_state::SessionStore> {
+    match config.session.backend {
+        SessionBackendKind::Memory => Ok(Arc::new(app_state::InMemorySessionStore::new())),
+        SessionBackendKind::Sqlite => {
+            let path = &config.session.db_path;
+            // validate() already insists on a key for sqlite
+            let cipher = cipher.ok_or_else(|| {
+                std::io::Error::other("SESSION_ENCRYPTION_KEY must be set to use the sqlite session backend")
+            })?;
+            let store = app_state::SqliteSessionStore::open(path, cipher)
+                .map_err(std::io::Error::other)?
+                .with_client_config(client_config.clone());
+            info!("Using sqlite session store at {}", path);
+            Ok(Arc::new(store))
+        }
+    }
+}
+
+// Optional; keys are backed by MFL credentials, so this needs the encryption key too
+fn build_api_key_store(
+    config: &AppConfig,
+    cipher: Option<crypto::SecretCipher>,
+    client_config: &mfl_api::MflClientConfig,
+) -> std::io::Result<Option<web::Data<api_keys::ApiKeyStore>>> {
+    let Some(path) = &config.api_keys.db_path else {
+        return Ok(None);
+    };
+    // validate() already insists on a key for API keys
+    let cipher = cipher.ok_or_else(|| {
+        std::io::Error::other("SESSION_ENCRYPTION_KEY must be set to use API keys")
+    })?;
+    let store = api_keys::ApiKeyStore::open(path, cipher)
+        .map_err(std::io::Error::other)?
+        .with_client_config(client_config.clone());
+    info!("Using API key store at {}", path);
+    Ok(Some(web::Data::new(store)))
+}
+
+// Without a key set, tokens only work against this process until it restarts
+fn build_token_keys(config: &AppConfig) -> std::io::Result<tokens::TokenKeys> {
+    match &config.session.jwks_path {
+        Some(path) => {
+            let jwks = std::fs::read_to_string(path)?;
+            let keys = tokens::TokenKeys::from_jwks(&jwks).map_err(std::io::Error::other)?;
+            info!("Signing access tokens with key {} from {}", keys.signing_kid(), path);
+            Ok(keys)
+        }
+        None => {
+            tracing::warn!("No session.jwks_path configured; access tokens will not survive a restart or work on other replicas");
+            Ok(tokens::TokenKeys::generate())
+        }
+    }
+}
+
+fn build_session_policy(config: &AppConfig) -> app_state::SessionPolicy {
+    app_state::SessionPolicy {
+        idle_timeout: Duration::from_secs(config.session.idle_timeout_secs),
+        max_lifetime: Duration::from_secs(config.session.max_lifetime_secs),
+    }
+}
+
+fn build_cors(config: &AppConfig) -> Cors {
+    let cors = if config.server.cors_origins.iter().any(|origin| origin == "*") {
+        Cors::default().allow_any_origin().send_wildcard()
+    } else {
+        config.server.cors_origins.iter().fold(Cors::default(), |cors, origin| cors.allowed_origin(origin))
+    };
+    cors.allowed_methods(vec!["GET", "POST", "PUT", "DELETE", "OPTIONS"])
+        .allowed_headers(vec!["Authorization", "Content-Type", "X-API-Key", "X-Request-Id"])
+        .expose_headers(vec!["Age", "X-Data-Source", "X-Request-Id"])
+        .max_age(3600) // Cache OPTIONS responses for 1 hour
+}
+
+// RUST_LOG still overrides the configured level. Also picks up `log` records from dependencies.
+fn init_tracing(config: &AppConfig) {
+    let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(&config.log.level));
+    // Every line is scrubbed of passwords, cookies, tokens and API keys before it is written
+    let subscriber = tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .with_writer(redact::RedactingMakeWriter::new(std::io::stdout));
+    match config.log.format {
+        // One object per line; span_list carries request_id and the MFL call fields
+        LogFormat::Json => subscriber.json().with_current_span(false).with_span_list(true).init(),
+        LogFormat::Text => subscriber.init(),
+    }
+}
+
+struct CliArgs {
+    config_path: Option<PathBuf>,
+    print_config: bool,
+}
+
+const USAGE: &str = "Usage: mfl_manager [--config <file.toml>] [--print-config]";
+
+fn parse_args() -> Result<CliArgs, String> {
+    let mut args = std::env::args().skip(1);
+    let mut cli = CliArgs { config_path: None, print_config: false };
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--config" => cli.config_path = Some(args.next().ok_or("--config needs a file path")?.into()),
+            "--print-config" => cli.print_config = true,
+            "--help" | "-h" => return Err(USAGE.to_string()),
+            other => return Err(format!("Unknown argument '{}'\n{}", other, USAGE)),
+        }
+    }
+    Ok(cli)
+}
 
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
-    // Initialize the logger
-    env_logger::init();
-    
-    // Initialization code...
-    let session_store: app_state::SessionStore = Arc::new(Mutex::new(HashMap::<String, handler_models::SessionData>::new()));
-    // Setup logger, dotenv etc.
+    let cli = match parse_args() {
+        Ok(cli) => cli,
+        Err(message) => {
+            eprintln!("{}", message);
+            std::process::exit(2);
+        }
+    };
+    // Defaults, then the TOML file, then environment variables
+    let config = match AppConfig::load(cli.config_path.as_deref()) {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    };
+    if cli.print_config {
+        print!("{}", config.to_toml_redacted());
+        return Ok(());
+    }
+    init_tracing(&config);
 
-    // Define host and port variables
-    let host = "0.0.0.0";
-    let port = 8080;
-    let server_addr = format!("{}:{}", host, port);
+    // Initialization code...
+    let cipher = build_cipher(&config)?;
+    let client_config = build_client_config(&config)?;
+    let session_store = build_session_store(&config, cipher.clone(), &client_config)?;
+    let api_key_store = build_api_key_store(&config, cipher.clone(), &client_config)?;
+    let token_keys = web::Data::new(build_token_keys(&config)?);
+    // Shared across workers, so /ready asks MFL at most once every few seconds
+    let mfl_probes = web::Data::new(readiness::MflProbeCache::new(Duration::from_secs(5)));
+    let session_policy = build_session_policy(&config);
+    app_state::spawn_session_sweeper(session_store.clone(), session_policy, Duration::from_secs(60));
+    if let Some(store) = &client_config.offline_store {
+        let retention = Duration::from_secs(config.mfl.offline_store_retention_days * 24 * 60 * 60);
+        offline_store::spawn_offline_store_sweeper(store.clone(), retention, Duration::from_secs(60 * 60));
+    }
 
+    let server_addr = config.server.bind.clone();
     info!("Starting server at http://{}", server_addr); // Log the server address
-    HttpServer::new(move || {
+    let app_config = config.clone();
+    let api_doc = openapi::api_doc();
+    let server = HttpServer::new(move || {
         App::new()
             .app_data(web::Data::new(session_store.clone()))
-            .wrap(
-                Cors::default()
-                    .allow_any_origin()              // Allow all origins (unsafe for production)
-                    .send_wildcard()
-                    .allowed_methods(vec!["GET", "POST", "PUT", "DELETE", "OPTIONS"])
-                    .allowed_headers(vec!["Authorization", "Content-Type"])
-                    .max_age(3600),                 // Cache OPTIONS responses for 1 hour
-            )
+            .app_data(web::Data::new(session_policy))
+            .app_data(token_keys.clone())
+            .app_data(mfl_probes.clone())
+            .app_data(web::Data::new(client_config.clone()))
+            .configure(|cfg| {
+                // Only offered when an encryption key is configured
+                if let Some(cipher) = &cipher {
+                    cfg.app_data(web::Data::new(cipher.clone()));
+                }
+                // Shared across workers: the store owns a single connection
+                if let Some(store) = &api_key_store {
+                    cfg.app_data(store.clone());
+                }
+            })
+            .wrap(build_cors(&app_config))
+            // Outside CORS, so preflights and rejected requests are counted too
+            .wrap(handler_middleware::RequestMetrics::new(client_config.metrics.clone()))
+            // Outermost, so every response (CORS preflights included) gets an X-Request-Id
+            .wrap(handler_middleware::RequestTracing)
             // Register public services first
             .service(handlers::login_handler)
+            // Liveness only; /ready checks MFL and the stores
             .service(web::resource("/health").route(web::get().to(handlers::health_check)))
+            .service(handlers::readiness_handler)
+            .service(handlers::metrics_handler)
+            // OpenAPI spec at /openapi.json, browsable at /docs/
+            .service(SwaggerUi::new("/docs/{_:.*}").url("/openapi.json", api_doc.clone()))
 
             // Now, create a new scope for services that require authentication
             .service(
                 web::scope("") // Using an empty scope to keep original paths
                     .wrap(handler_middleware::AuthMiddleware)
+                    .service(handlers::logout_handler)
+                    .service(handlers::refresh_session_handler)
                     .service(handlers::get_free_agents_handler)
+                    .service(handlers::get_me_handler)
+                    .service(handlers::set_active_league_handler)
+                    .service(handlers::get_message_board_handler)
+                    .service(handlers::post_message_thread_handler)
+                    .service(handlers::get_message_thread_handler)
+                    .service(handlers::reply_message_handler)
+                    .service(handlers::invalidate_cache_handler)
+                    .service(handlers::create_api_key_handler)
+                    .service(handlers::list_api_keys_handler)
+                    .service(handlers::revoke_api_key_handler)
                     // Add other protected services here in the future
             )
         // ... other services
-    })
-        .bind((host, port))?
-        .run()
-        .await
+    });
+    let server = match config.server.workers {
+        Some(workers) => server.workers(workers),
+        None => server,
+    };
+    server.bind(&server_addr)?.run().await
 }
\ No newline at end of file
@@ -0,0 +1,311 @@
+// src/api_keys.rs
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Mutex;
+use actix_web::http::Method;
+use actix_web::web;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use rand::RngCore;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+use utoipa::ToSchema;
+use crate::app_state::unix_now;
+use crate::crypto::{CredentialVault, CryptoError, SecretCipher};
+use crate::mfl_api::{MflApi, MflClientConfig, MflError};
+
+/// Every API key starts with this, so it can be told apart from a session token.
+pub const API_KEY_PREFIX: &str = "mflk_";
+
+// Bytes of randomness in the secret half of a key
+const SECRET_LEN: usize = 32;
+
+#[derive(Error, Debug)]
+pub enum ApiKeyError {
+    #[error("API key store lock was poisoned")]
+    LockPoisoned,
+
+    #[error("API key database error: {0}")]
+    Database(#[from] rusqlite::Error),
+
+    #[error("API key data could not be serialized: {0}")]
+    Serialization(#[from] serde_json::Error),
+
+    #[error("API key credentials could not be encrypted or decrypted: {0}")]
+    Crypto(#[from] CryptoError),
+
+    #[error("MFL client for API key could not be built: {0}")]
+    Client(#[from] MflError),
+
+    #[error("API key store call did not complete")]
+    Blocking,
+}
+
+/// Runs `call` against `store` on Actix's blocking thread pool, so SQLite never
+/// stalls the worker serving other requests.
+pub async fn blocking<T, F>(store: &web::Data<ApiKeyStore>, call: F) -> Result<T, ApiKeyError>
+where
+    F: FnOnce(&ApiKeyStore) -> Result<T, ApiKeyError> + Send + 'static,
+    T: Send + 'static,
+{
+    let store = store.clone();
+    web::block(move || call(&store)).await
+        .map_err(|_| ApiKeyError::Blocking)?
+}
+
+/// What an API key may do. Keys never reach session or admin endpoints.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Permission {
+    /// Every GET endpoint for the key's league
+    Read,
+    /// Starting threads and replying on the message board
+    MessagesWrite,
+}
+
+impl Permission {
+    /// The permission a key needs for `method` on `path`, or None if keys can't call it at all.
+    pub fn required_for(method: &Method, path: &str) -> Option<Permission> {
+        if path.starts_with("/admin") || path.starts_with("/session") || path == "/logout" {
+            return None;
+        }
+        if *method == Method::GET || *method == Method::HEAD {
+            return Some(Permission::Read);
+        }
+        if *method == Method::POST && (path == "/messages" || path.starts_with("/messages/")) {
+            return Some(Permission::MessagesWrite);
+        }
+        None
+    }
+}
+
+/// An API key as shown to admins. The secret is never stored, only its hash.
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+pub struct ApiKeyInfo {
+    pub id: String,
+    pub name: String,
+    pub league_id: String,
+    pub year: String,
+    pub permissions: Vec<Permission>,
+    pub created_at: u64,
+    pub last_used_at: Option<u64>,
+}
+
+impl ApiKeyInfo {
+    pub fn allows(&self, permission: Permission) -> bool {
+        self.permissions.contains(&permission)
+    }
+}
+
+/// A key that was just created. `key` is only available now.
+#[derive(Debug)]
+pub struct IssuedApiKey {
+    pub key: String,
+    pub info: ApiKeyInfo,
+}
+
+/// A presented key that matched, with the MFL client acting for it.
+#[derive(Debug, Clone)]
+pub struct AuthenticatedKey {
+    pub info: ApiKeyInfo,
+    pub mfl_api: MflApi,
+}
+
+/// Long-lived API keys in SQLite. Each key carries the MFL credentials it acts with,
+/// encrypted with `cipher`; its MFL client logs in with them when MFL asks.
+pub struct ApiKeyStore {
+    conn: Mutex<Connection>,
+    cipher: SecretCipher,
+    client_config: MflClientConfig,
+    // One client per key, so its MFL cookie is reused between requests
+    clients: Mutex<HashMap<String, MflApi>>,
+}
+
+impl std::fmt::Debug for ApiKeyStore {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ApiKeyStore").finish_non_exhaustive()
+    }
+}
+
+impl ApiKeyStore {
+    pub fn open<P: AsRef<Path>>(path: P, cipher: SecretCipher) -> Result<Self, ApiKeyError> {
+        Self::with_connection(Connection::open(path)?, cipher)
+    }
+
+    pub fn with_connection(conn: Connection, cipher: SecretCipher) -> Result<Self, ApiKeyError> {
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS api_keys (
+                id               TEXT PRIMARY KEY,
+                name             TEXT NOT NULL,
+                league_id        TEXT NOT NULL,
+                year             TEXT NOT NULL,
+                permissions      TEXT NOT NULL,
+                secret_hash      TEXT NOT NULL,
+                credential_vault TEXT NOT NULL,
+                created_at       INTEGER NOT NULL,
+                last_used_at     INTEGER
+            );",
+        )?;
+        Ok(ApiKeyStore {
+            conn: Mutex::new(conn),
+            cipher,
+            client_config: MflClientConfig::default(),
+            clients: Mutex::new(HashMap::new()),
+        })
+    }
+
+    pub fn with_client_config(mut self, client_config: MflClientConfig) -> Self {
+        self.client_config = client_config;
+        self
+    }
+
+    /// Creates a key for `league_id` acting as the given MFL account. `mfl_api`, if
+    /// already logged in with those credentials, is kept as the key's client.
+    pub fn create(
+        &self,
+        name: &str,
+        league_id: &str,
+        year: &str,
+        permissions: Vec<Permission>,
+        (username, password): (&str, &str),
+        mfl_api: Option<MflApi>,
+    ) -> Result<IssuedApiKey, ApiKeyError> {
+        let id = uuid::Uuid::new_v4().simple().to_string();
+        let mut secret = [0u8; SECRET_LEN];
+        rand::rng().fill_bytes(&mut secret);
+        let secret = URL_SAFE_NO_PAD.encode(secret);
+
+        let vault = CredentialVault::seal(self.cipher.clone(), username, password)?;
+        let info = ApiKeyInfo {
+            id: id.clone(),
+            name: name.to_string(),
+            league_id: league_id.to_string(),
+            year: year.to_string(),
+            permissions,
+            created_at: unix_now(),
+            last_used_at: None,
+        };
+
+        let conn = self.conn.lock().map_err(|_| ApiKeyError::LockPoisoned)?;
+        conn.execute(
+            "INSERT INTO api_keys (id, name, league_id, year, permissions, secret_hash, credential_vault, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            params![info.id, info.name, info.league_id, info.year, serde_json::to_string(&info.permissions)?,
+                    hash_secret(&secret), serde_json::to_string(vault.sealed())?, info.created_at],
+        )?;
+        drop(conn);
+
+        if let Some(mut mfl_api) = mfl_api {
+            mfl_api.set_credential_vault(Some(vault));
+            self.clients.lock().map_err(|_| ApiKeyError::LockPoisoned)?.insert(id.clone(), mfl_api);
+        }
+        Ok(IssuedApiKey { key: format!("{}{}_{}", API_KEY_PREFIX, id, secret), info })
+    }
+
+    /// Keys for `league_id`, oldest first.
+    pub fn list(&self, league_id: &str) -> Result<Vec<ApiKeyInfo>, ApiKeyError> {
+        let conn = self.conn.lock().map_err(|_| ApiKeyError::LockPoisoned)?;
+        let mut stmt = conn.prepare(
+            "SELECT id, name, league_id, year, permissions, created_at, last_used_at
+             FROM api_keys WHERE league_id = ?1 ORDER BY created_at, id",
+        )?;
+        let rows = stmt.query_map(params![league_id], |row| Ok((
+            row.get::<_, String>(0)?,
+            row.get::<_, String>(1)?,
+            row.get::<_, String>(2)?,
+            row.get::<_, String>(3)?,
+            row.get::<_, String>(4)?,
+            row.get::<_, u64>(5)?,
+            row.get::<_, Option<u64>>(6)?,
+        )))?.collect::<Result<Vec<_>, _>>()?;
+
+        rows.into_iter()
+            .map(|(id, name, league_id, year, permissions, created_at, last_used_at)| Ok(ApiKeyInfo {
+                id,
+                name,
+                league_id,
+                year,
+                permissions: serde_json::from_str(&permissions)?,
+                created_at,
+                last_used_at,
+            }))
+            .collect()
+    }
+
+    /// Deletes key `id` of `league_id`, returning whether it existed. It stops working immediately.
+    pub fn revoke(&self, league_id: &str, id: &str) -> Result<bool, ApiKeyError> {
+        let conn = self.conn.lock().map_err(|_| ApiKeyError::LockPoisoned)?;
+        let removed = conn.execute("DELETE FROM api_keys WHERE id = ?1 AND league_id = ?2", params![id, league_id])?;
+        drop(conn);
+        self.clients.lock().map_err(|_| ApiKeyError::LockPoisoned)?.remove(id);
+        Ok(removed > 0)
+    }
+
+    /// Looks up a presented key (`mflk_<id>_<secret>`). None if it is malformed, unknown or revoked.
+    pub fn authenticate(&self, presented: &str) -> Result<Option<AuthenticatedKey>, ApiKeyError> {
+        let Some((id, secret)) = presented.strip_prefix(API_KEY_PREFIX).and_then(|rest| rest.split_once('_')) else {
+            return Ok(None);
+        };
+
+        let conn = self.conn.lock().map_err(|_| ApiKeyError::LockPoisoned)?;
+        let row = conn.query_row(
+            "SELECT name, league_id, year, permissions, secret_hash, credential_vault, created_at
+             FROM api_keys WHERE id = ?1",
+            params![id],
+            |row| Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, String>(2)?,
+                row.get::<_, String>(3)?,
+                row.get::<_, String>(4)?,
+                row.get::<_, String>(5)?,
+                row.get::<_, u64>(6)?,
+            )),
+        ).optional()?;
+        let Some((name, league_id, year, permissions, secret_hash, credential_vault, created_at)) = row else {
+            return Ok(None);
+        };
+        if !constant_time_eq(hash_secret(secret).as_bytes(), secret_hash.as_bytes()) {
+            return Ok(None);
+        }
+        let now = unix_now();
+        conn.execute("UPDATE api_keys SET last_used_at = ?2 WHERE id = ?1", params![id, now])?;
+        drop(conn);
+
+        let info = ApiKeyInfo {
+            id: id.to_string(),
+            name,
+            league_id,
+            year,
+            permissions: serde_json::from_str(&permissions)?,
+            created_at,
+            last_used_at: Some(now),
+        };
+
+        let mut clients = self.clients.lock().map_err(|_| ApiKeyError::LockPoisoned)?;
+        let mfl_api = match clients.get(id) {
+            Some(mfl_api) => mfl_api.clone(),
+            None => {
+                let mut mfl_api = MflApi::with_config(info.year.clone(), &self.client_config)?;
+                let vault = CredentialVault::from_sealed(self.cipher.clone(), serde_json::from_str(&credential_vault)?);
+                // So exports are keyed on the user before its first login
+                mfl_api.set_mfl_username(Some(vault.open()?.0));
+                mfl_api.set_credential_vault(Some(vault));
+                clients.insert(id.to_string(), mfl_api.clone());
+                mfl_api
+            }
+        };
+        Ok(Some(AuthenticatedKey { info, mfl_api }))
+    }
+}
+
+// Hex SHA-256. Secrets are 256 random bits, so a fast unsalted hash is enough.
+fn hash_secret(secret: &str) -> String {
+    Sha256::digest(secret.as_bytes()).iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |diff, (x, y)| diff | (x ^ y)) == 0
+}
@@ -1,7 +1,432 @@
 // src/app_state.rs
 use std::collections::HashMap;
+use std::path::Path;
 use std::sync::{Arc, Mutex};
-use crate::handler_models::SessionData;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use rusqlite::{params, Connection, OptionalExtension};
+use thiserror::Error;
+use crate::crypto::{CredentialVault, CryptoError, SecretCipher};
+use crate::handler_models::{Role, SessionData, UserLeague};
+use crate::mfl_api::{MflApi, MflClientConfig, MflError};
 
-// Use a thread-safe HashMap for session storage
-pub type SessionStore = Arc<Mutex<HashMap<String, SessionData>>>;
\ No newline at end of file
+#[derive(Error, Debug)]
+pub enum SessionStoreError {
+    #[error("Session store lock was poisoned")]
+    LockPoisoned,
+
+    #[error("Session database error: {0}")]
+    Database(#[from] rusqlite::Error),
+
+    #[error("Session data could not be serialized: {0}")]
+    Serialization(#[from] serde_json::Error),
+
+    #[error("Session secret could not be encrypted or decrypted: {0}")]
+    Crypto(#[from] CryptoError),
+
+    #[error("Session could not be restored: {0}")]
+    Restore(#[from] MflError),
+
+    #[error("Session store call did not complete")]
+    Blocking,
+}
+
+/// Seconds since the Unix epoch, the unit session timestamps are stored in.
+pub fn unix_now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or_default()
+}
+
+/// How long a session may live. A session expires after `idle_timeout` without
+/// requests, or `max_lifetime` after it was created, whichever comes first.
+#[derive(Debug, Clone, Copy)]
+pub struct SessionPolicy {
+    pub idle_timeout: Duration,
+    pub max_lifetime: Duration,
+}
+
+impl Default for SessionPolicy {
+    fn default() -> Self {
+        SessionPolicy {
+            idle_timeout: Duration::from_secs(2 * 60 * 60),
+            max_lifetime: Duration::from_secs(24 * 60 * 60),
+        }
+    }
+}
+
+impl SessionPolicy {
+    pub fn is_expired(&self, session: &SessionData, now: u64) -> bool {
+        now.saturating_sub(session.last_seen) > self.idle_timeout.as_secs()
+            || now.saturating_sub(session.created_at) > self.max_lifetime.as_secs()
+    }
+
+    /// When the session ends however active it is; no token for it outlives this.
+    pub fn ends_at(&self, session: &SessionData) -> u64 {
+        session.created_at + self.max_lifetime.as_secs()
+    }
+}
+
+/// Storage for logged in sessions, keyed by bearer token.
+/// Implementations must be cheap to share between Actix workers.
+pub trait SessionBackend: Send + Sync {
+    fn get(&self, token: &str) -> Result<Option<SessionData>, SessionStoreError>;
+
+    /// Inserts a new session or replaces the existing one for `token`.
+    fn insert(&self, token: &str, session: SessionData) -> Result<(), SessionStoreError>;
+
+    /// Removes the session, returning whether it existed.
+    fn remove(&self, token: &str) -> Result<bool, SessionStoreError>;
+
+    /// Records activity on the session so the idle timeout starts over.
+    fn touch(&self, token: &str, now: u64) -> Result<(), SessionStoreError>;
+
+    /// Stores an MFL cookie the session renewed by logging in again, unless the
+    /// store already holds one issued later (`issued_at` is in Unix milliseconds).
+    fn update_cookie(&self, token: &str, cookie: &str, issued_at: u64) -> Result<(), SessionStoreError>;
+
+    /// Drops every session `policy` considers expired, returning how many were removed.
+    /// Each one is remembered as expired for as long as its tokens could still be presented.
+    fn remove_expired(&self, policy: &SessionPolicy, now: u64) -> Result<usize, SessionStoreError>;
+
+    /// Whether `token` named a session that was swept for expiring, as opposed to
+    /// one that was logged out or never existed.
+    fn was_expired(&self, token: &str) -> Result<bool, SessionStoreError>;
+
+    /// How many sessions are stored, including expired ones the sweeper hasn't removed yet.
+    fn count(&self) -> Result<usize, SessionStoreError>;
+}
+
+// Shared handle to whichever backend was configured at startup
+pub type SessionStore = Arc<dyn SessionBackend>;
+
+/// Runs `call` against `store` on Actix's blocking thread pool. Backends may wait on
+/// disk I/O, which must not stall the worker serving other requests.
+pub async fn blocking<T, F>(store: &SessionStore, call: F) -> Result<T, SessionStoreError>
+where
+    F: FnOnce(&dyn SessionBackend) -> Result<T, SessionStoreError> + Send + 'static,
+    T: Send + 'static,
+{
+    let store = store.clone();
+    actix_web::web::block(move || call(store.as_ref())).await
+        .map_err(|_| SessionStoreError::Blocking)?
+}
+
+/// Periodically removes expired sessions from `store`. Must be called from within the Actix runtime.
+pub fn spawn_session_sweeper(store: SessionStore, policy: SessionPolicy, every: Duration) {
+    actix_web::rt::spawn(async move {
+        let mut interval = actix_web::rt::time::interval(every);
+        loop {
+            interval.tick().await;
+            match blocking(&store, move |store| store.remove_expired(&policy, unix_now())).await {
+                Ok(0) => {}
+                Ok(removed) => tracing::info!("Session sweeper removed {} expired sessions", removed),
+                Err(e) => tracing::error!("Session sweeper failed: {}", e),
+            }
+        }
+    });
+}
+
+// --- In-memory backend ---
+
+/// Keeps sessions in a process-local map. Sessions are lost on restart.
+#[derive(Default)]
+pub struct InMemorySessionStore {
+    sessions: Mutex<HashMap<String, SessionData>>,
+    // Swept sessions, until their tokens have expired too
+    expired: Mutex<HashMap<String, u64>>,
+}
+
+impl InMemorySessionStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn clear(&self) -> Result<(), SessionStoreError> {
+        self.sessions.lock().map_err(|_| SessionStoreError::LockPoisoned)?.clear();
+        Ok(())
+    }
+}
+
+impl SessionBackend for InMemorySessionStore {
+    fn get(&self, token: &str) -> Result<Option<SessionData>, SessionStoreError> {
+        let sessions = self.sessions.lock().map_err(|_| SessionStoreError::LockPoisoned)?;
+        Ok(sessions.get(token).cloned())
+    }
+
+    fn insert(&self, token: &str, session: SessionData) -> Result<(), SessionStoreError> {
+        let mut sessions = self.sessions.lock().map_err(|_| SessionStoreError::LockPoisoned)?;
+        sessions.insert(token.to_string(), session);
+        Ok(())
+    }
+
+    fn remove(&self, token: &str) -> Result<bool, SessionStoreError> {
+        let mut sessions = self.sessions.lock().map_err(|_| SessionStoreError::LockPoisoned)?;
+        Ok(sessions.remove(token).is_some())
+    }
+
+    fn touch(&self, token: &str, now: u64) -> Result<(), SessionStoreError> {
+        let mut sessions = self.sessions.lock().map_err(|_| SessionStoreError::LockPoisoned)?;
+        if let Some(session) = sessions.get_mut(token) {
+            session.last_seen = now;
+        }
+        Ok(())
+    }
+
+    fn update_cookie(&self, token: &str, cookie: &str, issued_at: u64) -> Result<(), SessionStoreError> {
+        let sessions = self.sessions.lock().map_err(|_| SessionStoreError::LockPoisoned)?;
+        if let Some(session) = sessions.get(token).filter(|session| session.mfl_api.mfl_user_id_cookie_issued_at() < issued_at) {
+            session.mfl_api.restore_mfl_user_id_cookie(Some(cookie.to_string()), issued_at);
+        }
+        Ok(())
+    }
+
+    fn remove_expired(&self, policy: &SessionPolicy, now: u64) -> Result<usize, SessionStoreError> {
+        let mut sessions = self.sessions.lock().map_err(|_| SessionStoreError::LockPoisoned)?;
+        let mut expired = self.expired.lock().map_err(|_| SessionStoreError::LockPoisoned)?;
+        expired.retain(|_, until| *until >= now);
+        let before = sessions.len();
+        sessions.retain(|token, session| {
+            let keep = !policy.is_expired(session, now);
+            if !keep {
+                expired.insert(token.clone(), policy.ends_at(session));
+            }
+            keep
+        });
+        Ok(before - sessions.len())
+    }
+
+    fn was_expired(&self, token: &str) -> Result<bool, SessionStoreError> {
+        Ok(self.expired.lock().map_err(|_| SessionStoreError::LockPoisoned)?.contains_key(token))
+    }
+
+    fn count(&self) -> Result<usize, SessionStoreError> {
+        Ok(self.sessions.lock().map_err(|_| SessionStoreError::LockPoisoned)?.len())
+    }
+}
+
+// --- SQLite backend ---
+
+/// Persists sessions to a SQLite database so they survive restarts.
+/// The MFL cookie and any credential vault are encrypted with `cipher` before they are written.
+/// Restored sessions are kept in memory so the `MflApi` (and its league host cache)
+/// is shared between requests, just like with the in-memory backend.
+/// Replicas can share the database: every lookup checks it for logouts and new MFL cookies.
+pub struct SqliteSessionStore {
+    conn: Mutex<Connection>,
+    cipher: SecretCipher,
+    live: InMemorySessionStore,
+    // Used to rebuild the MflApi of restored sessions
+    client_config: MflClientConfig,
+}
+
+impl SqliteSessionStore {
+    pub fn open<P: AsRef<Path>>(path: P, cipher: SecretCipher) -> Result<Self, SessionStoreError> {
+        Self::with_connection(Connection::open(path)?, cipher)
+    }
+
+    pub fn with_connection(conn: Connection, cipher: SecretCipher) -> Result<Self, SessionStoreError> {
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS sessions (
+                token            TEXT PRIMARY KEY,
+                league_id        TEXT NOT NULL,
+                year             TEXT NOT NULL,
+                franchise_id     TEXT,
+                leagues          TEXT NOT NULL,
+                mfl_cookie       TEXT,
+                created_at       INTEGER NOT NULL DEFAULT 0,
+                last_seen        INTEGER NOT NULL DEFAULT 0,
+                credential_vault TEXT,
+                role             TEXT,
+                cookie_issued_at INTEGER NOT NULL DEFAULT 0,
+                mfl_username     TEXT
+            );
+            CREATE TABLE IF NOT EXISTS expired_sessions (
+                token TEXT PRIMARY KEY,
+                until INTEGER NOT NULL
+            );",
+        )?;
+        Ok(SqliteSessionStore {
+            conn: Mutex::new(conn),
+            cipher,
+            live: InMemorySessionStore::new(),
+            client_config: MflClientConfig::default(),
+        })
+    }
+
+    pub fn with_client_config(mut self, client_config: MflClientConfig) -> Self {
+        self.client_config = client_config;
+        self
+    }
+
+    fn load(&self, token: &str) -> Result<Option<SessionData>, SessionStoreError> {
+        let conn = self.conn.lock().map_err(|_| SessionStoreError::LockPoisoned)?;
+        let row = conn.query_row(
+            "SELECT league_id, year, franchise_id, leagues, mfl_cookie, created_at, last_seen, credential_vault, role,
+                    cookie_issued_at, mfl_username
+             FROM sessions WHERE token = ?1",
+            params![token],
+            |row| Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, Option<String>>(2)?,
+                row.get::<_, String>(3)?,
+                row.get::<_, Option<String>>(4)?,
+                row.get::<_, u64>(5)?,
+                row.get::<_, u64>(6)?,
+                row.get::<_, Option<String>>(7)?,
+                row.get::<_, Option<String>>(8)?,
+                row.get::<_, u64>(9)?,
+                row.get::<_, Option<String>>(10)?,
+            )),
+        ).optional()?;
+        drop(conn);
+
+        let Some((league_id, year, franchise_id, leagues, mfl_cookie, created_at, last_seen, credential_vault, role, cookie_issued_at, mfl_username)) = row else {
+            return Ok(None);
+        };
+
+        let mut mfl_api = MflApi::with_config(year.clone(), &self.client_config)?;
+        let mfl_cookie = mfl_cookie.map(|sealed| self.cipher.decrypt(&sealed)).transpose()?;
+        mfl_api.restore_mfl_user_id_cookie(mfl_cookie, cookie_issued_at);
+        mfl_api.set_mfl_username(mfl_username);
+        // The vault's fields are already encrypted with our cipher
+        let vault = credential_vault
+            .map(|json| serde_json::from_str(&json))
+            .transpose()?
+            .map(|sealed| CredentialVault::from_sealed(self.cipher.clone(), sealed));
+        mfl_api.set_credential_vault(vault);
+        let leagues: Vec<UserLeague> = serde_json::from_str(&leagues)?;
+        let role = role.as_deref().and_then(Role::parse).unwrap_or_default();
+
+        Ok(Some(SessionData {
+            mfl_api,
+            league_id,
+            year,
+            franchise_id,
+            role,
+            leagues,
+            created_at,
+            last_seen,
+        }))
+    }
+
+    // The parts of a session other processes sharing the database may change:
+    // (mfl_cookie, cookie_issued_at, last_seen)
+    fn shared_state(&self, token: &str) -> Result<Option<(Option<String>, u64, u64)>, SessionStoreError> {
+        let conn = self.conn.lock().map_err(|_| SessionStoreError::LockPoisoned)?;
+        let row = conn.query_row(
+            "SELECT mfl_cookie, cookie_issued_at, last_seen FROM sessions WHERE token = ?1",
+            params![token],
+            |row| Ok((row.get::<_, Option<String>>(0)?, row.get::<_, u64>(1)?, row.get::<_, u64>(2)?)),
+        ).optional()?;
+        drop(conn);
+
+        row.map(|(mfl_cookie, cookie_issued_at, last_seen)| {
+            let mfl_cookie = mfl_cookie.map(|sealed| self.cipher.decrypt(&sealed)).transpose()?;
+            Ok((mfl_cookie, cookie_issued_at, last_seen))
+        }).transpose()
+    }
+}
+
+impl SessionBackend for SqliteSessionStore {
+    fn get(&self, token: &str) -> Result<Option<SessionData>, SessionStoreError> {
+        if let Some(mut session) = self.live.get(token)? {
+            // Another replica may have ended the session, used it or stored a new MFL cookie since
+            let Some((mfl_cookie, cookie_issued_at, last_seen)) = self.shared_state(token)? else {
+                self.live.remove(token)?;
+                return Ok(None);
+            };
+            // Only a newer cookie: ours may have been renewed since the database was written
+            if mfl_cookie.is_some() && cookie_issued_at > session.mfl_api.mfl_user_id_cookie_issued_at() {
+                session.mfl_api.restore_mfl_user_id_cookie(mfl_cookie, cookie_issued_at);
+            }
+            session.last_seen = session.last_seen.max(last_seen);
+            return Ok(Some(session));
+        }
+        // Not seen since startup: restore it from disk
+        let session = self.load(token)?;
+        if let Some(session) = &session {
+            self.live.insert(token, session.clone())?;
+        }
+        Ok(session)
+    }
+
+    fn insert(&self, token: &str, session: SessionData) -> Result<(), SessionStoreError> {
+        let mfl_cookie = session.mfl_api.mfl_user_id_cookie()
+            .map(|cookie| self.cipher.encrypt(&cookie))
+            .transpose()?;
+        let credential_vault = session.mfl_api.credential_vault()
+            .map(|vault| serde_json::to_string(vault.sealed()))
+            .transpose()?;
+        let leagues = serde_json::to_string(&session.leagues)?;
+
+        let conn = self.conn.lock().map_err(|_| SessionStoreError::LockPoisoned)?;
+        conn.execute(
+            "INSERT OR REPLACE INTO sessions
+                (token, league_id, year, franchise_id, leagues, mfl_cookie, created_at, last_seen, credential_vault, role,
+                 cookie_issued_at, mfl_username)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)",
+            params![token, session.league_id, session.year, session.franchise_id, leagues, mfl_cookie,
+                    session.created_at, session.last_seen, credential_vault, session.role.as_str(),
+                    session.mfl_api.mfl_user_id_cookie_issued_at(), session.mfl_api.mfl_username()],
+        )?;
+        drop(conn);
+
+        self.live.insert(token, session)
+    }
+
+    fn remove(&self, token: &str) -> Result<bool, SessionStoreError> {
+        self.live.remove(token)?;
+        let conn = self.conn.lock().map_err(|_| SessionStoreError::LockPoisoned)?;
+        let removed = conn.execute("DELETE FROM sessions WHERE token = ?1", params![token])?;
+        Ok(removed > 0)
+    }
+
+    fn touch(&self, token: &str, now: u64) -> Result<(), SessionStoreError> {
+        self.live.touch(token, now)?;
+        let conn = self.conn.lock().map_err(|_| SessionStoreError::LockPoisoned)?;
+        conn.execute("UPDATE sessions SET last_seen = ?2 WHERE token = ?1", params![token, now])?;
+        Ok(())
+    }
+
+    fn update_cookie(&self, token: &str, cookie: &str, issued_at: u64) -> Result<(), SessionStoreError> {
+        self.live.update_cookie(token, cookie, issued_at)?;
+        let sealed = self.cipher.encrypt(cookie)?;
+        let conn = self.conn.lock().map_err(|_| SessionStoreError::LockPoisoned)?;
+        conn.execute(
+            "UPDATE sessions SET mfl_cookie = ?2, cookie_issued_at = ?3 WHERE token = ?1 AND cookie_issued_at < ?3",
+            params![token, sealed, issued_at],
+        )?;
+        Ok(())
+    }
+
+    fn remove_expired(&self, policy: &SessionPolicy, now: u64) -> Result<usize, SessionStoreError> {
+        self.live.remove_expired(policy, now)?;
+        let conn = self.conn.lock().map_err(|_| SessionStoreError::LockPoisoned)?;
+        let idle_since = now.saturating_sub(policy.idle_timeout.as_secs());
+        let created_before = now.saturating_sub(policy.max_lifetime.as_secs());
+        conn.execute("DELETE FROM expired_sessions WHERE until < ?1", params![now])?;
+        conn.execute(
+            "INSERT OR REPLACE INTO expired_sessions (token, until)
+             SELECT token, created_at + ?3 FROM sessions WHERE last_seen < ?1 OR created_at < ?2",
+            params![idle_since, created_before, policy.max_lifetime.as_secs()],
+        )?;
+        let removed = conn.execute(
+            "DELETE FROM sessions WHERE last_seen < ?1 OR created_at < ?2",
+            params![idle_since, created_before],
+        )?;
+        Ok(removed)
+    }
+
+    fn was_expired(&self, token: &str) -> Result<bool, SessionStoreError> {
+        let conn = self.conn.lock().map_err(|_| SessionStoreError::LockPoisoned)?;
+        let found = conn.query_row("SELECT 1 FROM expired_sessions WHERE token = ?1", params![token], |_| Ok(()))
+            .optional()?;
+        Ok(found.is_some())
+    }
+
+    // Sessions on disk, whether or not they were used since startup
+    fn count(&self) -> Result<usize, SessionStoreError> {
+        let conn = self.conn.lock().map_err(|_| SessionStoreError::LockPoisoned)?;
+        let count: i64 = conn.query_row("SELECT COUNT(*) FROM sessions", [], |row| row.get(0))?;
+        Ok(count as usize)
+    }
+}
@@ -0,0 +1,175 @@
+// src/circuit_breaker.rs
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use utoipa::ToSchema;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum CircuitState {
+    // Requests flow normally
+    Closed,
+    // MFL kept failing: requests fail fast until `open_for` has passed
+    Open,
+    // Cool-down over: one probe request decides whether to close again
+    HalfOpen,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct CircuitSnapshot {
+    pub state: CircuitState,
+    pub consecutive_failures: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub retry_after_secs: Option<u64>,
+}
+
+#[derive(Debug)]
+struct BreakerState {
+    state: CircuitState,
+    consecutive_failures: u32,
+    opened_at: Option<Instant>,
+    probe_in_flight: bool,
+}
+
+/// Stops calling MFL for a while once it has failed `failure_threshold` times in a row.
+#[derive(Debug)]
+pub struct CircuitBreaker {
+    failure_threshold: u32,
+    open_for: Duration,
+    inner: Mutex<BreakerState>,
+}
+
+static SHARED_BREAKER: Lazy<Arc<CircuitBreaker>> = Lazy::new(|| Arc::new(CircuitBreaker::default()));
+
+impl Default for CircuitBreaker {
+    fn default() -> Self {
+        CircuitBreaker::new(5, Duration::from_secs(30))
+    }
+}
+
+impl CircuitBreaker {
+    pub fn new(failure_threshold: u32, open_for: Duration) -> Self {
+        CircuitBreaker {
+            failure_threshold: failure_threshold.max(1),
+            open_for,
+            inner: Mutex::new(BreakerState {
+                state: CircuitState::Closed,
+                consecutive_failures: 0,
+                opened_at: None,
+                probe_in_flight: false,
+            }),
+        }
+    }
+
+    /// The process-wide breaker used by `MflApi` unless another one is supplied.
+    pub fn shared() -> Arc<CircuitBreaker> {
+        SHARED_BREAKER.clone()
+    }
+
+    /// Asks to send a request. Returns `Err(retry_after)` while the circuit is open.
+    pub fn allow_request(&self) -> Result<CallPermit<'_>, Duration> {
+        let permit = |probe| CallPermit { breaker: self, probe, settled: false };
+        let Ok(mut inner) = self.inner.lock() else {
+            return Ok(permit(false));
+        };
+        match inner.state {
+            CircuitState::Closed => Ok(permit(false)),
+            CircuitState::Open => {
+                let elapsed = inner.opened_at.map(|at| at.elapsed()).unwrap_or(self.open_for);
+                if elapsed >= self.open_for {
+                    inner.state = CircuitState::HalfOpen;
+                    inner.probe_in_flight = true;
+                    Ok(permit(true))
+                } else {
+                    Err(self.open_for - elapsed)
+                }
+            }
+            CircuitState::HalfOpen if !inner.probe_in_flight => {
+                inner.probe_in_flight = true;
+                Ok(permit(true))
+            }
+            // Only the probe goes through until we know how it went
+            CircuitState::HalfOpen => Err(Duration::from_secs(1)),
+        }
+    }
+
+    pub fn record_success(&self) {
+        if let Ok(mut inner) = self.inner.lock() {
+            if inner.state != CircuitState::Closed {
+                tracing::info!("MFL circuit breaker closed");
+            }
+            inner.state = CircuitState::Closed;
+            inner.consecutive_failures = 0;
+            inner.opened_at = None;
+            inner.probe_in_flight = false;
+        }
+    }
+
+    pub fn record_failure(&self) {
+        if let Ok(mut inner) = self.inner.lock() {
+            inner.consecutive_failures = inner.consecutive_failures.saturating_add(1);
+            let trip = inner.state == CircuitState::HalfOpen
+                || inner.consecutive_failures >= self.failure_threshold;
+            if trip && inner.state != CircuitState::Open {
+                tracing::warn!("MFL circuit breaker opened after {} consecutive failures", inner.consecutive_failures);
+            }
+            if trip {
+                inner.state = CircuitState::Open;
+                inner.opened_at = Some(Instant::now());
+                inner.probe_in_flight = false;
+            }
+        }
+    }
+
+    pub fn state(&self) -> CircuitState {
+        self.snapshot().state
+    }
+
+    pub fn snapshot(&self) -> CircuitSnapshot {
+        let Ok(inner) = self.inner.lock() else {
+            return CircuitSnapshot { state: CircuitState::Closed, consecutive_failures: 0, retry_after_secs: None };
+        };
+        let retry_after_secs = match (inner.state, inner.opened_at) {
+            (CircuitState::Open, Some(at)) => Some(self.open_for.saturating_sub(at.elapsed()).as_secs()),
+            _ => None,
+        };
+        CircuitSnapshot {
+            state: inner.state,
+            consecutive_failures: inner.consecutive_failures,
+            retry_after_secs,
+        }
+    }
+}
+
+/// Permission to send one request. Report how it went with `success` or `failure`.
+/// A half-open probe dropped without either, e.g. because the handler was cancelled,
+/// counts as a failure so the breaker isn't left waiting on it forever.
+#[must_use = "report the outcome with success() or failure()"]
+#[derive(Debug)]
+pub struct CallPermit<'a> {
+    breaker: &'a CircuitBreaker,
+    probe: bool,
+    settled: bool,
+}
+
+impl CallPermit<'_> {
+    pub fn success(mut self) {
+        self.settled = true;
+        self.breaker.record_success();
+    }
+
+    pub fn failure(mut self) {
+        self.settled = true;
+        self.breaker.record_failure();
+    }
+}
+
+impl Drop for CallPermit<'_> {
+    fn drop(&mut self) {
+        if self.probe && !self.settled {
+            tracing::warn!("MFL circuit breaker probe was abandoned, treating it as failed");
+            self.breaker.record_failure();
+        }
+    }
+}
@@ -0,0 +1,364 @@
+// src/config.rs
+use std::collections::BTreeMap;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use crate::crypto::SecretCipher;
+use crate::export_cache::CachePolicy;
+use crate::mfl_api::DEFAULT_MFL_BASE_URL;
+
+#[derive(Error, Debug)]
+pub enum ConfigError {
+    #[error("Could not read config file {path}: {source}")]
+    Read { path: PathBuf, source: std::io::Error },
+
+    #[error("Config file {path} is not valid: {source}")]
+    Parse { path: PathBuf, source: toml::de::Error },
+
+    #[error("Environment variable {name} is not valid: {message}")]
+    Env { name: String, message: String },
+
+    #[error("Invalid configuration:\n  - {}", .0.join("\n  - "))]
+    Invalid(Vec<String>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    Text,
+    Json,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SessionBackendKind {
+    Memory,
+    Sqlite,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FixtureModeSetting {
+    Record,
+    Replay,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ServerSettings {
+    pub bind: String,
+    // Defaults to one per CPU core
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub workers: Option<usize>,
+    // "*" allows any origin
+    pub cors_origins: Vec<String>,
+}
+
+impl Default for ServerSettings {
+    fn default() -> Self {
+        ServerSettings {
+            bind: "0.0.0.0:8080".to_string(),
+            workers: None,
+            cors_origins: vec!["*".to_string()],
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LogSettings {
+    pub format: LogFormat,
+    // tracing EnvFilter directives, e.g. "info" or "mfl_manager_lib=debug"; RUST_LOG still wins
+    pub level: String,
+}
+
+impl Default for LogSettings {
+    fn default() -> Self {
+        LogSettings { format: LogFormat::Text, level: "info".to_string() }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MflSettings {
+    pub base_url: String,
+    pub connect_timeout_secs: u64,
+    pub read_timeout_secs: u64,
+    pub max_retries: u32,
+    // Keeps the last good exports here to serve while MFL is down
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub offline_store_path: Option<String>,
+    // Stored replies older than this are deleted
+    pub offline_store_retention_days: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fixture_mode: Option<FixtureModeSetting>,
+    pub fixture_dir: String,
+}
+
+impl Default for MflSettings {
+    fn default() -> Self {
+        MflSettings {
+            base_url: DEFAULT_MFL_BASE_URL.to_string(),
+            connect_timeout_secs: 5,
+            read_timeout_secs: 20,
+            max_retries: 2,
+            offline_store_path: None,
+            offline_store_retention_days: 30,
+            fixture_mode: None,
+            fixture_dir: "fixtures".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CacheSettings {
+    // Seconds per export TYPE; 0 turns caching off for that export
+    pub ttl_secs: BTreeMap<String, u64>,
+}
+
+impl Default for CacheSettings {
+    fn default() -> Self {
+        CacheSettings {
+            ttl_secs: CachePolicy::default().ttls()
+                .map(|(export, ttl)| (export.to_string(), ttl.as_secs()))
+                .collect(),
+        }
+    }
+}
+
+impl CacheSettings {
+    pub fn policy(&self) -> CachePolicy {
+        self.ttl_secs.iter().fold(CachePolicy::empty(), |policy, (export, secs)| {
+            policy.with_ttl(export, Duration::from_secs(*secs))
+        })
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SessionSettings {
+    pub backend: SessionBackendKind,
+    pub db_path: String,
+    pub idle_timeout_secs: u64,
+    pub max_lifetime_secs: u64,
+    // Base64 AES-256 key; prefer SESSION_ENCRYPTION_KEY over putting it in the file
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub encryption_key: Option<String>,
+    // JWKS file of HS256 keys signing access tokens, see tokens::TokenKeys. Replicas must share
+    // it (and a sqlite session store); without it each process signs with a key of its own.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub jwks_path: Option<String>,
+}
+
+impl Default for SessionSettings {
+    fn default() -> Self {
+        SessionSettings {
+            backend: SessionBackendKind::Memory,
+            db_path: "sessions.db".to_string(),
+            idle_timeout_secs: 2 * 60 * 60,
+            max_lifetime_secs: 24 * 60 * 60,
+            encryption_key: None,
+            jwks_path: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ApiKeySettings {
+    // API keys are off unless this is set; they need session.encryption_key for their MFL credentials
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub db_path: Option<String>,
+}
+
+/// Settings for the server binary. Built from defaults, then the TOML file,
+/// then environment variables, each layer overriding the one before.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AppConfig {
+    pub server: ServerSettings,
+    pub log: LogSettings,
+    pub mfl: MflSettings,
+    pub cache: CacheSettings,
+    pub session: SessionSettings,
+    pub api_keys: ApiKeySettings,
+}
+
+// Config file used when neither --config nor MFL_MANAGER_CONFIG names one
+const DEFAULT_CONFIG_FILE: &str = "mfl_manager.toml";
+
+impl AppConfig {
+    /// Loads `path` (or MFL_MANAGER_CONFIG, or ./mfl_manager.toml if present),
+    /// applies environment overrides and validates the result.
+    pub fn load(path: Option<&Path>) -> Result<Self, ConfigError> {
+        let path = path.map(Path::to_path_buf)
+            .or_else(|| std::env::var_os("MFL_MANAGER_CONFIG").map(PathBuf::from));
+        let mut config = match path {
+            Some(path) => Self::from_file(&path)?,
+            None if Path::new(DEFAULT_CONFIG_FILE).exists() => Self::from_file(Path::new(DEFAULT_CONFIG_FILE))?,
+            None => AppConfig::default(),
+        };
+        config.apply_env(|name| std::env::var(name).ok())?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    pub fn from_file(path: &Path) -> Result<Self, ConfigError> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|source| ConfigError::Read { path: path.to_path_buf(), source })?;
+        Self::from_toml(&text).map_err(|source| ConfigError::Parse { path: path.to_path_buf(), source })
+    }
+
+    pub fn from_toml(text: &str) -> Result<Self, toml::de::Error> {
+        let mut config: AppConfig = toml::from_str(text)?;
+        // A [cache.ttl_secs] table only overrides the exports it names
+        for (export, secs) in CacheSettings::default().ttl_secs {
+            config.cache.ttl_secs.entry(export).or_insert(secs);
+        }
+        Ok(config)
+    }
+
+    /// Overrides settings from environment variables, read through `var`.
+    pub fn apply_env<F>(&mut self, var: F) -> Result<(), ConfigError>
+    where
+        F: Fn(&str) -> Option<String>,
+    {
+        let parse = |name: &str, value: String| -> Result<u64, ConfigError> {
+            value.trim().parse().map_err(|_| ConfigError::Env {
+                name: name.to_string(),
+                message: format!("expected a whole number, got '{}'", value),
+            })
+        };
+
+        if let Some(value) = var("BIND_ADDR") { self.server.bind = value; }
+        if let Some(value) = var("WORKERS") { self.server.workers = Some(parse("WORKERS", value)? as usize); }
+        if let Some(value) = var("CORS_ORIGINS") {
+            self.server.cors_origins = value.split(',').map(str::trim).filter(|origin| !origin.is_empty()).map(String::from).collect();
+        }
+        if let Some(value) = var("LOG_FORMAT") {
+            self.log.format = match value.trim().to_ascii_lowercase().as_str() {
+                "text" => LogFormat::Text,
+                "json" => LogFormat::Json,
+                other => return Err(ConfigError::Env { name: "LOG_FORMAT".to_string(), message: format!("expected 'text' or 'json', got '{}'", other) }),
+            };
+        }
+        if let Some(value) = var("LOG_LEVEL") { self.log.level = value; }
+
+        if let Some(value) = var("MFL_BASE_URL") { self.mfl.base_url = value; }
+        if let Some(value) = var("MFL_CONNECT_TIMEOUT_SECS") { self.mfl.connect_timeout_secs = parse("MFL_CONNECT_TIMEOUT_SECS", value)?; }
+        if let Some(value) = var("MFL_READ_TIMEOUT_SECS") { self.mfl.read_timeout_secs = parse("MFL_READ_TIMEOUT_SECS", value)?; }
+        if let Some(value) = var("MFL_MAX_RETRIES") { self.mfl.max_retries = parse("MFL_MAX_RETRIES", value)? as u32; }
+        if let Some(value) = var("OFFLINE_STORE_PATH") { self.mfl.offline_store_path = Some(value); }
+        if let Some(value) = var("OFFLINE_STORE_RETENTION_DAYS") { self.mfl.offline_store_retention_days = parse("OFFLINE_STORE_RETENTION_DAYS", value)?; }
+        if let Some(value) = var("MFL_FIXTURE_MODE") {
+            self.mfl.fixture_mode = match value.trim().to_ascii_lowercase().as_str() {
+                "record" => Some(FixtureModeSetting::Record),
+                "replay" => Some(FixtureModeSetting::Replay),
+                other => return Err(ConfigError::Env { name: "MFL_FIXTURE_MODE".to_string(), message: format!("expected 'record' or 'replay', got '{}'", other) }),
+            };
+        }
+        if let Some(value) = var("MFL_FIXTURE_DIR") { self.mfl.fixture_dir = value; }
+
+        // CACHE_TTLS=players=86400,liveScoring=10
+        if let Some(value) = var("CACHE_TTLS") {
+            for pair in value.split(',').map(str::trim).filter(|pair| !pair.is_empty()) {
+                let (export, secs) = pair.split_once('=').ok_or_else(|| ConfigError::Env {
+                    name: "CACHE_TTLS".to_string(),
+                    message: format!("expected EXPORT=SECONDS, got '{}'", pair),
+                })?;
+                self.cache.ttl_secs.insert(export.trim().to_string(), parse("CACHE_TTLS", secs.to_string())?);
+            }
+        }
+
+        if let Some(value) = var("SESSION_BACKEND") {
+            self.session.backend = match value.trim().to_ascii_lowercase().as_str() {
+                "memory" => SessionBackendKind::Memory,
+                "sqlite" => SessionBackendKind::Sqlite,
+                other => return Err(ConfigError::Env { name: "SESSION_BACKEND".to_string(), message: format!("expected 'memory' or 'sqlite', got '{}'", other) }),
+            };
+        }
+        if let Some(value) = var("SESSION_DB_PATH") { self.session.db_path = value; }
+        if let Some(value) = var("SESSION_IDLE_TIMEOUT_SECS") { self.session.idle_timeout_secs = parse("SESSION_IDLE_TIMEOUT_SECS", value)?; }
+        if let Some(value) = var("SESSION_MAX_LIFETIME_SECS") { self.session.max_lifetime_secs = parse("SESSION_MAX_LIFETIME_SECS", value)?; }
+        if let Some(value) = var("SESSION_ENCRYPTION_KEY") { self.session.encryption_key = Some(value); }
+        if let Some(value) = var("SESSION_JWKS_PATH") { self.session.jwks_path = Some(value); }
+
+        if let Some(value) = var("API_KEYS_DB_PATH") { self.api_keys.db_path = Some(value); }
+        Ok(())
+    }
+
+    /// Checks every setting, reporting all problems at once.
+    pub fn validate(&mut self) -> Result<(), ConfigError> {
+        let mut problems = Vec::new();
+
+        if self.server.bind.parse::<SocketAddr>().is_err() {
+            problems.push(format!("server.bind '{}' must be an address like 0.0.0.0:8080", self.server.bind));
+        }
+        if self.server.workers == Some(0) {
+            problems.push("server.workers must be at least 1".to_string());
+        }
+        if self.server.cors_origins.is_empty() {
+            problems.push("server.cors_origins must list at least one origin, or \"*\"".to_string());
+        }
+        for origin in &self.server.cors_origins {
+            if origin != "*" && !is_origin(origin) {
+                problems.push(format!("server.cors_origins entry '{}' must be \"*\" or a scheme and host like https://app.example.com", origin));
+            }
+        }
+
+        self.mfl.base_url = self.mfl.base_url.trim_end_matches('/').to_string();
+        if !is_http_url(&self.mfl.base_url) {
+            problems.push(format!("mfl.base_url '{}' must be an http(s) URL", self.mfl.base_url));
+        }
+        if self.mfl.connect_timeout_secs == 0 {
+            problems.push("mfl.connect_timeout_secs must be greater than 0".to_string());
+        }
+        if self.mfl.read_timeout_secs == 0 {
+            problems.push("mfl.read_timeout_secs must be greater than 0".to_string());
+        }
+        if self.mfl.offline_store_retention_days == 0 {
+            problems.push("mfl.offline_store_retention_days must be greater than 0".to_string());
+        }
+
+        if self.cache.ttl_secs.keys().any(|export| export.trim().is_empty()) {
+            problems.push("cache.ttl_secs keys must be export TYPE names".to_string());
+        }
+
+        if self.session.idle_timeout_secs == 0 || self.session.max_lifetime_secs == 0 {
+            problems.push("session.idle_timeout_secs and session.max_lifetime_secs must be greater than 0".to_string());
+        }
+        if self.session.backend == SessionBackendKind::Sqlite && self.session.encryption_key.is_none() {
+            problems.push("session.backend = \"sqlite\" needs an encryption key (SESSION_ENCRYPTION_KEY)".to_string());
+        }
+        if self.api_keys.db_path.is_some() && self.session.encryption_key.is_none() {
+            problems.push("api_keys.db_path needs an encryption key (SESSION_ENCRYPTION_KEY) to store MFL credentials".to_string());
+        }
+        if let Some(Err(e)) = self.session.encryption_key.as_deref().map(SecretCipher::from_base64_key) {
+            problems.push(format!("session.encryption_key is not usable: {}", e));
+        }
+
+        if problems.is_empty() { Ok(()) } else { Err(ConfigError::Invalid(problems)) }
+    }
+
+    /// The effective configuration as TOML, with secrets masked.
+    pub fn to_toml_redacted(&self) -> String {
+        let mut shown = self.clone();
+        if shown.session.encryption_key.is_some() {
+            shown.session.encryption_key = Some("<redacted>".to_string());
+        }
+        toml::to_string_pretty(&shown).unwrap_or_else(|e| format!("# could not render config: {}", e))
+    }
+}
+
+fn is_http_url(value: &str) -> bool {
+    reqwest::Url::parse(value)
+        .is_ok_and(|url| matches!(url.scheme(), "http" | "https") && url.host_str().is_some())
+}
+
+// Browsers send origins as scheme://host[:port], without a path
+fn is_origin(value: &str) -> bool {
+    is_http_url(value) && reqwest::Url::parse(value).is_ok_and(|url| url.path() == "/" && !value.ends_with('/'))
+}
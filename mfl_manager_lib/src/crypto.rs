@@ -0,0 +1,118 @@
+// src/crypto.rs
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+// AES-GCM uses a 96 bit nonce, stored in front of the ciphertext
+const NONCE_LEN: usize = 12;
+
+#[derive(Error, Debug)]
+pub enum CryptoError {
+    #[error("Encryption key must be 32 bytes encoded as base64")]
+    InvalidKey,
+
+    #[error("Failed to encrypt secret")]
+    EncryptFailed,
+
+    #[error("Failed to decrypt secret: data is corrupt or was encrypted with another key")]
+    DecryptFailed,
+}
+
+/// Encrypts secrets we have to keep at rest (MFL cookies, credentials) with AES-256-GCM.
+/// Output is base64 of `nonce || ciphertext`.
+#[derive(Clone)]
+pub struct SecretCipher {
+    cipher: Aes256Gcm,
+}
+
+impl SecretCipher {
+    pub fn new(key: &[u8; 32]) -> Self {
+        SecretCipher {
+            cipher: Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(key)),
+        }
+    }
+
+    /// Builds a cipher from a base64 encoded 32 byte key, e.g. the output of `openssl rand -base64 32`.
+    pub fn from_base64_key(encoded: &str) -> Result<Self, CryptoError> {
+        let bytes = BASE64.decode(encoded.trim()).map_err(|_| CryptoError::InvalidKey)?;
+        let key: [u8; 32] = bytes.try_into().map_err(|_| CryptoError::InvalidKey)?;
+        Ok(SecretCipher::new(&key))
+    }
+
+    pub fn encrypt(&self, plaintext: &str) -> Result<String, CryptoError> {
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let ciphertext = self.cipher
+            .encrypt(&nonce, plaintext.as_bytes())
+            .map_err(|_| CryptoError::EncryptFailed)?;
+
+        let mut sealed = nonce.to_vec();
+        sealed.extend_from_slice(&ciphertext);
+        Ok(BASE64.encode(sealed))
+    }
+
+    pub fn decrypt(&self, sealed: &str) -> Result<String, CryptoError> {
+        let bytes = BASE64.decode(sealed).map_err(|_| CryptoError::DecryptFailed)?;
+        if bytes.len() < NONCE_LEN {
+            return Err(CryptoError::DecryptFailed);
+        }
+        let (nonce, ciphertext) = bytes.split_at(NONCE_LEN);
+        let plaintext = self.cipher
+            .decrypt(Nonce::from_slice(nonce), ciphertext)
+            .map_err(|_| CryptoError::DecryptFailed)?;
+        String::from_utf8(plaintext).map_err(|_| CryptoError::DecryptFailed)
+    }
+}
+
+// Never print key material
+impl std::fmt::Debug for SecretCipher {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("SecretCipher(..)")
+    }
+}
+
+/// MFL credentials encrypted at rest, as persisted alongside a session.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct SealedCredentials {
+    pub username: String,
+    pub password: String,
+}
+
+/// Opt-in store for a user's MFL credentials, used to log in again when MFL
+/// invalidates the session cookie. Credentials stay encrypted until `open` is called.
+#[derive(Clone)]
+pub struct CredentialVault {
+    cipher: SecretCipher,
+    sealed: SealedCredentials,
+}
+
+impl CredentialVault {
+    pub fn seal(cipher: SecretCipher, username: &str, password: &str) -> Result<Self, CryptoError> {
+        let sealed = SealedCredentials {
+            username: cipher.encrypt(username)?,
+            password: cipher.encrypt(password)?,
+        };
+        Ok(CredentialVault { cipher, sealed })
+    }
+
+    pub fn from_sealed(cipher: SecretCipher, sealed: SealedCredentials) -> Self {
+        CredentialVault { cipher, sealed }
+    }
+
+    pub fn sealed(&self) -> &SealedCredentials {
+        &self.sealed
+    }
+
+    /// Decrypts the credentials, returning `(username, password)`.
+    pub fn open(&self) -> Result<(String, String), CryptoError> {
+        Ok((self.cipher.decrypt(&self.sealed.username)?, self.cipher.decrypt(&self.sealed.password)?))
+    }
+}
+
+impl std::fmt::Debug for CredentialVault {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("CredentialVault(..)")
+    }
+}
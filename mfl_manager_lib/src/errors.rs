@@ -1,9 +1,13 @@
+use crate::api_keys::ApiKeyError;
+use crate::app_state::SessionStoreError;
 use crate::mfl_api::MflError;
+use crate::tokens::TokenError;
 // src/errors.rs
-use actix_web::{error::ResponseError, http::StatusCode, HttpResponse};
+use actix_web::{error::ResponseError, http::header::RETRY_AFTER, http::StatusCode, HttpResponse};
 use derive_more::Display;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use thiserror::Error;
+use utoipa::ToSchema;
 // *** USE thiserror::Error ***
 
 
@@ -22,6 +26,13 @@ pub enum ServiceError {
     #[display("Unauthorized: {}", _0)]
     Unauthorized(String), // NO #[source]
 
+    // Authenticated, but the session's role doesn't allow it
+    #[display("Forbidden: {}", _0)]
+    Forbidden(String),
+
+    #[display("Session expired: please log in again")]
+    SessionExpired,
+
     // MflError variants: The inner MflError IS the source. Mark it.
     #[display("MFL API Error: {}", _0)]
     MflApiError(#[source] MflError), // <<< ADD #[source] attribute here
@@ -31,39 +42,231 @@ pub enum ServiceError {
 
     #[display("Not Found: {}", _0)]
     NotFound(String), // NO #[source]
+
+    #[display("Session Store Error: {}", _0)]
+    SessionStoreError(#[source] SessionStoreError),
+
+    #[display("API Key Error: {}", _0)]
+    ApiKeyError(#[source] ApiKeyError),
+
+    #[display("Token Error: {}", _0)]
+    TokenError(#[source] TokenError),
+}
+
+
+
+
+/// Stable, machine-readable error codes. Clients should branch on these, not on `message`.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCode {
+    InternalError,
+    BadRequest,
+    Unauthorized,
+    Forbidden,
+    SessionExpired,
+    NotFound,
+    /// MFL rejected the username or password
+    LoginFailed,
+    /// MFL dropped the session and it could not be renewed; log in again
+    MflReauthenticationRequired,
+    /// MFL, or our limiter protecting it, asks us to slow down
+    MflRateLimited,
+    /// MFL is down, or the circuit breaker is open
+    MflUnavailable,
+    /// MFL did not answer in time
+    MflTimeout,
+    /// MFL failed or replied with something we could not use
+    MflBadGateway,
+    /// MFL refused a write, e.g. an empty message
+    MflImportRejected,
+    /// MFL says this account may not do that
+    MflPermissionDenied,
+}
+
+impl ErrorCode {
+    /// The code for errors that aren't ServiceErrors (bad JSON, unknown routes, ...).
+    pub fn for_status(status: StatusCode) -> Self {
+        match status {
+            StatusCode::UNAUTHORIZED => ErrorCode::Unauthorized,
+            StatusCode::FORBIDDEN => ErrorCode::Forbidden,
+            StatusCode::NOT_FOUND => ErrorCode::NotFound,
+            status if status.is_client_error() => ErrorCode::BadRequest,
+            _ => ErrorCode::InternalError,
+        }
+    }
+}
+
+/// What MFL said when a call to it failed.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, ToSchema)]
+pub struct UpstreamError {
+    /// MFL's HTTP status, if it answered at all
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status: Option<u16>,
+    /// MFL's reply or the transport error, shortened and with secrets redacted
+    pub details: String,
+}
+
+// Longest reply excerpt put into an error body
+const MAX_UPSTREAM_DETAILS: usize = 500;
+
+// `text` as it may be shown to clients: secrets redacted, cut short
+fn excerpt(text: &str) -> String {
+    let text = crate::redact::redact(text.trim());
+    match text.char_indices().nth(MAX_UPSTREAM_DETAILS) {
+        Some((end, _)) => format!("{}...", &text[..end]),
+        None => text.into_owned(),
+    }
+}
+
+impl UpstreamError {
+    fn new(status: Option<u16>, details: &str) -> Self {
+        UpstreamError { status, details: excerpt(details) }
+    }
+}
+
+/// The JSON body of every error response.
+#[derive(Serialize, Deserialize, Debug, ToSchema)]
+pub struct ErrorResponse {
+    pub code: ErrorCode,
+    pub message: String,
+    // Whether the same request may succeed later; see also retry_after_secs
+    pub retryable: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retry_after_secs: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub upstream: Option<UpstreamError>,
+    // Same as the X-Request-Id response header, for quoting in bug reports
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<String>,
 }
 
+// How an MFL failure is reported: our status, the code, and whether retrying may help
+fn classify_mfl_error(error: &MflError) -> (StatusCode, ErrorCode, bool) {
+    match error {
+        MflError::RateLimited { .. } => (StatusCode::TOO_MANY_REQUESTS, ErrorCode::MflRateLimited, true),
+        MflError::Unavailable { .. } => (StatusCode::SERVICE_UNAVAILABLE, ErrorCode::MflUnavailable, true),
+        // MFL dropped the session and we couldn't log in again: the client has to
+        MflError::ReauthenticationRequired(_) => (StatusCode::UNAUTHORIZED, ErrorCode::MflReauthenticationRequired, false),
+        MflError::LoginFailed(_) | MflError::LoginCookieNotFound => (StatusCode::UNAUTHORIZED, ErrorCode::LoginFailed, false),
+        MflError::Network(e) if e.is_timeout() => (StatusCode::GATEWAY_TIMEOUT, ErrorCode::MflTimeout, true),
+        MflError::Network(_) | MflError::RequestFailed(_) => (StatusCode::BAD_GATEWAY, ErrorCode::MflBadGateway, true),
+        MflError::ApiStatusError { status, .. } => match status.as_u16() {
+            429 => (StatusCode::TOO_MANY_REQUESTS, ErrorCode::MflRateLimited, true),
+            503 => (StatusCode::SERVICE_UNAVAILABLE, ErrorCode::MflUnavailable, true),
+            504 => (StatusCode::GATEWAY_TIMEOUT, ErrorCode::MflTimeout, true),
+            // Including 404s: our route exists, MFL's answer didn't
+            _ => (StatusCode::BAD_GATEWAY, ErrorCode::MflBadGateway, status.is_server_error()),
+        },
+        MflError::ImportRejected(_) => (StatusCode::UNPROCESSABLE_ENTITY, ErrorCode::MflImportRejected, false),
+        MflError::PermissionDenied(_) => (StatusCode::FORBIDDEN, ErrorCode::MflPermissionDenied, false),
+        MflError::JsonParse(_) | MflError::UnexpectedReply(_) | MflError::LeagueHostNotFound => {
+            (StatusCode::BAD_GATEWAY, ErrorCode::MflBadGateway, false)
+        }
+        MflError::Regex(_)
+        | MflError::InvalidHeaderValue(_)
+        | MflError::ClientInitializationFailed(_)
+        | MflError::FixtureNotFound(_) => (StatusCode::INTERNAL_SERVER_ERROR, ErrorCode::InternalError, false),
+    }
+}
+
+// The transport error with its causes, which reqwest keeps out of its own Display
+fn error_chain(error: &dyn std::error::Error) -> String {
+    let mut chain = error.to_string();
+    let mut source = error.source();
+    while let Some(cause) = source {
+        chain.push_str(": ");
+        chain.push_str(&cause.to_string());
+        source = cause.source();
+    }
+    chain
+}
+
+impl ServiceError {
+    fn mfl_error(&self) -> Option<&MflError> {
+        match self {
+            ServiceError::MflApiError(e) | ServiceError::MflLoginError(e) => Some(e),
+            _ => None,
+        }
+    }
+
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            ServiceError::InternalServerError => ErrorCode::InternalError,
+            ServiceError::BadRequest(_) => ErrorCode::BadRequest,
+            ServiceError::Unauthorized(_) => ErrorCode::Unauthorized,
+            ServiceError::Forbidden(_) => ErrorCode::Forbidden,
+            ServiceError::SessionExpired => ErrorCode::SessionExpired,
+            ServiceError::MflApiError(e) | ServiceError::MflLoginError(e) => classify_mfl_error(e).1,
+            ServiceError::NotFound(_) => ErrorCode::NotFound,
+            ServiceError::SessionStoreError(_) => ErrorCode::InternalError,
+            ServiceError::ApiKeyError(_) => ErrorCode::InternalError,
+            ServiceError::TokenError(_) => ErrorCode::InternalError,
+        }
+    }
 
+    /// Whether the same request may succeed if sent again later.
+    pub fn retryable(&self) -> bool {
+        self.mfl_error().is_some_and(|e| classify_mfl_error(e).2)
+    }
 
+    /// What MFL replied, for failures MFL caused.
+    pub fn upstream(&self) -> Option<UpstreamError> {
+        match self.mfl_error()? {
+            MflError::ApiStatusError { status, body } => Some(UpstreamError::new(Some(status.as_u16()), body)),
+            MflError::Network(e) => Some(UpstreamError::new(e.status().map(|status| status.as_u16()), &error_chain(e))),
+            MflError::JsonParse(e) => Some(UpstreamError::new(None, &e.to_string())),
+            MflError::RequestFailed(details)
+            | MflError::ImportRejected(details)
+            | MflError::PermissionDenied(details)
+            | MflError::UnexpectedReply(details)
+            | MflError::LoginFailed(details) => Some(UpstreamError::new(None, details)),
+            _ => None,
+        }
+    }
 
-#[derive(Serialize)]
-struct ErrorResponse {
-    error: String,
+    fn retry_after_secs(&self) -> Option<u64> {
+        match self.mfl_error()? {
+            MflError::RateLimited { retry_after } | MflError::Unavailable { retry_after } => Some(retry_after.as_secs().max(1)),
+            _ => None,
+        }
+    }
 }
 
-// --- ResponseError Implementation (Review status codes for MFL errors) ---
 impl ResponseError for ServiceError {
     fn status_code(&self) -> StatusCode {
-        match *self {
+        match self {
             ServiceError::InternalServerError => StatusCode::INTERNAL_SERVER_ERROR,
             ServiceError::BadRequest(_) => StatusCode::BAD_REQUEST,
             ServiceError::Unauthorized(_) => StatusCode::UNAUTHORIZED,
-            // Consider if 500/502 is better for MflApiError if the API itself fails
-            ServiceError::MflApiError(_) => StatusCode::INTERNAL_SERVER_ERROR,
-            // Consider if 500 is better for MflLoginError if the API itself fails
-            ServiceError::MflLoginError(_) => StatusCode::UNAUTHORIZED,
+            ServiceError::Forbidden(_) => StatusCode::FORBIDDEN,
+            ServiceError::SessionExpired => StatusCode::UNAUTHORIZED,
+            ServiceError::MflApiError(e) | ServiceError::MflLoginError(e) => classify_mfl_error(e).0,
             ServiceError::NotFound(_) => StatusCode::NOT_FOUND,
+            ServiceError::SessionStoreError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            ServiceError::ApiKeyError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            ServiceError::TokenError(_) => StatusCode::INTERNAL_SERVER_ERROR,
         }
     }
 
     fn error_response(&self) -> HttpResponse {
-        #[derive(Serialize)]
-        struct ErrorResponse {
-            message: String,
+        let mut response = HttpResponse::build(self.status_code());
+
+        // Tell throttled clients (or clients waiting out an MFL outage) when to come back
+        let retry_after_secs = self.retry_after_secs();
+        if let Some(secs) = retry_after_secs {
+            response.insert_header((RETRY_AFTER, secs.to_string()));
         }
-        // Use the Display implementation derived by derive_more
-        let message = self.to_string();
-        HttpResponse::build(self.status_code()).json(ErrorResponse { message })
+
+        response.json(ErrorResponse {
+            code: self.code(),
+            // The Display text may quote MFL's reply, which can hold the user's cookie
+            message: excerpt(&self.to_string()),
+            retryable: self.retryable(),
+            retry_after_secs,
+            upstream: self.upstream(),
+            request_id: crate::request_id::current(),
+        })
     }
 }
 
@@ -0,0 +1,291 @@
+// src/export_cache.rs
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use futures_util::lock::Mutex as AsyncMutex;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use utoipa::ToSchema;
+use crate::mfl_api::{MflError, MflReply};
+
+/// Where a reply came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum DataSource {
+    // Fetched from MFL for this request
+    Live,
+    // Served from the export cache within its TTL
+    Cache,
+    // MFL was unreachable, so this is the last good reply from the offline store
+    Stale,
+}
+
+/// How old a reply is and where it came from, reported to API clients.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct Freshness {
+    pub source: DataSource,
+    pub age_secs: u64,
+}
+
+impl Default for Freshness {
+    fn default() -> Self {
+        Freshness::live()
+    }
+}
+
+impl Freshness {
+    pub fn live() -> Self {
+        Freshness { source: DataSource::Live, age_secs: 0 }
+    }
+
+    /// Combines the freshness of two replies used for one response: the worse of each wins.
+    pub fn combine(self, other: Freshness) -> Freshness {
+        Freshness {
+            source: self.source.max(other.source),
+            age_secs: self.age_secs.max(other.age_secs),
+        }
+    }
+}
+
+/// Identifies one export reply: `TYPE`, league, year, the remaining query parameters
+/// and whose view of the league it is.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CacheKey {
+    pub export: String,
+    pub league_id: String,
+    pub year: String,
+    // Sorted `KEY=value` pairs other than TYPE, L and JSON
+    pub params: String,
+    // SHA-256 of the MFL username the export was fetched for, empty when logged out.
+    // MFL hides private leagues and owner-only data from other users
+    pub user: String,
+}
+
+impl CacheKey {
+    /// Builds the key for an export query string such as `TYPE=players&L=12345&PLAYERS=1,2&JSON=1`.
+    pub fn from_args(league_id: &str, year: &str, args: &str) -> Self {
+        let mut export = String::new();
+        let mut params: Vec<&str> = Vec::new();
+        for pair in args.split('&').filter(|pair| !pair.is_empty()) {
+            match pair.split_once('=') {
+                Some(("TYPE", value)) => export = value.to_string(),
+                Some(("L", _)) | Some(("JSON", _)) => {}
+                _ => params.push(pair),
+            }
+        }
+        params.sort_unstable();
+        CacheKey {
+            export,
+            league_id: league_id.to_string(),
+            year: year.to_string(),
+            params: params.join("&"),
+            user: String::new(),
+        }
+    }
+
+    /// The same key for the MFL user `username`.
+    pub fn for_user(mut self, username: Option<&str>) -> Self {
+        self.user = username
+            .map(|username| Sha256::digest(username.as_bytes()).iter().map(|byte| format!("{:02x}", byte)).collect())
+            .unwrap_or_default();
+        self
+    }
+}
+
+/// How long each export type stays fresh. Exports without a TTL are never cached.
+#[derive(Debug, Clone)]
+pub struct CachePolicy {
+    ttls: HashMap<String, Duration>,
+}
+
+impl Default for CachePolicy {
+    fn default() -> Self {
+        CachePolicy::empty()
+            .with_ttl("players", Duration::from_secs(24 * 60 * 60))
+            .with_ttl("league", Duration::from_secs(60 * 60))
+            .with_ttl("rosters", Duration::from_secs(5 * 60))
+            .with_ttl("freeAgents", Duration::from_secs(5 * 60))
+            .with_ttl("messageBoard", Duration::from_secs(60))
+            .with_ttl("messageBoardThread", Duration::from_secs(60))
+            .with_ttl("liveScoring", Duration::from_secs(15))
+    }
+}
+
+impl CachePolicy {
+    /// A policy that caches nothing until TTLs are added.
+    pub fn empty() -> Self {
+        CachePolicy { ttls: HashMap::new() }
+    }
+
+    /// Sets the TTL for an export `TYPE`; a zero TTL disables caching for it.
+    pub fn with_ttl(mut self, export: &str, ttl: Duration) -> Self {
+        if ttl.is_zero() {
+            self.ttls.remove(export);
+        } else {
+            self.ttls.insert(export.to_string(), ttl);
+        }
+        self
+    }
+
+    pub fn ttl_for(&self, export: &str) -> Option<Duration> {
+        self.ttls.get(export).copied()
+    }
+
+    /// Every export with a TTL.
+    pub fn ttls(&self) -> impl Iterator<Item = (&str, Duration)> {
+        self.ttls.iter().map(|(export, ttl)| (export.as_str(), *ttl))
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    pub entries: usize,
+}
+
+#[derive(Debug)]
+struct CacheEntry {
+    reply: MflReply,
+    fetched_at: Instant,
+}
+
+// Expired entries are swept once the cache grows past this many entries
+const SWEEP_THRESHOLD: usize = 4096;
+
+static SHARED_CACHE: Lazy<Arc<ExportCache>> = Lazy::new(|| Arc::new(ExportCache::new(CachePolicy::default())));
+
+/// In-memory cache of successful MFL export replies. Concurrent requests for the
+/// same key share a single upstream fetch.
+#[derive(Debug)]
+pub struct ExportCache {
+    policy: CachePolicy,
+    entries: Mutex<HashMap<CacheKey, CacheEntry>>,
+    // One gate per key being fetched; later callers wait on it instead of calling MFL
+    in_flight: Mutex<HashMap<CacheKey, Arc<AsyncMutex<()>>>>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+impl ExportCache {
+    pub fn new(policy: CachePolicy) -> Self {
+        ExportCache {
+            policy,
+            entries: Mutex::new(HashMap::new()),
+            in_flight: Mutex::new(HashMap::new()),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        }
+    }
+
+    /// The process-wide cache used by `MflApi` unless another one is supplied.
+    pub fn shared() -> Arc<ExportCache> {
+        SHARED_CACHE.clone()
+    }
+
+    pub fn policy(&self) -> &CachePolicy {
+        &self.policy
+    }
+
+    /// Returns the cached reply for `key` if it is still fresh, otherwise calls `fetch`
+    /// (at most once across concurrent callers) and caches a successful reply.
+    pub async fn get_or_fetch<F, Fut>(&self, key: &CacheKey, fetch: F) -> Result<MflReply, MflError>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<MflReply, MflError>>,
+    {
+        let Some(ttl) = self.policy.ttl_for(&key.export) else {
+            return fetch().await;
+        };
+        if let Some(reply) = self.lookup(key, ttl) {
+            self.hits.fetch_add(1, Ordering::Relaxed);
+            return Ok(reply);
+        }
+
+        let gate = self.gate_for(key);
+        let guard = gate.lock().await;
+        // Someone else may have fetched it while we waited
+        if let Some(reply) = self.lookup(key, ttl) {
+            self.hits.fetch_add(1, Ordering::Relaxed);
+            return Ok(reply);
+        }
+
+        self.misses.fetch_add(1, Ordering::Relaxed);
+        let result = fetch().await;
+        if let Ok(reply) = &result {
+            if reply.status.is_success() {
+                self.store(key, reply.clone());
+            }
+        }
+
+        drop(guard);
+        self.release_gate(key, &gate);
+        result
+    }
+
+    fn lookup(&self, key: &CacheKey, ttl: Duration) -> Option<MflReply> {
+        let entries = self.entries.lock().ok()?;
+        let entry = entries.get(key)?;
+        let age = entry.fetched_at.elapsed();
+        (age < ttl).then(|| MflReply {
+            freshness: Freshness { source: DataSource::Cache, age_secs: age.as_secs() },
+            ..entry.reply.clone()
+        })
+    }
+
+    fn store(&self, key: &CacheKey, reply: MflReply) {
+        let Ok(mut entries) = self.entries.lock() else {
+            return;
+        };
+        if entries.len() >= SWEEP_THRESHOLD {
+            let policy = &self.policy;
+            entries.retain(|key, entry| {
+                policy.ttl_for(&key.export).is_some_and(|ttl| entry.fetched_at.elapsed() < ttl)
+            });
+        }
+        entries.insert(key.clone(), CacheEntry { reply, fetched_at: Instant::now() });
+    }
+
+    fn gate_for(&self, key: &CacheKey) -> Arc<AsyncMutex<()>> {
+        match self.in_flight.lock() {
+            Ok(mut gates) => gates.entry(key.clone()).or_default().clone(),
+            // Without the map we can't merge requests, but we can still serve this one
+            Err(_) => Arc::new(AsyncMutex::new(())),
+        }
+    }
+
+    fn release_gate(&self, key: &CacheKey, gate: &Arc<AsyncMutex<()>>) {
+        if let Ok(mut gates) = self.in_flight.lock() {
+            // Only the map and this caller still hold it: nobody is waiting
+            if Arc::strong_count(gate) <= 2 {
+                gates.remove(key);
+            }
+        }
+    }
+
+    /// Drops cached replies, optionally only for one export `TYPE` and/or league.
+    /// Returns how many entries were removed.
+    pub fn invalidate(&self, export: Option<&str>, league_id: Option<&str>) -> usize {
+        let Ok(mut entries) = self.entries.lock() else {
+            return 0;
+        };
+        let before = entries.len();
+        entries.retain(|key, _| {
+            let export_matches = export.is_none_or(|export| key.export == export);
+            let league_matches = league_id.is_none_or(|league_id| key.league_id == league_id);
+            !(export_matches && league_matches)
+        });
+        before - entries.len()
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        CacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            entries: self.entries.lock().map(|entries| entries.len()).unwrap_or_default(),
+        }
+    }
+}
@@ -1,11 +1,28 @@
 use actix_web::{
+    body::EitherBody,
     dev::{self, Service, ServiceRequest, ServiceResponse, Transform},
-    Error, HttpMessage, web, // Added web for error::*
+    http::header::{HeaderName, HeaderValue},
+    Error, HttpMessage, HttpResponse, web, // Added web for error::*
     error // Added error for specific error types
 };
 use futures_util::future::LocalBoxFuture;
+use futures_util::TryFutureExt;
 use std::future::{ready, Ready};
-use crate::app_state::SessionStore; // Assuming this path is correct
+use std::rc::Rc;
+use std::sync::Arc;
+use std::time::Instant;
+use crate::api_keys::{self, ApiKeyStore, Permission, API_KEY_PREFIX};
+use crate::app_state::{self, unix_now, SessionBackend, SessionPolicy, SessionStore, SessionStoreError};
+use crate::errors::{ErrorCode, ErrorResponse, ServiceError};
+use crate::handler_models::{Role, SessionData, SessionId};
+use crate::mfl_api::MflApi;
+use crate::metrics::{Metrics, UNMATCHED_ROUTE};
+use crate::request_id::{self, RequestId, REQUEST_ID_HEADER};
+use crate::tokens::{TokenError, TokenKeys};
+use tracing::Instrument;
+
+/// Header API keys can be sent in instead of `Authorization: Bearer`.
+pub const API_KEY_HEADER: &str = "X-API-Key";
 
 // --- AuthMiddleware struct (No changes needed) ---
 pub struct AuthMiddleware;
@@ -17,20 +34,21 @@ where
     S::Future: 'static, // This bound is crucial!
     B: 'static,
 {
-    type Response = ServiceResponse<B>;
+    type Response = ServiceResponse<EitherBody<B>>;
     type Error = Error;
     type Transform = AuthMiddlewareService<S>;
     type InitError = ();
     type Future = Ready<Result<Self::Transform, Self::InitError>>;
 
     fn new_transform(&self, service: S) -> Self::Future {
-        ready(Ok(AuthMiddlewareService { service }))
+        ready(Ok(AuthMiddlewareService { service: Rc::new(service) }))
     }
 }
 
 // --- AuthMiddlewareService struct (No changes needed) ---
 pub struct AuthMiddlewareService<S> {
-    service: S,
+    // Shared with the future that finishes the request once the session is loaded
+    service: Rc<S>,
 }
 
 
@@ -41,7 +59,7 @@ where
     S::Future: 'static, // Crucial: Guarantees self.service.call returns a 'static future
     B: 'static,
 {
-    type Response = ServiceResponse<B>;
+    type Response = ServiceResponse<EitherBody<B>>;
     type Error = Error;
     // The future returned by *this* call method must be 'static
     type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
@@ -53,7 +71,7 @@ where
         if req.path() == "/login" {
             // Directly call the next service and pin its future.
             // self.service.call() returns S::Future, which is 'static.
-            return Box::pin(self.service.call(req));
+            return Box::pin(self.service.call(req).map_ok(ServiceResponse::map_into_left_body));
         }
 
         // --- Authentication Logic ---
@@ -63,7 +81,7 @@ where
             None => {
                 // If store is missing, return an error future immediately.
                 // This async block is simple and doesn't capture problematic references.
-                return Box::pin(async { Err(error::ErrorInternalServerError("Session store missing")) });
+                return reject(req, error::ErrorInternalServerError("Session store missing"));
             }
         };
 
@@ -72,35 +90,385 @@ where
             .headers()
             .get("Authorization")
             .and_then(|hv| hv.to_str().ok())
-            .and_then(|s| s.strip_prefix("Bearer ")); // Use strip_prefix for efficiency
-
-        let mut authenticated = false; // Flag to track if authentication succeeded
-
-        if let Some(token) = token_opt {
-            // Lock should be held only while reading
-            let sessions_lock = sessions.lock().unwrap(); // Handle potential poison error if needed
-            if let Some(session_data) = sessions_lock.get(token) {
-                // Clone the data *while* holding the lock
-                let session_data_clone = session_data.clone();
-                // Drop the lock explicitly or let it go out of scope here
-                drop(sessions_lock);
-
-                // Insert cloned data into request extensions
-                req.extensions_mut().insert(session_data_clone);
-                authenticated = true;
+            .and_then(|s| s.strip_prefix("Bearer ")) // Use strip_prefix for efficiency
+            .or_else(|| req.headers().get(API_KEY_HEADER).and_then(|hv| hv.to_str().ok()));
+
+        // API keys stand in for a session, limited to their league and permissions
+        if let Some(key) = token_opt.filter(|token| token.starts_with(API_KEY_PREFIX)) {
+            let key = key.to_string();
+            let service = self.service.clone();
+            return Box::pin(async move {
+                match api_key_session(&req, key).await {
+                    Ok(session_data) => {
+                        req.extensions_mut().insert(session_data);
+                        service.call(req).await.map(ServiceResponse::map_into_left_body)
+                    }
+                    Err(error) => reject(req, error.into()).await,
+                }
+            });
+        }
+
+        // Fall back to the default lifetimes if the app didn't register a policy
+        let policy = req.app_data::<web::Data<SessionPolicy>>()
+            .map(|data| *data.get_ref())
+            .unwrap_or_default();
+        let now = unix_now();
+
+        // Access tokens are JWTs; any replica holding the keys can check them
+        let claims = token_opt.map(|token| match req.app_data::<web::Data<TokenKeys>>() {
+            Some(keys) => keys.verify(token),
+            None => TokenKeys::process_default().verify(token),
+        });
+
+        let claims = match claims {
+            Some(Ok(claims)) => claims,
+            Some(Err(TokenError::Expired)) => return reject(req, ServiceError::SessionExpired.into()),
+            Some(Err(e)) => {
+                tracing::debug!("Rejected access token: {}", e);
+                return reject(req, ServiceError::Unauthorized("Invalid or missing token".to_string()).into());
+            }
+            // No token at all
+            None => return reject(req, ServiceError::Unauthorized("Invalid or missing token".to_string()).into()),
+        };
+
+        // The claims name the session; the (shared) store holds its MFL cookie.
+        // The store may wait on disk, so the lookup runs off the worker thread.
+        let service = self.service.clone();
+        Box::pin(async move {
+            let sid = claims.sid.clone();
+            let lookup = app_state::blocking(&sessions, move |store| lookup_session(store, &sid, &policy, now)).await;
+            let error = match lookup {
+                Ok(SessionLookup::Active(mut session_data)) => {
+                    claims.apply_to(&mut session_data);
+                    let mfl_api = session_data.mfl_api.clone();
+                    let issued_at = mfl_api.mfl_user_id_cookie_issued_at();
+
+                    // Insert the session into request extensions
+                    req.extensions_mut().insert(*session_data);
+                    req.extensions_mut().insert(SessionId(claims.sid.clone()));
+                    let response = service.call(req).await.map(ServiceResponse::map_into_left_body);
+                    save_renewed_cookie(&sessions, claims.sid, &mfl_api, issued_at).await;
+                    return response;
+                }
+                Ok(SessionLookup::Expired) => ServiceError::SessionExpired.into(),
+                // Validly signed, but the session was logged out or swept
+                Ok(SessionLookup::Unknown) => ServiceError::Unauthorized("Invalid or missing token".to_string()).into(),
+                Err(e) => {
+                    tracing::error!("Session store lookup failed: {}", e);
+                    error::ErrorInternalServerError("Session store unavailable")
+                }
+            };
+            reject(req, error).await
+        })
+    }
+}
+
+// What the store knows about the session a token names
+enum SessionLookup {
+    Active(Box<SessionData>),
+    Expired,
+    Unknown,
+}
+
+// Live sessions have their activity recorded. Expired ones are left for the sweeper,
+// which remembers them so their tokens keep getting the expired error.
+fn lookup_session(store: &dyn SessionBackend, sid: &str, policy: &SessionPolicy, now: u64) -> Result<SessionLookup, SessionStoreError> {
+    match store.get(sid)? {
+        Some(session_data) if policy.is_expired(&session_data, now) => Ok(SessionLookup::Expired),
+        Some(mut session_data) => {
+            if let Err(e) = store.touch(sid, now) {
+                tracing::warn!("Failed to record session activity: {}", e);
             }
-            // else: token exists but not found in store -> remain unauthenticated
+            session_data.last_seen = now;
+            Ok(SessionLookup::Active(Box::new(session_data)))
         }
-        // else: token is None -> remain unauthenticated
-
-        // --- Decision Point ---
-        if authenticated {
-            // If authenticated, call the next service. Its future is 'static.
-            Box::pin(self.service.call(req))
-        } else {
-            // If not authenticated, return an unauthorized error future.
-            // This async block is simple and doesn't capture 'self' problematically.
-            Box::pin(async { Err(error::ErrorUnauthorized("Unauthorized")) })
+        None if store.was_expired(sid)? => Ok(SessionLookup::Expired),
+        None => Ok(SessionLookup::Unknown),
+    }
+}
+
+// The handler may have logged in to MFL again. Store the new cookie so a restart or
+// another replica doesn't start over with the one MFL already rejected.
+async fn save_renewed_cookie(sessions: &SessionStore, sid: String, mfl_api: &MflApi, issued_before: u64) {
+    let issued_at = mfl_api.mfl_user_id_cookie_issued_at();
+    let Some(cookie) = mfl_api.mfl_user_id_cookie().filter(|_| issued_at > issued_before) else {
+        return;
+    };
+    if let Err(e) = app_state::blocking(sessions, move |store| store.update_cookie(&sid, &cookie, issued_at)).await {
+        tracing::error!("Failed to store the renewed MFL cookie: {}", e);
+    }
+}
+
+// The session an API key acts as, if the key is valid and may call this route.
+// Checking the key reads and writes SQLite, so it runs off the worker thread.
+async fn api_key_session(req: &ServiceRequest, key: String) -> Result<SessionData, ServiceError> {
+    let invalid = || ServiceError::Unauthorized("Invalid or missing token".to_string());
+    let store = req.app_data::<web::Data<ApiKeyStore>>().ok_or_else(invalid)?;
+    let authenticated = api_keys::blocking(store, move |store| store.authenticate(&key)).await.map_err(|e| {
+        tracing::error!("API key lookup failed: {}", e);
+        ServiceError::ApiKeyError(e)
+    })?.ok_or_else(invalid)?;
+
+    let info = &authenticated.info;
+    match Permission::required_for(req.method(), req.path()) {
+        Some(permission) if info.allows(permission) => {}
+        _ => {
+            tracing::warn!(api_key = %info.id, method = %req.method(), path = req.path(), "API key not allowed");
+            return Err(ServiceError::Forbidden("This API key does not allow this request".to_string()));
         }
     }
-}
\ No newline at end of file
+
+    let now = unix_now();
+    Ok(SessionData {
+        mfl_api: authenticated.mfl_api,
+        league_id: info.league_id.clone(),
+        year: info.year.clone(),
+        franchise_id: None,
+        role: Role::Owner,
+        leagues: Vec::new(),
+        created_at: info.created_at,
+        last_seen: now,
+    })
+}
+
+// Answers the request with `error` instead of failing the call, so the middleware outside
+// (request id, metrics) still gets a response to decorate. The error stays attached to it.
+fn reject<B>(req: ServiceRequest, error: Error) -> LocalBoxFuture<'static, Result<ServiceResponse<EitherBody<B>>, Error>>
+where
+    B: 'static,
+{
+    let response = req.error_response(error).map_into_right_body();
+    Box::pin(async move { Ok(response) })
+}
+
+/// Only lets sessions with at least `required` through; everyone else gets a 403.
+/// Must run inside AuthMiddleware, e.g. `#[delete("/admin/cache", wrap = "RequireRole::commissioner()")]`.
+pub struct RequireRole {
+    required: Role,
+}
+
+impl RequireRole {
+    pub fn new(required: Role) -> Self {
+        RequireRole { required }
+    }
+
+    pub fn commissioner() -> Self {
+        Self::new(Role::Commissioner)
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for RequireRole
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Transform = RequireRoleService<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(RequireRoleService { service, required: self.required }))
+    }
+}
+
+pub struct RequireRoleService<S> {
+    service: S,
+    required: Role,
+}
+
+impl<S, B> Service<ServiceRequest> for RequireRoleService<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    dev::forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let role = req.extensions().get::<SessionData>().map(|session| session.role);
+        match role {
+            Some(role) if role.allows(self.required) => {
+                Box::pin(self.service.call(req).map_ok(ServiceResponse::map_into_left_body))
+            }
+            Some(role) => {
+                tracing::warn!(role = role.as_str(), required = self.required.as_str(), "Role not allowed");
+                let error = ServiceError::Forbidden(format!("This needs the {} role", self.required.as_str()));
+                reject(req, error.into())
+            }
+            // No session: AuthMiddleware isn't in front of this route
+            None => reject(req, ServiceError::Unauthorized("Invalid or missing token".to_string()).into()),
+        }
+    }
+}
+
+/// Counts and times every request by route pattern and status, and counts the MFL
+/// errors handed back to clients. Wrap it outside AuthMiddleware so rejected requests are counted too.
+pub struct RequestMetrics {
+    metrics: Arc<Metrics>,
+}
+
+impl RequestMetrics {
+    pub fn new(metrics: Arc<Metrics>) -> Self {
+        RequestMetrics { metrics }
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for RequestMetrics
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Transform = RequestMetricsService<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(RequestMetricsService { service, metrics: self.metrics.clone() }))
+    }
+}
+
+pub struct RequestMetricsService<S> {
+    service: S,
+    metrics: Arc<Metrics>,
+}
+
+impl<S, B> Service<ServiceRequest> for RequestMetricsService<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    dev::forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        // The route pattern (/messages/{thread_id}), not the path, keeps the label set small
+        let route = req.match_pattern().unwrap_or_else(|| UNMATCHED_ROUTE.to_string());
+        let method = req.method().to_string();
+        let metrics = self.metrics.clone();
+        let started = Instant::now();
+        let response = self.service.call(req);
+
+        Box::pin(async move {
+            let result = response.await;
+            // Handler errors ride along on the response; middleware errors (e.g. a 401) come back as Err
+            let (status, error) = match &result {
+                Ok(res) => (res.status(), res.response().error()),
+                Err(e) => (e.as_response_error().status_code(), Some(e)),
+            };
+            if let Some(ServiceError::MflApiError(e) | ServiceError::MflLoginError(e)) =
+                error.and_then(|e| e.as_error::<ServiceError>())
+            {
+                metrics.count_mfl_error(e);
+            }
+            metrics.observe_request(&route, &method, status.as_u16(), started.elapsed());
+            result
+        })
+    }
+}
+
+/// Gives every request an id (the client's X-Request-Id if it sent a usable one) and runs
+/// it inside an `http_request` span carrying that id, so handler and MFL call logs can be
+/// tied back to it. The id is returned in the X-Request-Id header and in error bodies.
+/// Wrap it outermost.
+pub struct RequestTracing;
+
+impl<S, B> Transform<S, ServiceRequest> for RequestTracing
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Transform = RequestTracingService<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(RequestTracingService { service }))
+    }
+}
+
+pub struct RequestTracingService<S> {
+    service: S,
+}
+
+impl<S, B> Service<ServiceRequest> for RequestTracingService<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    dev::forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let request_id = RequestId::accept_or_generate(
+            req.headers().get(REQUEST_ID_HEADER).and_then(|value| value.to_str().ok())
+        );
+        req.extensions_mut().insert(request_id.clone());
+        let span = tracing::info_span!(
+            "http_request",
+            request_id = %request_id.as_str(),
+            method = %req.method(),
+            route = %req.match_pattern().unwrap_or_else(|| UNMATCHED_ROUTE.to_string()),
+            status = tracing::field::Empty,
+        );
+        let started = Instant::now();
+        let response = {
+            let _entered = span.enter();
+            request_id::sync_scope(&request_id, || self.service.call(req))
+        };
+
+        Box::pin(async move {
+            let result = request_id::scope(&request_id, response).instrument(span.clone()).await;
+            // The request is gone by now, so an Err can only be passed on (our own middleware answers instead)
+            let mut res = with_json_error_body(result?, &request_id);
+
+            if let Ok(value) = HeaderValue::from_str(request_id.as_str()) {
+                res.headers_mut().insert(HeaderName::from_static("x-request-id"), value);
+            }
+            span.record("status", res.status().as_u16());
+            span.in_scope(|| {
+                tracing::info!(status = res.status().as_u16(), duration_ms = started.elapsed().as_millis() as u64, "Request finished");
+            });
+            Ok(res)
+        })
+    }
+}
+
+// ServiceError bodies already carry the request id; give every other error (bad JSON, a
+// missing extractor, ...) the same JSON shape instead of actix's plain text
+fn with_json_error_body<B>(res: ServiceResponse<B>, request_id: &RequestId) -> ServiceResponse<EitherBody<B>> {
+    let message = match res.response().error() {
+        Some(error) if error.as_error::<ServiceError>().is_none() => error.to_string(),
+        _ => return res.map_into_left_body(),
+    };
+    let status = res.status();
+    let body = ErrorResponse {
+        code: ErrorCode::for_status(status),
+        message,
+        retryable: false,
+        retry_after_secs: None,
+        upstream: None,
+        request_id: Some(request_id.0.clone()),
+    };
+    res.into_response(HttpResponse::build(status).json(body)).map_into_right_body()
+}
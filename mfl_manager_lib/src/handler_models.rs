@@ -1,21 +1,113 @@
 // src/handler_models
+use std::collections::BTreeMap;
 use serde::{Deserialize, Serialize};
-use crate::mfl_api::MflApi;
+use utoipa::{IntoParams, ToSchema};
+use crate::api_keys::{ApiKeyInfo, Permission};
+use crate::circuit_breaker::CircuitSnapshot;
+use crate::export_cache::Freshness;
+use crate::mfl_api::{Abilities, MflApi, MyLeague};
 
-#[derive(Deserialize,Serialize)]
+#[derive(Deserialize,Serialize, ToSchema)]
 pub struct LoginRequest {
     pub username: String,
     pub password: String,
-    pub league_id: String,
+    /// Optional: defaults to the first league returned by MFL's myleagues export
+    #[serde(default)]
+    pub league_id: Option<String>,
     pub year: String,
+    /// Opt-in: keep the credentials (encrypted) so the server can log in to MFL again
+    /// when MFL expires the session cookie
+    #[serde(default)]
+    pub remember_credentials: bool,
+}
+
+/// What the user may do in the active league. Ordered, so a role allows everything below it.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Role {
+    #[default]
+    Owner,
+    Commissioner,
+}
+
+impl Role {
+    /// The role MFL's abilities export grants in a league. A commissioner may own a
+    /// franchise there too, so this never looks at the franchise.
+    pub fn from_abilities(abilities: &Abilities) -> Self {
+        if abilities.has("COMMISSIONER") {
+            Role::Commissioner
+        } else {
+            Role::Owner
+        }
+    }
+
+    pub fn allows(self, required: Role) -> bool {
+        self >= required
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Role::Owner => "owner",
+            Role::Commissioner => "commissioner",
+        }
+    }
+
+    pub fn parse(role: &str) -> Option<Self> {
+        match role {
+            "owner" => Some(Role::Owner),
+            "commissioner" => Some(Role::Commissioner),
+            _ => None,
+        }
+    }
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, ToSchema)]
 pub struct LoginResponse {
     pub token: String,
+    pub league_id: String,
+    pub franchise_id: Option<String>,
+    #[serde(default)]
+    pub role: Role,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+pub struct UserLeague {
+    pub league_id: String,
+    pub name: String,
+    pub franchise_id: Option<String>,
+    pub franchise_name: Option<String>,
+}
+
+impl From<MyLeague> for UserLeague {
+    fn from(league: MyLeague) -> Self {
+        UserLeague {
+            league_id: league.league_id,
+            name: league.name,
+            franchise_id: league.franchise_id,
+            franchise_name: league.franchise_name,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, ToSchema)]
+pub struct MeResponse {
+    pub year: String,
+    pub league_id: String,
+    pub franchise_id: Option<String>,
+    pub franchise_name: Option<String>,
+    pub role: Role,
+    pub leagues: Vec<UserLeague>,
+    // Only set by PUT /me/active-league: a token for the new league, as tokens carry their league
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, ToSchema)]
+pub struct ActiveLeagueRequest {
+    pub league_id: String,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, ToSchema)]
 pub struct PlayerResponse {
     pub id: String,
     pub name: String,
@@ -23,10 +115,153 @@ pub struct PlayerResponse {
     pub team: Option<String>
 }
 
+#[derive(Serialize, Deserialize, Debug, ToSchema)]
+pub struct FreeAgentsResponse {
+    pub players: Vec<PlayerResponse>,
+    /// Where the data came from and how old it is
+    #[serde(default)]
+    pub freshness: Freshness,
+}
+
+#[derive(Serialize, Deserialize, Debug, ToSchema)]
+pub struct MessageThreadSummary {
+    pub id: String,
+    pub subject: Option<String>,
+    pub last_post_time: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, ToSchema)]
+pub struct MessageBoardResponse {
+    pub threads: Vec<MessageThreadSummary>,
+    /// Where the data came from and how old it is
+    #[serde(default)]
+    pub freshness: Freshness,
+}
+
+#[derive(Serialize, Deserialize, Debug, ToSchema)]
+pub struct MessagePost {
+    pub id: String,
+    pub franchise_id: Option<String>,
+    pub post_time: Option<String>,
+    pub subject: Option<String>,
+    pub body: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, ToSchema)]
+pub struct MessageThreadResponse {
+    pub id: String,
+    pub subject: Option<String>,
+    pub posts: Vec<MessagePost>,
+    #[serde(default)]
+    pub freshness: Freshness,
+}
+
+#[derive(Serialize, Deserialize, Debug, ToSchema)]
+pub struct PostMessageRequest {
+    /// Required when starting a new thread, ignored by MFL for replies
+    #[serde(default)]
+    pub subject: String,
+    pub body: String,
+    /// Commissioners only: post as this franchise, e.g. "0003"
+    #[serde(default)]
+    pub franchise_id: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, ToSchema)]
+pub struct PostMessageResponse {
+    pub status: String,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct CreateApiKeyRequest {
+    /// What the key is for, e.g. "appsmith dashboard"
+    pub name: String,
+    pub permissions: Vec<Permission>,
+    /// The MFL account the key acts as; checked with MFL, then stored encrypted
+    pub username: String,
+    pub password: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, ToSchema)]
+pub struct CreateApiKeyResponse {
+    /// Send as `Authorization: Bearer <key>` or `X-API-Key: <key>`. Shown only once.
+    pub key: String,
+    pub api_key: ApiKeyInfo,
+}
+
+#[derive(Serialize, Deserialize, Debug, IntoParams)]
+pub struct CacheInvalidationQuery {
+    /// Limit invalidation to one export TYPE, e.g. "players"
+    pub export: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, ToSchema)]
+pub struct CacheInvalidationResponse {
+    pub removed: usize,
+}
+
+#[derive(Serialize, Debug, ToSchema)]
+pub struct HealthResponse {
+    pub status: String,
+    pub mfl_circuit: CircuitSnapshot,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum DependencyStatus {
+    Up,
+    Down,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+pub struct DependencyCheck {
+    pub status: DependencyStatus,
+    /// Whether the server is not ready while this is down
+    pub critical: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub latency_ms: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, ToSchema)]
+pub struct ReadinessResponse {
+    pub ready: bool,
+    /// Keyed by dependency: mfl, circuit_breaker, session_store, export_cache and offline_store (if configured)
+    pub checks: BTreeMap<String, DependencyCheck>,
+}
+
+// The session id (`sid` claim) of the current request's token, inserted by AuthMiddleware next to SessionData
+#[derive(Debug, Clone)]
+pub struct SessionId(pub String);
+
 #[derive(Debug, Clone)] // Added Clone
 pub struct SessionData {
     pub mfl_api: MflApi, // Store the initialized MflApi
-    //pub league_host: String, // and other session-specific data
-    pub league_id: String,
-    pub year: String
+    // League hosts are resolved and cached inside MflApi
+    pub league_id: String, // The active league
+    pub year: String,
+    pub franchise_id: Option<String>, // The user's franchise in the active league
+    pub role: Role, // From MFL's abilities in the active league, see Role::from_abilities
+    pub leagues: Vec<UserLeague>, // Every league the user owns a franchise in
+    pub created_at: u64, // Unix seconds, see app_state::SessionPolicy
+    pub last_seen: u64,
+}
+
+impl SessionData {
+    pub fn active_league(&self) -> Option<&UserLeague> {
+        self.leagues.iter().find(|league| league.league_id == self.league_id)
+    }
+
+    pub fn me(&self) -> MeResponse {
+        MeResponse {
+            year: self.year.clone(),
+            league_id: self.league_id.clone(),
+            franchise_id: self.franchise_id.clone(),
+            franchise_name: self.active_league().and_then(|league| league.franchise_name.clone()),
+            role: self.role,
+            leagues: self.leagues.clone(),
+            token: None,
+        }
+    }
 }
\ No newline at end of file
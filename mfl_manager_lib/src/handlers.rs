@@ -1,13 +1,36 @@
 // src/handlers.rs
-use actix_web::{get, post, web, HttpMessage, HttpRequest, HttpResponse, Responder, Result};
-use crate::handler_models::{LoginRequest, LoginResponse, SessionData};
-use crate::app_state::SessionStore;
+use actix_web::{delete, get, post, put, web, HttpMessage, HttpRequest, HttpResponse, HttpResponseBuilder, Responder, Result};
+use crate::handler_models::{ActiveLeagueRequest, CacheInvalidationQuery, CacheInvalidationResponse, CreateApiKeyRequest, CreateApiKeyResponse, DependencyStatus, FreeAgentsResponse, HealthResponse, LoginRequest, LoginResponse, MeResponse, MessageBoardResponse, MessageThreadResponse, PlayerResponse, PostMessageRequest, PostMessageResponse, ReadinessResponse, Role, SessionData, SessionId, UserLeague};
+use crate::api_keys::{self, ApiKeyInfo, ApiKeyStore};
+use crate::app_state::{self, SessionPolicy, SessionStore};
+use crate::crypto::SecretCipher;
+use crate::export_cache::Freshness;
+use crate::mfl_api::MflClientConfig;
+use crate::errors::ErrorResponse;
+use crate::handler_middleware::RequireRole;
+use crate::tokens::{AccessClaims, TokenKeys};
 
+#[utoipa::path(
+    tag = "session",
+    request_body = LoginRequest,
+    responses(
+        (status = 200, description = "Logged in to MFL; use the token as a bearer token", body = LoginResponse),
+        (status = 400, description = "No league_id given and the user has no leagues", body = ErrorResponse),
+        (status = 401, description = "MFL rejected the credentials", body = ErrorResponse),
+        (status = 404, description = "league_id is not one of the user's leagues", body = ErrorResponse),
+    )
+)]
 #[post("/login")]
+#[tracing::instrument(skip_all, fields(year = %req_body.year))]
 pub async fn login_handler(
     req_body: web::Json<LoginRequest>,
     sessions: web::Data<SessionStore>,
+    policy: Option<web::Data<SessionPolicy>>,
+    token_keys: Option<web::Data<TokenKeys>>,
+    vault_cipher: Option<web::Data<SecretCipher>>,
+    client_config: Option<web::Data<MflClientConfig>>,
 ) -> Result<impl Responder> {
+    use crate::crypto::CredentialVault;
     use crate::errors::ServiceError;
     use crate::mfl_api;
     // ... (rest of your login_handler logic from the previous example) ...
@@ -15,29 +38,98 @@ pub async fn login_handler(
     let login_data = req_body.into_inner();
 
     // Initialize MFL API.  Handle errors appropriately.
-    let mut api = mfl_api::MflApi::new(login_data.year.clone()).map_err(ServiceError::MflApiError)?;
+    let client_config = client_config.map(|config| config.get_ref().clone()).unwrap_or_default();
+    let mut api = mfl_api::MflApi::with_config(login_data.year.clone(), &client_config).map_err(ServiceError::MflApiError)?;
 
     api.login(&login_data.username, &login_data.password).await.map_err(ServiceError::MflLoginError)?;
 
-    //let league_host = api.get_league_host().await.map_err(ServiceError::MflApiError)?;
+    if login_data.remember_credentials {
+        let cipher = vault_cipher.ok_or_else(|| {
+            ServiceError::BadRequest("remember_credentials is not available: no encryption key is configured".to_string())
+        })?;
+        let vault = CredentialVault::seal(cipher.get_ref().clone(), &login_data.username, &login_data.password)
+            .map_err(|_| ServiceError::InternalServerError)?;
+        api.set_credential_vault(Some(vault));
+    }
 
-    // Create a new session.
-    let token = uuid::Uuid::new_v4().to_string();
+    // Discover every league (and franchise) this user owns.
+    let leagues: Vec<UserLeague> = api.get_my_leagues().await
+        .map_err(ServiceError::MflApiError)?
+        .into_iter()
+        .map(UserLeague::from)
+        .collect();
+
+    // Use the requested league, otherwise fall back to the user's first league.
+    let league_id = match login_data.league_id {
+        Some(league_id) if leagues.iter().any(|league| league.league_id == league_id) => league_id,
+        Some(league_id) => {
+            return Err(ServiceError::NotFound(format!("League {} is not one of your leagues", league_id)).into());
+        }
+        None => leagues.first().map(|league| league.league_id.clone()).ok_or_else(|| {
+            ServiceError::BadRequest("No leagues found for this user; supply a league_id".to_string())
+        })?,
+    };
+    let franchise_id = leagues.iter()
+        .find(|league| league.league_id == league_id)
+        .and_then(|league| league.franchise_id.clone());
+    let abilities = api.get_abilities(&league_id).await.map_err(ServiceError::MflApiError)?;
+    let role = Role::from_abilities(&abilities);
+
+    // Create a new session. Its id goes into the token; the session itself stays server side.
+    let sid = uuid::Uuid::new_v4().to_string();
+    let now = crate::app_state::unix_now();
 
     // Store session data.
     let session_data = SessionData {
         mfl_api: api,
-        //league_host,
-        league_id: login_data.league_id,
-        year: login_data.year
+        league_id: league_id.clone(),
+        year: login_data.year,
+        franchise_id: franchise_id.clone(),
+        role,
+        leagues,
+        created_at: now,
+        last_seen: now,
     };
 
-   sessions.lock().unwrap().insert(token.clone(), session_data);
+    let token = issue_token(token_keys.as_ref(), policy.as_ref(), &sid, &session_data)?;
+    app_state::blocking(&sessions, move |store| store.insert(&sid, session_data)).await
+        .map_err(ServiceError::SessionStoreError)?;
 
-    Ok(HttpResponse::Ok().json(LoginResponse { token }))
+    Ok(HttpResponse::Ok().json(LoginResponse { token, league_id, franchise_id, role }))
 }
 
+// Signs an access token for session `sid`, valid until the session's lifetime runs out.
+// Without registered keys, this process's own key signs it.
+fn issue_token(
+    token_keys: Option<&web::Data<TokenKeys>>,
+    policy: Option<&web::Data<SessionPolicy>>,
+    sid: &str,
+    session_data: &SessionData,
+) -> Result<String, crate::errors::ServiceError> {
+    let ends_at = policy.map(|policy| *policy.get_ref()).unwrap_or_default().ends_at(session_data);
+    let now = crate::app_state::unix_now();
+    let ttl = std::time::Duration::from_secs(ends_at.saturating_sub(now));
+    let claims = AccessClaims::for_session(sid, session_data, now, ttl);
+    token_keys.map(|keys| keys.get_ref()).unwrap_or_else(|| TokenKeys::process_default())
+        .issue(&claims)
+        .map_err(crate::errors::ServiceError::TokenError)
+}
+
+#[utoipa::path(
+    tag = "players",
+    params(("position" = String, Path, description = "MFL position code, e.g. WR")),
+    responses(
+        (status = 200, description = "Free agents at the position in the active league", body = FreeAgentsResponse,
+            headers(("X-Data-Source" = String, description = "live, cache or stale"), ("Age" = u64, description = "Age of the data in seconds"))),
+        (status = 401, description = "Missing, invalid or expired session", body = ErrorResponse),
+        (status = 502, description = "MFL failed or sent a reply we could not use", body = ErrorResponse),
+        (status = 503, description = "MFL is unavailable", body = ErrorResponse),
+        (status = 504, description = "MFL did not answer in time", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = []))
+)]
 #[get("/free-agents/{position}")]
+#[tracing::instrument(skip_all, fields(position = %position))]
 pub async fn get_free_agents_handler(
     position: web::Path<String>,
     req: HttpRequest,
@@ -45,39 +137,39 @@ pub async fn get_free_agents_handler(
     use crate::errors::ServiceError;
     // Import the specific model structs you provided
     use crate::mfl_api::{PlayersPlayers, PlayersPlayer};
-    use crate::handler_models::PlayerResponse; // Your response model
 
     // --- Existing Setup ---
     let session_data = req.extensions().get::<SessionData>().ok_or_else(|| {
         ServiceError::Unauthorized("Unauthorized: Session data missing or invalid".to_string())
     })?.clone();
     let position_str = position.into_inner();
-    log::info!("get_free_agents_handler position:{}", position_str);
+    tracing::info!(league_id = %session_data.league_id, "Listing free agents");
     
     let free_agents = session_data.mfl_api.get_free_agents(
         &session_data.league_id,
-        //&session_data.league_host,
         Some(&position_str)
     ).await.map_err(ServiceError::MflApiError)?; // Assuming get_free_agents returns Vec<{id: String}> or similar
-    let player_ids = free_agents
+    let mut freshness = free_agents.freshness;
+    let player_ids = free_agents.data
         .iter()
         .map(|player| player.id.as_str()) // Assuming free_agents elements have an 'id' field
         .collect::<Vec<&str>>()
         .join(",");
 
     if player_ids.is_empty() {
-        let empty_response: Vec<PlayerResponse> = Vec::new();
-        return Ok(HttpResponse::Ok().json(empty_response));
+        let empty_response = FreeAgentsResponse { players: Vec::new(), freshness };
+        return Ok(with_freshness(HttpResponse::Ok(), freshness).json(empty_response));
     }
     // --- End Setup ---
 
     // --- Call get_players ---
     // Assume session_data.mfl_api.get_players directly returns Result<PlayersPlayers, _>
-    let players_data: PlayersPlayers = session_data.mfl_api.get_players(
+    let players = session_data.mfl_api.get_players(
         &session_data.league_id,
-        //&session_data.league_host,
         &player_ids
     ).await.map_err(ServiceError::MflApiError)?;
+    freshness = freshness.combine(players.freshness);
+    let players_data: PlayersPlayers = players.data;
     // Now 'players_data' is the PlayersPlayers struct instance
     // --- End Call ---
 
@@ -90,7 +182,7 @@ pub async fn get_free_agents_handler(
     // Iterate over the BORROWED list (&Vec<PlayersPlayer>)
     // .into_iter() on a borrow yields references to items (&PlayersPlayer)
     let response_players: Vec<PlayerResponse> = players_list
-        .into_iter()
+        .iter()
         // Closure now correctly takes a reference to PlayersPlayer
         .map(|player: &PlayersPlayer| {
             // Map fields from &PlayersPlayer -> PlayerResponse
@@ -108,10 +200,605 @@ pub async fn get_free_agents_handler(
         .collect();
     // --- End Transformation ---
 
-    Ok(HttpResponse::Ok().json(response_players))
+    Ok(with_freshness(HttpResponse::Ok(), freshness).json(FreeAgentsResponse { players: response_players, freshness }))
+}
+
+// Tells clients whether data came from MFL, the cache or the offline store, and its age in seconds
+fn with_freshness(mut builder: HttpResponseBuilder, freshness: Freshness) -> HttpResponseBuilder {
+    use actix_web::http::header::AGE;
+    use crate::export_cache::DataSource;
+
+    let source = match freshness.source {
+        DataSource::Live => "live",
+        DataSource::Cache => "cache",
+        DataSource::Stale => "stale",
+    };
+    builder
+        .insert_header(("X-Data-Source", source))
+        .insert_header((AGE, freshness.age_secs.to_string()));
+    builder
+}
+
+// The franchise a post is made as: the user's own unless the commissioner names another
+fn acting_franchise<'a>(session_data: &SessionData, franchise_id: Option<&'a str>) -> Result<Option<&'a str>, crate::errors::ServiceError> {
+    use crate::errors::ServiceError;
+
+    let Some(franchise_id) = franchise_id else {
+        return Ok(None);
+    };
+    if !session_data.role.allows(Role::Commissioner) {
+        return Err(ServiceError::Forbidden("Only the commissioner can act for another franchise".to_string()));
+    }
+    if franchise_id.len() != 4 || !franchise_id.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(ServiceError::BadRequest(format!("'{}' is not a franchise id; expected four digits such as 0001", franchise_id)));
+    }
+    tracing::info!(league_id = %session_data.league_id, franchise_id, "Acting for franchise");
+    Ok(Some(franchise_id))
+}
+
+#[utoipa::path(
+    tag = "session",
+    responses(
+        (status = 204, description = "Session ended"),
+        (status = 401, description = "Missing, invalid or expired session", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = []))
+)]
+#[post("/logout")]
+#[tracing::instrument(skip_all)]
+pub async fn logout_handler(
+    req: HttpRequest,
+    sessions: web::Data<SessionStore>,
+) -> Result<impl Responder> {
+    use crate::errors::ServiceError;
+
+    let sid = req.extensions().get::<SessionId>().ok_or_else(|| {
+        ServiceError::Unauthorized("Unauthorized: Session data missing or invalid".to_string())
+    })?.clone();
+
+    // Every token naming this session stops working
+    app_state::blocking(&sessions, move |store| store.remove(&sid.0)).await
+        .map_err(ServiceError::SessionStoreError)?;
+    tracing::info!("Session removed");
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+/// Swaps the current token for a new one. The session keeps its absolute lifetime,
+/// so refreshing never extends it; the old token stops working immediately.
+#[utoipa::path(
+    tag = "session",
+    responses(
+        (status = 200, description = "A new token; the old one no longer works", body = LoginResponse),
+        (status = 401, description = "Missing, invalid or expired session", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = []))
+)]
+#[post("/session/refresh")]
+#[tracing::instrument(skip_all)]
+pub async fn refresh_session_handler(
+    req: HttpRequest,
+    sessions: web::Data<SessionStore>,
+    policy: Option<web::Data<SessionPolicy>>,
+    token_keys: Option<web::Data<TokenKeys>>,
+) -> Result<impl Responder> {
+    use crate::errors::ServiceError;
+
+    let old_sid = req.extensions().get::<SessionId>().ok_or_else(|| {
+        ServiceError::Unauthorized("Unauthorized: Session data missing or invalid".to_string())
+    })?.clone();
+    let mut session_data = req.extensions().get::<SessionData>().ok_or_else(|| {
+        ServiceError::Unauthorized("Unauthorized: Session data missing or invalid".to_string())
+    })?.clone();
+
+    // A new session id, so tokens for the old one stop working
+    let sid = uuid::Uuid::new_v4().to_string();
+    session_data.last_seen = crate::app_state::unix_now();
+    let league_id = session_data.league_id.clone();
+    let franchise_id = session_data.franchise_id.clone();
+    let role = session_data.role;
+
+    let token = issue_token(token_keys.as_ref(), policy.as_ref(), &sid, &session_data)?;
+    app_state::blocking(&sessions, move |store| {
+        store.insert(&sid, session_data)?;
+        store.remove(&old_sid.0).map(|_| ())
+    }).await.map_err(ServiceError::SessionStoreError)?;
+
+    Ok(HttpResponse::Ok().json(LoginResponse { token, league_id, franchise_id, role }))
+}
+
+#[utoipa::path(
+    tag = "session",
+    responses(
+        (status = 200, description = "The logged in user's leagues and active league", body = MeResponse),
+        (status = 401, description = "Missing, invalid or expired session", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = []))
+)]
+#[get("/me")]
+#[tracing::instrument(skip_all)]
+pub async fn get_me_handler(
+    req: HttpRequest,
+) -> Result<impl Responder> {
+    use crate::errors::ServiceError;
+
+    let session_data = req.extensions().get::<SessionData>().ok_or_else(|| {
+        ServiceError::Unauthorized("Unauthorized: Session data missing or invalid".to_string())
+    })?.clone();
+
+    Ok(HttpResponse::Ok().json(session_data.me()))
+}
+
+#[utoipa::path(
+    tag = "session",
+    request_body = ActiveLeagueRequest,
+    responses(
+        (status = 200, description = "Active league switched; use the returned token for it", body = MeResponse),
+        (status = 401, description = "Missing, invalid or expired session", body = ErrorResponse),
+        (status = 404, description = "Not one of the user's leagues", body = ErrorResponse),
+        (status = 502, description = "MFL failed to report the user's abilities in the league", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = []))
+)]
+#[put("/me/active-league")]
+#[tracing::instrument(skip_all)]
+pub async fn set_active_league_handler(
+    req_body: web::Json<ActiveLeagueRequest>,
+    req: HttpRequest,
+    sessions: web::Data<SessionStore>,
+    policy: Option<web::Data<SessionPolicy>>,
+    token_keys: Option<web::Data<TokenKeys>>,
+) -> Result<impl Responder> {
+    use crate::errors::ServiceError;
+
+    let sid = req.extensions().get::<SessionId>().ok_or_else(|| {
+        ServiceError::Unauthorized("Unauthorized: Session data missing or invalid".to_string())
+    })?.clone();
+    let league_id = req_body.into_inner().league_id;
+
+    let stored_sid = sid.0.clone();
+    let mut session_data = app_state::blocking(&sessions, move |store| store.get(&stored_sid)).await
+        .map_err(ServiceError::SessionStoreError)?
+        .ok_or_else(|| ServiceError::Unauthorized("Unauthorized: Session data missing or invalid".to_string()))?;
+
+    let franchise_id = session_data.leagues.iter()
+        .find(|league| league.league_id == league_id)
+        .map(|league| league.franchise_id.clone())
+        .ok_or_else(|| ServiceError::NotFound(format!("League {} is not one of your leagues", league_id)))?;
+
+    tracing::info!(league_id = %league_id, "Switching active league");
+    let abilities = session_data.mfl_api.get_abilities(&league_id).await.map_err(ServiceError::MflApiError)?;
+    session_data.league_id = league_id;
+    session_data.role = Role::from_abilities(&abilities);
+    session_data.franchise_id = franchise_id;
+    let mut me = session_data.me();
+    // The current token keeps acting for its own league; the new one is for this league
+    me.token = Some(issue_token(token_keys.as_ref(), policy.as_ref(), &sid.0, &session_data)?);
+    app_state::blocking(&sessions, move |store| store.insert(&sid.0, session_data)).await
+        .map_err(ServiceError::SessionStoreError)?;
+
+    Ok(HttpResponse::Ok().json(me))
+}
+
+#[utoipa::path(
+    tag = "messages",
+    responses(
+        (status = 200, description = "Threads on the active league's message board", body = MessageBoardResponse),
+        (status = 401, description = "Missing, invalid or expired session", body = ErrorResponse),
+        (status = 502, description = "MFL failed or sent a reply we could not use", body = ErrorResponse),
+        (status = 503, description = "MFL is unavailable", body = ErrorResponse),
+        (status = 504, description = "MFL did not answer in time", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = []))
+)]
+#[get("/messages")]
+#[tracing::instrument(skip_all)]
+pub async fn get_message_board_handler(
+    req: HttpRequest,
+) -> Result<impl Responder> {
+    use crate::errors::ServiceError;
+    use crate::handler_models::MessageThreadSummary;
+
+    let session_data = req.extensions().get::<SessionData>().ok_or_else(|| {
+        ServiceError::Unauthorized("Unauthorized: Session data missing or invalid".to_string())
+    })?.clone();
+    tracing::info!(league_id = %session_data.league_id, "Reading message board");
+
+    let message_board = session_data.mfl_api.get_message_board(
+        &session_data.league_id
+    ).await.map_err(ServiceError::MflApiError)?;
+
+    let threads = message_board.data.thread
+        .into_iter()
+        .map(|thread| MessageThreadSummary {
+            id: thread.id,
+            subject: thread.subject,
+            last_post_time: thread.last_post_time,
+        })
+        .collect();
+
+    let freshness = message_board.freshness;
+    Ok(with_freshness(HttpResponse::Ok(), freshness).json(MessageBoardResponse { threads, freshness }))
+}
+
+#[utoipa::path(
+    tag = "messages",
+    params(("thread_id" = String, Path, description = "MFL message board thread id")),
+    responses(
+        (status = 200, description = "Posts in the thread", body = MessageThreadResponse),
+        (status = 401, description = "Missing, invalid or expired session", body = ErrorResponse),
+        (status = 502, description = "MFL failed or sent a reply we could not use", body = ErrorResponse),
+        (status = 503, description = "MFL is unavailable", body = ErrorResponse),
+        (status = 504, description = "MFL did not answer in time", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = []))
+)]
+#[get("/messages/{thread_id}")]
+#[tracing::instrument(skip_all, fields(thread_id = %thread_id))]
+pub async fn get_message_thread_handler(
+    thread_id: web::Path<String>,
+    req: HttpRequest,
+) -> Result<impl Responder> {
+    use crate::errors::ServiceError;
+    use crate::handler_models::MessagePost;
+
+    let session_data = req.extensions().get::<SessionData>().ok_or_else(|| {
+        ServiceError::Unauthorized("Unauthorized: Session data missing or invalid".to_string())
+    })?.clone();
+    let thread_id = thread_id.into_inner();
+    tracing::info!(league_id = %session_data.league_id, "Reading message thread");
+
+    let fetched = session_data.mfl_api.get_message_board_thread(
+        &session_data.league_id,
+        &thread_id
+    ).await.map_err(ServiceError::MflApiError)?;
+    let (thread, freshness) = (fetched.data, fetched.freshness);
+
+    let posts = thread.post
+        .into_iter()
+        .map(|post| MessagePost {
+            id: post.id,
+            franchise_id: post.franchise,
+            post_time: post.post_time,
+            subject: post.subject,
+            body: post.body,
+        })
+        .collect();
+
+    Ok(with_freshness(HttpResponse::Ok(), freshness).json(MessageThreadResponse {
+        id: thread.id.unwrap_or(thread_id),
+        subject: thread.subject,
+        posts,
+        freshness,
+    }))
+}
+
+#[utoipa::path(
+    tag = "messages",
+    request_body = PostMessageRequest,
+    responses(
+        (status = 201, description = "Thread started", body = PostMessageResponse),
+        (status = 400, description = "Subject or body missing", body = ErrorResponse),
+        (status = 401, description = "Missing, invalid or expired session", body = ErrorResponse),
+        (status = 403, description = "franchise_id given by someone other than the commissioner", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = []))
+)]
+#[post("/messages")]
+#[tracing::instrument(skip_all)]
+pub async fn post_message_thread_handler(
+    req_body: web::Json<PostMessageRequest>,
+    req: HttpRequest,
+) -> Result<impl Responder> {
+    use crate::errors::ServiceError;
+
+    let session_data = req.extensions().get::<SessionData>().ok_or_else(|| {
+        ServiceError::Unauthorized("Unauthorized: Session data missing or invalid".to_string())
+    })?.clone();
+    let message = req_body.into_inner();
+
+    if message.subject.trim().is_empty() {
+        return Err(ServiceError::BadRequest("A subject is required to start a thread".to_string()).into());
+    }
+    if message.body.trim().is_empty() {
+        return Err(ServiceError::BadRequest("Message body must not be empty".to_string()).into());
+    }
+    let on_behalf_of = acting_franchise(&session_data, message.franchise_id.as_deref())?;
+
+    session_data.mfl_api.post_message(
+        &session_data.league_id,
+        None,
+        &message.subject,
+        &message.body,
+        on_behalf_of
+    ).await.map_err(ServiceError::MflApiError)?;
+
+    Ok(HttpResponse::Created().json(PostMessageResponse { status: "OK".to_string() }))
+}
+
+#[utoipa::path(
+    tag = "messages",
+    params(("thread_id" = String, Path, description = "MFL message board thread id")),
+    request_body = PostMessageRequest,
+    responses(
+        (status = 201, description = "Reply posted", body = PostMessageResponse),
+        (status = 400, description = "Body missing", body = ErrorResponse),
+        (status = 401, description = "Missing, invalid or expired session", body = ErrorResponse),
+        (status = 403, description = "franchise_id given by someone other than the commissioner", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = []))
+)]
+#[post("/messages/{thread_id}")]
+#[tracing::instrument(skip_all, fields(thread_id = %thread_id))]
+pub async fn reply_message_handler(
+    thread_id: web::Path<String>,
+    req_body: web::Json<PostMessageRequest>,
+    req: HttpRequest,
+) -> Result<impl Responder> {
+    use crate::errors::ServiceError;
+
+    let session_data = req.extensions().get::<SessionData>().ok_or_else(|| {
+        ServiceError::Unauthorized("Unauthorized: Session data missing or invalid".to_string())
+    })?.clone();
+    let thread_id = thread_id.into_inner();
+    let message = req_body.into_inner();
+
+    if message.body.trim().is_empty() {
+        return Err(ServiceError::BadRequest("Message body must not be empty".to_string()).into());
+    }
+    let on_behalf_of = acting_franchise(&session_data, message.franchise_id.as_deref())?;
+
+    session_data.mfl_api.post_message(
+        &session_data.league_id,
+        Some(&thread_id),
+        &message.subject,
+        &message.body,
+        on_behalf_of
+    ).await.map_err(ServiceError::MflApiError)?;
+
+    Ok(HttpResponse::Created().json(PostMessageResponse { status: "OK".to_string() }))
+}
+
+/// Drops cached MFL exports for the active league so the next read goes upstream. Commissioner only.
+#[utoipa::path(
+    tag = "admin",
+    params(CacheInvalidationQuery),
+    responses(
+        (status = 200, description = "Number of cached replies dropped", body = CacheInvalidationResponse),
+        (status = 401, description = "Missing, invalid or expired session", body = ErrorResponse),
+        (status = 403, description = "Not the league's commissioner", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = []))
+)]
+#[delete("/admin/cache", wrap = "RequireRole::commissioner()")]
+#[tracing::instrument(skip_all)]
+pub async fn invalidate_cache_handler(
+    query: web::Query<CacheInvalidationQuery>,
+    req: HttpRequest,
+) -> Result<impl Responder> {
+    use crate::errors::ServiceError;
+
+    let session_data = req.extensions().get::<SessionData>().ok_or_else(|| {
+        ServiceError::Unauthorized("Unauthorized: Session data missing or invalid".to_string())
+    })?.clone();
+    let export = query.into_inner().export;
+
+    let removed = session_data.mfl_api.config().export_cache.invalidate(
+        export.as_deref(),
+        Some(&session_data.league_id)
+    );
+    tracing::info!(league_id = %session_data.league_id, export = ?export, removed, "Invalidated cached exports");
+
+    Ok(HttpResponse::Ok().json(CacheInvalidationResponse { removed }))
+}
+
+// The API key store, if API keys are enabled
+fn api_key_store(api_keys: Option<web::Data<ApiKeyStore>>) -> Result<web::Data<ApiKeyStore>, crate::errors::ServiceError> {
+    api_keys.ok_or_else(|| {
+        crate::errors::ServiceError::BadRequest("API keys are not enabled: no api_keys.db_path is configured".to_string())
+    })
+}
+
+/// Issues an API key for the active league, acting as the given MFL account. Commissioner only.
+#[utoipa::path(
+    tag = "admin",
+    request_body = CreateApiKeyRequest,
+    responses(
+        (status = 201, description = "Key created; the key itself is only returned here", body = CreateApiKeyResponse),
+        (status = 400, description = "Missing name or permissions, or API keys are not enabled", body = ErrorResponse),
+        (status = 401, description = "Missing, invalid or expired session, or MFL rejected the credentials", body = ErrorResponse),
+        (status = 403, description = "Not the league's commissioner", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = []))
+)]
+#[post("/admin/api-keys", wrap = "RequireRole::commissioner()")]
+#[tracing::instrument(skip_all)]
+pub async fn create_api_key_handler(
+    req_body: web::Json<CreateApiKeyRequest>,
+    req: HttpRequest,
+    api_keys: Option<web::Data<ApiKeyStore>>,
+) -> Result<impl Responder> {
+    use crate::errors::ServiceError;
+    use crate::mfl_api::MflApi;
+
+    let session_data = req.extensions().get::<SessionData>().ok_or_else(|| {
+        ServiceError::Unauthorized("Unauthorized: Session data missing or invalid".to_string())
+    })?.clone();
+    let store = api_key_store(api_keys)?;
+    let request = req_body.into_inner();
+
+    if request.name.trim().is_empty() {
+        return Err(ServiceError::BadRequest("An API key needs a name".to_string()).into());
+    }
+    if request.permissions.is_empty() {
+        return Err(ServiceError::BadRequest("An API key needs at least one permission".to_string()).into());
+    }
+
+    // Only keep credentials MFL accepts; the logged in client becomes the key's client
+    let mfl_api = MflApi::with_config(session_data.year.clone(), session_data.mfl_api.config()).map_err(ServiceError::MflApiError)?;
+    mfl_api.login(&request.username, &request.password).await.map_err(ServiceError::MflLoginError)?;
+
+    let (league_id, year) = (session_data.league_id.clone(), session_data.year.clone());
+    let issued = api_keys::blocking(&store, move |store| store.create(
+        request.name.trim(),
+        &league_id,
+        &year,
+        request.permissions,
+        (&request.username, &request.password),
+        Some(mfl_api)
+    )).await.map_err(ServiceError::ApiKeyError)?;
+    tracing::info!(league_id = %session_data.league_id, api_key = %issued.info.id, "Issued API key");
+
+    Ok(HttpResponse::Created().json(CreateApiKeyResponse { key: issued.key, api_key: issued.info }))
 }
 
+/// API keys of the active league, without their secrets. Commissioner only.
+#[utoipa::path(
+    tag = "admin",
+    responses(
+        (status = 200, description = "Keys of the active league", body = Vec<ApiKeyInfo>),
+        (status = 400, description = "API keys are not enabled", body = ErrorResponse),
+        (status = 401, description = "Missing, invalid or expired session", body = ErrorResponse),
+        (status = 403, description = "Not the league's commissioner", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = []))
+)]
+#[get("/admin/api-keys", wrap = "RequireRole::commissioner()")]
+#[tracing::instrument(skip_all)]
+pub async fn list_api_keys_handler(
+    req: HttpRequest,
+    api_keys: Option<web::Data<ApiKeyStore>>,
+) -> Result<impl Responder> {
+    use crate::errors::ServiceError;
+
+    let session_data = req.extensions().get::<SessionData>().ok_or_else(|| {
+        ServiceError::Unauthorized("Unauthorized: Session data missing or invalid".to_string())
+    })?.clone();
+    let league_id = session_data.league_id.clone();
+    let keys = api_keys::blocking(&api_key_store(api_keys)?, move |store| store.list(&league_id)).await
+        .map_err(ServiceError::ApiKeyError)?;
+
+    Ok(HttpResponse::Ok().json(keys))
+}
 
-pub async fn health_check() -> impl Responder {
-    HttpResponse::Ok().body("OK")
-}
\ No newline at end of file
+/// Revokes an API key of the active league. Commissioner only.
+#[utoipa::path(
+    tag = "admin",
+    params(("key_id" = String, Path, description = "The key's id, as listed")),
+    responses(
+        (status = 204, description = "Key revoked"),
+        (status = 401, description = "Missing, invalid or expired session", body = ErrorResponse),
+        (status = 403, description = "Not the league's commissioner", body = ErrorResponse),
+        (status = 404, description = "No such key in the active league", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = []))
+)]
+#[delete("/admin/api-keys/{key_id}", wrap = "RequireRole::commissioner()")]
+#[tracing::instrument(skip_all, fields(key_id = %key_id))]
+pub async fn revoke_api_key_handler(
+    key_id: web::Path<String>,
+    req: HttpRequest,
+    api_keys: Option<web::Data<ApiKeyStore>>,
+) -> Result<impl Responder> {
+    use crate::errors::ServiceError;
+
+    let session_data = req.extensions().get::<SessionData>().ok_or_else(|| {
+        ServiceError::Unauthorized("Unauthorized: Session data missing or invalid".to_string())
+    })?.clone();
+    let key_id = key_id.into_inner();
+
+    let (league_id, revoked_id) = (session_data.league_id.clone(), key_id.clone());
+    let revoked = api_keys::blocking(&api_key_store(api_keys)?, move |store| store.revoke(&league_id, &revoked_id)).await
+        .map_err(ServiceError::ApiKeyError)?;
+    if !revoked {
+        return Err(ServiceError::NotFound(format!("No API key {} in this league", key_id)).into());
+    }
+    tracing::info!(league_id = %session_data.league_id, "Revoked API key");
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+/// Prometheus metrics. Cache and session figures are sampled on each scrape.
+#[utoipa::path(
+    tag = "health",
+    responses((status = 200, description = "Prometheus text exposition format", body = String, content_type = "text/plain"))
+)]
+#[get("/metrics")]
+#[tracing::instrument(skip_all)]
+pub async fn metrics_handler(
+    sessions: Option<web::Data<SessionStore>>,
+    client_config: Option<web::Data<MflClientConfig>>,
+) -> impl Responder {
+    let client_config = client_config.map(|config| config.get_ref().clone()).unwrap_or_default();
+    let active_sessions = match sessions {
+        Some(sessions) => match app_state::blocking(&sessions, |store| store.count()).await {
+            Ok(count) => Some(count),
+            Err(e) => {
+                tracing::warn!("Could not count sessions for metrics: {}", e);
+                None
+            }
+        },
+        None => None,
+    };
+
+    let body = client_config.metrics.render(&client_config.export_cache.stats(), active_sessions);
+    HttpResponse::Ok()
+        .content_type("text/plain; version=0.0.4")
+        .body(body)
+}
+
+#[utoipa::path(
+    get,
+    path = "/health",
+    tag = "health",
+    responses((status = 200, description = "The server is up", body = HealthResponse))
+)]
+#[tracing::instrument(skip_all)]
+pub async fn health_check(
+    client_config: Option<web::Data<MflClientConfig>>,
+) -> impl Responder {
+    use crate::circuit_breaker::CircuitBreaker;
+
+    let breaker = client_config
+        .map(|config| config.circuit_breaker.clone())
+        .unwrap_or_else(CircuitBreaker::shared);
+
+    HttpResponse::Ok().json(HealthResponse {
+        status: "OK".to_string(),
+        mfl_circuit: breaker.snapshot(),
+    })
+}
+
+#[utoipa::path(
+    tag = "health",
+    responses(
+        (status = 200, description = "Every critical dependency is up", body = ReadinessResponse),
+        (status = 503, description = "A critical dependency is down; see `checks`", body = ReadinessResponse),
+    )
+)]
+#[get("/ready")]
+#[tracing::instrument(skip_all)]
+pub async fn readiness_handler(
+    client_config: Option<web::Data<MflClientConfig>>,
+    sessions: Option<web::Data<SessionStore>>,
+    probes: Option<web::Data<crate::readiness::MflProbeCache>>,
+) -> impl Responder {
+    use crate::readiness;
+
+    let client_config = client_config.map(|config| config.get_ref().clone()).unwrap_or_default();
+    let readiness = readiness::check(
+        &client_config,
+        sessions.as_ref().map(|store| store.get_ref()),
+        probes.as_ref().map(|probes| probes.get_ref()),
+    ).await;
+    if readiness.ready {
+        return HttpResponse::Ok().json(readiness);
+    }
+
+    let failing: Vec<&str> = readiness.checks.iter()
+        .filter(|(_, check)| check.critical && check.status == DependencyStatus::Down)
+        .map(|(name, _)| name.as_str())
+        .collect();
+    tracing::warn!(?failing, "Not ready");
+    HttpResponse::ServiceUnavailable().json(readiness)
+}
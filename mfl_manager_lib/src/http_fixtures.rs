@@ -0,0 +1,209 @@
+// src/http_fixtures.rs
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum FixtureError {
+    #[error("Fixture file error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("Fixture file is not valid JSON: {0}")]
+    Json(#[from] serde_json::Error),
+}
+
+/// Whether `MflApi` writes its traffic to fixture files or answers from them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FixtureMode {
+    // Talk to MFL as usual and save every request/response pair
+    Record,
+    // Never touch the network; unmatched requests fail
+    Replay,
+}
+
+// Request parameters that must never reach a fixture file
+const SECRET_PARAMS: &[&str] = &["USERNAME", "PASSWORD", "APIKEY"];
+const REDACTED: &str = "REDACTED";
+
+// Cookie values in login replies, e.g. MFL_USER_ID="abc" or cookie_value="abc"
+static COOKIE_ATTRIBUTE_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#"(MFL_USER_ID|cookie_value)="[^"]*""#).expect("Invalid cookie attribute regex")
+});
+
+/// The parts of a request a fixture is matched on. The host is left out so a
+/// recording made against a league host replays wherever the league lives.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct FixtureRequest {
+    pub method: String,
+    pub path: String,
+    #[serde(default)]
+    pub query: BTreeMap<String, String>,
+    #[serde(default)]
+    pub form: BTreeMap<String, String>,
+}
+
+impl FixtureRequest {
+    /// Describes `request` with credentials already scrubbed.
+    pub fn from_request(request: &reqwest::Request) -> Self {
+        let query = request.url().query_pairs()
+            .map(|(key, value)| (key.into_owned(), value.into_owned()))
+            .collect();
+        let form = request.body()
+            .and_then(|body| body.as_bytes())
+            .and_then(|bytes| serde_urlencoded::from_bytes::<Vec<(String, String)>>(bytes).ok())
+            .map(|pairs| pairs.into_iter().collect())
+            .unwrap_or_default();
+        FixtureRequest {
+            method: request.method().to_string(),
+            path: request.url().path().to_string(),
+            query: scrub_params(query),
+            form: scrub_params(form),
+        }
+    }
+
+    // A short, stable name for the fixture file
+    fn file_stem(&self, occurrence: usize) -> String {
+        let label = self.query.get("TYPE")
+            .map(String::as_str)
+            .or_else(|| self.form.get("TYPE").map(String::as_str))
+            .or_else(|| self.path.rsplit('/').next())
+            .unwrap_or("request");
+        let label: String = label.chars().filter(|c| c.is_ascii_alphanumeric()).collect();
+        format!("{}-{}-{:016x}-{}", self.method.to_lowercase(), label, self.fingerprint(), occurrence)
+    }
+
+    // FNV-1a, so file names stay the same across Rust versions
+    fn fingerprint(&self) -> u64 {
+        let canonical = serde_json::to_string(self).unwrap_or_default();
+        canonical.bytes().fold(0xcbf29ce484222325, |hash, byte| {
+            (hash ^ u64::from(byte)).wrapping_mul(0x100000001b3)
+        })
+    }
+}
+
+fn scrub_params(params: BTreeMap<String, String>) -> BTreeMap<String, String> {
+    params.into_iter()
+        .map(|(key, value)| {
+            if SECRET_PARAMS.contains(&key.to_ascii_uppercase().as_str()) {
+                (key, REDACTED.to_string())
+            } else {
+                (key, value)
+            }
+        })
+        .collect()
+}
+
+/// Removes cookie values from a response body before it is written to disk.
+pub fn scrub_body(body: &str, cookie: Option<&str>) -> String {
+    let body = COOKIE_ATTRIBUTE_REGEX.replace_all(body, |caps: &regex::Captures| format!(r#"{}="{}""#, &caps[1], REDACTED));
+    match cookie {
+        Some(cookie) if !cookie.is_empty() => body.replace(cookie, REDACTED),
+        _ => body.into_owned(),
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FixtureResponse {
+    pub status: u16,
+    pub body: String,
+}
+
+/// One recorded request/response pair, as stored in a fixture file.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FixtureExchange {
+    pub request: FixtureRequest,
+    // Position among identical requests, so a sequence (before/after an import) replays in order
+    #[serde(default)]
+    pub occurrence: usize,
+    pub response: FixtureResponse,
+}
+
+/// Fixture files for `MflApi` traffic, one JSON file per exchange in `dir`.
+#[derive(Debug)]
+pub struct HttpFixtures {
+    mode: FixtureMode,
+    dir: PathBuf,
+    // Replay: recorded responses per request, in the order they were recorded
+    recorded: HashMap<FixtureRequest, Vec<FixtureResponse>>,
+    // How many times each request has been seen so far
+    seen: Mutex<HashMap<FixtureRequest, usize>>,
+}
+
+impl HttpFixtures {
+    /// Records into `dir`, creating it if needed. Existing files for the same requests are overwritten.
+    pub fn record<P: AsRef<Path>>(dir: P) -> Result<Self, FixtureError> {
+        std::fs::create_dir_all(dir.as_ref())?;
+        Ok(HttpFixtures {
+            mode: FixtureMode::Record,
+            dir: dir.as_ref().to_path_buf(),
+            recorded: HashMap::new(),
+            seen: Mutex::new(HashMap::new()),
+        })
+    }
+
+    /// Loads every fixture in `dir` for replay.
+    pub fn replay<P: AsRef<Path>>(dir: P) -> Result<Self, FixtureError> {
+        let mut exchanges = Vec::new();
+        for entry in std::fs::read_dir(dir.as_ref())? {
+            let path = entry?.path();
+            if path.extension().is_some_and(|ext| ext == "json") {
+                let exchange: FixtureExchange = serde_json::from_str(&std::fs::read_to_string(&path)?)?;
+                exchanges.push(exchange);
+            }
+        }
+        exchanges.sort_by_key(|exchange| exchange.occurrence);
+
+        let mut recorded: HashMap<FixtureRequest, Vec<FixtureResponse>> = HashMap::new();
+        for exchange in exchanges {
+            recorded.entry(exchange.request).or_default().push(exchange.response);
+        }
+        Ok(HttpFixtures {
+            mode: FixtureMode::Replay,
+            dir: dir.as_ref().to_path_buf(),
+            recorded,
+            seen: Mutex::new(HashMap::new()),
+        })
+    }
+
+    pub fn mode(&self) -> FixtureMode {
+        self.mode
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    // Counts this request and returns its position among identical ones
+    fn next_occurrence(&self, request: &FixtureRequest) -> usize {
+        let Ok(mut seen) = self.seen.lock() else {
+            return 0;
+        };
+        let count = seen.entry(request.clone()).or_default();
+        *count += 1;
+        *count - 1
+    }
+
+    /// The recorded response for `request`. Repeats beyond the recording get the last response again.
+    pub fn find(&self, request: &FixtureRequest) -> Option<FixtureResponse> {
+        let responses = self.recorded.get(request)?;
+        let occurrence = self.next_occurrence(request);
+        responses.get(occurrence).or_else(|| responses.last()).cloned()
+    }
+
+    /// Writes a fixture for `request`. `cookie` is scrubbed from the body along with any login cookie values.
+    pub fn save(&self, request: &FixtureRequest, status: u16, body: &str, cookie: Option<&str>) -> Result<(), FixtureError> {
+        let occurrence = self.next_occurrence(request);
+        let exchange = FixtureExchange {
+            request: request.clone(),
+            occurrence,
+            response: FixtureResponse { status, body: scrub_body(body, cookie) },
+        };
+        let path = self.dir.join(format!("{}.json", request.file_stem(occurrence)));
+        std::fs::write(path, serde_json::to_string_pretty(&exchange)?)?;
+        Ok(())
+    }
+}
@@ -1,10 +1,24 @@
 // mfl_manager_lib/src/lib.rs
+pub mod api_keys;
 pub mod app_state;
+pub mod circuit_breaker;
+pub mod config;
+pub mod crypto;
 pub mod errors;
+pub mod export_cache;
 pub mod handler_models;
 pub mod handlers;
 pub mod handler_middleware;
+pub mod http_fixtures;
+pub mod metrics;
 pub mod mfl_api;
+pub mod offline_store;
+pub mod openapi;
+pub mod rate_limit;
+pub mod readiness;
+pub mod redact;
+pub mod request_id;
+pub mod tokens;
 
 // You might also add a function here to configure and return the Actix App
 // that main.rs can call, but simply exporting modules is often enough.
\ No newline at end of file
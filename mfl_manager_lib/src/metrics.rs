@@ -0,0 +1,135 @@
+// src/metrics.rs
+use std::sync::Arc;
+use std::time::Duration;
+use once_cell::sync::Lazy;
+use prometheus::{
+    Encoder, Gauge, HistogramOpts, HistogramVec, IntCounter, IntCounterVec, IntGauge, Opts, Registry, TextEncoder,
+};
+use reqwest::StatusCode;
+use crate::export_cache::CacheStats;
+use crate::mfl_api::MflError;
+
+// Seconds; MFL calls regularly take a few seconds, so the buckets go well past the usual web defaults
+const LATENCY_BUCKETS: &[f64] = &[0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 20.0];
+
+// Used as the route label for requests no route matched, so scanners can't blow up the label set
+pub const UNMATCHED_ROUTE: &str = "unmatched";
+
+static SHARED_METRICS: Lazy<Arc<Metrics>> = Lazy::new(|| Arc::new(Metrics::new()));
+
+/// Prometheus metrics for the API and its MFL traffic, rendered by the /metrics handler.
+#[derive(Debug)]
+pub struct Metrics {
+    registry: Registry,
+    http_requests: IntCounterVec,
+    http_request_duration: HistogramVec,
+    mfl_requests: IntCounterVec,
+    mfl_request_duration: HistogramVec,
+    mfl_errors: IntCounterVec,
+    cache_hits: IntCounter,
+    cache_misses: IntCounter,
+    cache_entries: IntGauge,
+    cache_hit_ratio: Gauge,
+    active_sessions: IntGauge,
+}
+
+impl Default for Metrics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Metrics {
+    pub fn new() -> Self {
+        let registry = Registry::new();
+        let metrics = Metrics {
+            http_requests: IntCounterVec::new(
+                Opts::new("http_requests_total", "Requests served, by route pattern, method and status"),
+                &["route", "method", "status"],
+            ).expect("valid metric"),
+            http_request_duration: HistogramVec::new(
+                HistogramOpts::new("http_request_duration_seconds", "Time to serve a request")
+                    .buckets(LATENCY_BUCKETS.to_vec()),
+                &["route", "method", "status"],
+            ).expect("valid metric"),
+            mfl_requests: IntCounterVec::new(
+                Opts::new("mfl_requests_total", "Calls to MFL, by export TYPE and HTTP status (\"error\" if no reply)"),
+                &["export", "status"],
+            ).expect("valid metric"),
+            mfl_request_duration: HistogramVec::new(
+                HistogramOpts::new("mfl_request_duration_seconds", "Time for MFL to answer a call")
+                    .buckets(LATENCY_BUCKETS.to_vec()),
+                &["export"],
+            ).expect("valid metric"),
+            mfl_errors: IntCounterVec::new(
+                Opts::new("mfl_errors_total", "MFL errors returned to API clients, by MflError variant"),
+                &["variant"],
+            ).expect("valid metric"),
+            cache_hits: IntCounter::new("mfl_cache_hits_total", "Exports answered from the export cache").expect("valid metric"),
+            cache_misses: IntCounter::new("mfl_cache_misses_total", "Exports the export cache had to fetch").expect("valid metric"),
+            cache_entries: IntGauge::new("mfl_cache_entries", "Replies currently held by the export cache").expect("valid metric"),
+            cache_hit_ratio: Gauge::new("mfl_cache_hit_ratio", "Share of cacheable export reads answered from the cache").expect("valid metric"),
+            active_sessions: IntGauge::new("active_sessions", "Sessions in the session store").expect("valid metric"),
+            registry,
+        };
+
+        let collectors: Vec<Box<dyn prometheus::core::Collector>> = vec![
+            Box::new(metrics.http_requests.clone()),
+            Box::new(metrics.http_request_duration.clone()),
+            Box::new(metrics.mfl_requests.clone()),
+            Box::new(metrics.mfl_request_duration.clone()),
+            Box::new(metrics.mfl_errors.clone()),
+            Box::new(metrics.cache_hits.clone()),
+            Box::new(metrics.cache_misses.clone()),
+            Box::new(metrics.cache_entries.clone()),
+            Box::new(metrics.cache_hit_ratio.clone()),
+            Box::new(metrics.active_sessions.clone()),
+        ];
+        for collector in collectors {
+            metrics.registry.register(collector).expect("metric registered twice");
+        }
+        metrics
+    }
+
+    /// The process-wide metrics, used unless `MflClientConfig::metrics` is replaced.
+    pub fn shared() -> Arc<Metrics> {
+        SHARED_METRICS.clone()
+    }
+
+    pub fn observe_request(&self, route: &str, method: &str, status: u16, elapsed: Duration) {
+        let status = status.to_string();
+        let labels = [route, method, status.as_str()];
+        self.http_requests.with_label_values(&labels).inc();
+        self.http_request_duration.with_label_values(&labels).observe(elapsed.as_secs_f64());
+    }
+
+    /// Records one call to MFL. `status` is None when no reply came back (timeout, connection error).
+    pub fn observe_mfl_call(&self, export: &str, status: Option<StatusCode>, elapsed: Duration) {
+        let status = status.map(|status| status.as_u16().to_string()).unwrap_or_else(|| "error".to_string());
+        self.mfl_requests.with_label_values(&[export, status.as_str()]).inc();
+        self.mfl_request_duration.with_label_values(&[export]).observe(elapsed.as_secs_f64());
+    }
+
+    pub fn count_mfl_error(&self, error: &MflError) {
+        self.mfl_errors.with_label_values(&[error.variant_name()]).inc();
+    }
+
+    /// Text exposition of every metric. Cache and session figures are sampled now.
+    pub fn render(&self, cache: &CacheStats, active_sessions: Option<usize>) -> String {
+        // The cache keeps running totals; bring the counters up to them
+        self.cache_hits.inc_by(cache.hits.saturating_sub(self.cache_hits.get()));
+        self.cache_misses.inc_by(cache.misses.saturating_sub(self.cache_misses.get()));
+        self.cache_entries.set(cache.entries as i64);
+        let reads = cache.hits + cache.misses;
+        self.cache_hit_ratio.set(if reads == 0 { 0.0 } else { cache.hits as f64 / reads as f64 });
+        if let Some(count) = active_sessions {
+            self.active_sessions.set(count as i64);
+        }
+
+        let mut buffer = Vec::new();
+        if let Err(e) = TextEncoder::new().encode(&self.registry.gather(), &mut buffer) {
+            tracing::error!("Could not encode metrics: {}", e);
+        }
+        String::from_utf8(buffer).unwrap_or_default()
+    }
+}
@@ -1,16 +1,30 @@
 use regex::Regex; // Import Regex
 use once_cell::sync::Lazy;
-use reqwest::header::{HeaderMap, HeaderValue, COOKIE};
-use reqwest::Response;
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+use reqwest::header::{HeaderMap, HeaderValue, COOKIE, RETRY_AFTER};
 // Import Lazy for efficient regex compilation (optional but recommended)
 
 use urlencoding::encode;
 // Import the encode function
-use serde::{Deserialize, Serialize};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Deserializer, Serialize};
 use serde_json;
 use thiserror::Error;
 use reqwest::StatusCode;
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tracing::Instrument;
 
+use crate::circuit_breaker::CircuitBreaker;
+use crate::crypto::CredentialVault;
+use crate::export_cache::{CacheKey, ExportCache, Freshness};
+use crate::http_fixtures::{FixtureMode, FixtureRequest, HttpFixtures};
+use crate::metrics::Metrics;
+use crate::offline_store::{self, OfflineStore};
+use crate::rate_limit::RateLimiter;
 use crate::mfl_api::MflError::{ApiStatusError, ClientInitializationFailed, LoginCookieNotFound, RequestFailed};
 // Add thiserror crate for convenience
 
@@ -18,7 +32,132 @@ use crate::mfl_api::MflError::{ApiStatusError, ClientInitializationFailed, Login
 pub struct MflApi {
     client: reqwest::Client,
     pub year: String,
-    pub mfl_user_id_cookie: Option<String>,
+    // Shared between clones so a re-login is seen by every copy of the session
+    mfl_user_id_cookie: Arc<RwLock<MflCookie>>,
+    // MFL username the cookie belongs to. Unlike the cookie it survives a re-login,
+    // so cached and offline exports are keyed on it
+    mfl_username: Arc<RwLock<Option<String>>>,
+    // Opt-in: lets us log in again when MFL invalidates the cookie
+    credential_vault: Option<CredentialVault>,
+    // Base URL (e.g. https://www48.myfantasyleague.com) per league id, shared between clones
+    league_hosts: Arc<RwLock<HashMap<String, String>>>,
+    config: MflClientConfig,
+}
+
+// The MFL_USER_ID cookie and when it was issued, in milliseconds since the Unix epoch
+#[derive(Debug, Default)]
+struct MflCookie {
+    value: Option<String>,
+    issued_at: u64,
+}
+
+/// Settings for the HTTP client behind `MflApi`. The rate limiter and circuit
+/// breaker are shared by every session unless replaced.
+#[derive(Debug, Clone)]
+pub struct MflClientConfig {
+    // Base URL of the MFL API host, e.g. a mock server in tests. No trailing slash
+    pub base_url: String,
+    pub connect_timeout: Duration,
+    // Longest we wait for MFL to send data before giving up on the request
+    pub read_timeout: Duration,
+    // Extra attempts for idempotent exports that time out or get a 5xx
+    pub max_retries: u32,
+    pub retry_base_delay: Duration,
+    pub retry_max_delay: Duration,
+    pub rate_limiter: Arc<RateLimiter>,
+    pub circuit_breaker: Arc<CircuitBreaker>,
+    pub export_cache: Arc<ExportCache>,
+    // Last good replies, served when MFL is down. Off unless configured
+    pub offline_store: Option<Arc<OfflineStore>>,
+    // Record MFL traffic to fixture files, or replay it from them instead of calling MFL
+    pub fixtures: Option<Arc<HttpFixtures>>,
+    pub metrics: Arc<Metrics>,
+}
+
+impl Default for MflClientConfig {
+    fn default() -> Self {
+        MflClientConfig {
+            base_url: DEFAULT_MFL_BASE_URL.to_string(),
+            connect_timeout: Duration::from_secs(5),
+            read_timeout: Duration::from_secs(20),
+            max_retries: 2,
+            retry_base_delay: Duration::from_millis(200),
+            retry_max_delay: Duration::from_secs(2),
+            rate_limiter: RateLimiter::shared(),
+            circuit_breaker: CircuitBreaker::shared(),
+            export_cache: ExportCache::shared(),
+            offline_store: None,
+            fixtures: None,
+            metrics: Metrics::shared(),
+        }
+    }
+}
+
+impl MflClientConfig {
+    // Exponential back-off with full jitter: anywhere between zero and base * 2^attempt
+    fn retry_delay(&self, attempt: u32) -> Duration {
+        use rand::Rng;
+        let ceiling = self.retry_base_delay
+            .saturating_mul(2u32.saturating_pow(attempt))
+            .min(self.retry_max_delay);
+        let millis = u64::try_from(ceiling.as_millis()).unwrap_or(u64::MAX);
+        Duration::from_millis(rand::rng().random_range(0..=millis))
+    }
+}
+
+/// A fully read reply from MFL.
+#[derive(Debug, Clone)]
+pub struct MflReply {
+    pub status: StatusCode,
+    pub url: reqwest::Url, // Final URL, after any redirects
+    pub body: String,
+    pub freshness: Freshness,
+}
+
+/// A parsed export together with how fresh it is.
+#[derive(Debug, Clone)]
+pub struct Fetched<T> {
+    pub data: T,
+    pub freshness: Freshness,
+}
+
+impl<T> Fetched<T> {
+    pub fn map<U>(self, f: impl FnOnce(T) -> U) -> Fetched<U> {
+        Fetched { data: f(self.data), freshness: self.freshness }
+    }
+}
+
+/// An MFL export, declared by its `TYPE`, the parameters it takes and the JSON it
+/// answers with. [`MflApi::export`] does the sending, status check and decoding.
+pub trait MflExport {
+    const TYPE: &'static str;
+    type Params: ?Sized;
+    type Response: DeserializeOwned;
+
+    /// Query arguments besides TYPE, L and JSON, already encoded.
+    fn query(params: &Self::Params) -> Vec<(&'static str, String)>;
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum OneOrMany<T> {
+    Many(Vec<T>),
+    One(T),
+}
+
+/// MFL's JSON drops the array around a single element and leaves the key out
+/// when there are none. Use with `#[serde(default, deserialize_with = "one_or_many")]`
+/// on every list field of an MFL model.
+pub fn one_or_many<'de, D, T>(deserializer: D) -> Result<Vec<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    Ok(match Option::<OneOrMany<T>>::deserialize(deserializer)? {
+        Some(OneOrMany::Many(items)) => items,
+        Some(OneOrMany::One(item)) => vec![item],
+        None => Vec::new(),
+    })
 }
 
 // get_free_agents
@@ -26,27 +165,45 @@ pub struct MflApi {
 pub struct FreeAgentPlayer {
     pub id: String,
     pub salary: String,
-    pub contractStatus: String,
+    #[serde(rename = "contractStatus")]
+    pub contract_status: String,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct FreeAgentLeagueUnit {
     pub unit: String,
+    #[serde(default, deserialize_with = "one_or_many")]
     pub player: Vec<FreeAgentPlayer>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct FreeAgents {
-    pub leagueUnit: FreeAgentLeagueUnit,
+    // One unit per conference or division in leagues that have them
+    #[serde(rename = "leagueUnit", default, deserialize_with = "one_or_many")]
+    pub league_unit: Vec<FreeAgentLeagueUnit>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct FreeAgentResponse {
     pub version: String,
-    pub freeAgents: FreeAgents,
+    #[serde(rename = "freeAgents")]
+    pub free_agents: FreeAgents,
     pub encoding: String,
 }
 
+/// Free agents, optionally of one position.
+pub struct FreeAgentsExport;
+
+impl MflExport for FreeAgentsExport {
+    const TYPE: &'static str = "freeAgents";
+    type Params = Option<String>;
+    type Response = FreeAgentResponse;
+
+    fn query(position: &Option<String>) -> Vec<(&'static str, String)> {
+        position.iter().map(|pos| ("POSITION", encode(pos).into_owned())).collect()
+    }
+}
+
 // get_players
 #[derive(Serialize, Deserialize, Debug)]
 pub struct PlayersStatusResponse {
@@ -57,6 +214,7 @@ pub struct PlayersStatusResponse {
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct PlayersPlayers {
+    #[serde(default, deserialize_with = "one_or_many")]
     pub player: Vec<PlayersPlayer>,
     pub timestamp: String,
 }
@@ -69,14 +227,206 @@ pub struct PlayersPlayer {
     pub name: String,
     pub status: Option<String>, // Added status as it is present in some player objects.
 }
+
+/// Player details for a comma separated list of player ids.
+pub struct PlayersExport;
+
+impl MflExport for PlayersExport {
+    const TYPE: &'static str = "players";
+    type Params = str;
+    type Response = PlayersStatusResponse;
+
+    fn query(player_ids: &str) -> Vec<(&'static str, String)> {
+        vec![("PLAYERS", player_ids.to_string())]
+    }
+}
 // end- get_player_roster_status
 
+// get_my_leagues
+#[derive(Serialize, Deserialize, Debug)]
+pub struct MyLeaguesResponse {
+    pub leagues: MyLeagues,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct MyLeagues {
+    #[serde(default, deserialize_with = "one_or_many")]
+    pub league: Vec<MyLeague>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct MyLeague {
+    pub league_id: String,
+    pub name: String,
+    pub url: Option<String>,
+    pub franchise_id: Option<String>,
+    pub franchise_name: Option<String>,
+}
+
+/// The logged in user's leagues. Not league scoped, so only the api host answers it.
+pub struct MyLeaguesExport;
+
+impl MflExport for MyLeaguesExport {
+    const TYPE: &'static str = "myleagues";
+    type Params = ();
+    type Response = MyLeaguesResponse;
+
+    fn query(_: &()) -> Vec<(&'static str, String)> {
+        vec![("FRANCHISE_NAMES", "1".to_string())]
+    }
+}
+// end- get_my_leagues
+
+// get_abilities
+#[derive(Serialize, Deserialize, Debug)]
+pub struct AbilitiesResponse {
+    #[serde(default)]
+    pub abilities: Abilities,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct Abilities {
+    #[serde(default, deserialize_with = "one_or_many")]
+    pub ability: Vec<Ability>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Ability {
+    pub name: String,
+    #[serde(rename = "$t", default)]
+    pub value: String,
+}
+
+impl Abilities {
+    /// Whether MFL grants the ability, e.g. `COMMISSIONER`. Names are case-insensitive.
+    pub fn has(&self, name: &str) -> bool {
+        self.ability.iter().any(|ability| ability.name.eq_ignore_ascii_case(name) && ability.value == "1")
+    }
+}
+
+/// What the logged in user may do in a league, whatever franchise they own.
+pub struct AbilitiesExport;
+
+impl MflExport for AbilitiesExport {
+    const TYPE: &'static str = "abilities";
+    type Params = ();
+    type Response = AbilitiesResponse;
+
+    fn query(_: &()) -> Vec<(&'static str, String)> {
+        Vec::new()
+    }
+}
+// end- get_abilities
+
+// get_league_host
+#[derive(Serialize, Deserialize, Debug)]
+pub struct LeagueResponse {
+    pub league: LeagueInfo,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct LeagueInfo {
+    pub id: Option<String>,
+    pub name: Option<String>,
+    #[serde(rename = "baseURL")]
+    pub base_url: Option<String>,
+}
+
+/// League settings, including the host the league lives on.
+pub struct LeagueExport;
+
+impl MflExport for LeagueExport {
+    const TYPE: &'static str = "league";
+    type Params = ();
+    type Response = LeagueResponse;
+
+    fn query(_: &()) -> Vec<(&'static str, String)> {
+        Vec::new()
+    }
+}
+// end- get_league_host
+
+// get_message_board
+#[derive(Serialize, Deserialize, Debug)]
+pub struct MessageBoardResponse {
+    #[serde(rename = "messageBoard")]
+    pub message_board: MessageBoard,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct MessageBoard {
+    #[serde(default, deserialize_with = "one_or_many")]
+    pub thread: Vec<MessageBoardThreadSummary>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct MessageBoardThreadSummary {
+    pub id: String,
+    pub subject: Option<String>,
+    #[serde(rename = "lastPostTime")]
+    pub last_post_time: Option<String>,
+}
+
+/// The league message board's thread list.
+pub struct MessageBoardExport;
+
+impl MflExport for MessageBoardExport {
+    const TYPE: &'static str = "messageBoard";
+    type Params = ();
+    type Response = MessageBoardResponse;
+
+    fn query(_: &()) -> Vec<(&'static str, String)> {
+        Vec::new()
+    }
+}
+
+// get_message_board_thread
+#[derive(Serialize, Deserialize, Debug)]
+pub struct MessageBoardThreadResponse {
+    #[serde(rename = "messageBoardThread")]
+    pub message_board_thread: MessageBoardThread,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct MessageBoardThread {
+    pub id: Option<String>,
+    pub subject: Option<String>,
+    #[serde(default, deserialize_with = "one_or_many")]
+    pub post: Vec<MessageBoardPost>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct MessageBoardPost {
+    pub id: String,
+    pub franchise: Option<String>,
+    #[serde(rename = "postTime")]
+    pub post_time: Option<String>,
+    pub subject: Option<String>,
+    // MFL puts the post text in the element body, which its JSON encodes as "$t".
+    #[serde(rename = "body", alias = "$t")]
+    pub body: Option<String>,
+}
+
+/// The posts of one message board thread.
+pub struct MessageBoardThreadExport;
+
+impl MflExport for MessageBoardThreadExport {
+    const TYPE: &'static str = "messageBoardThread";
+    type Params = str;
+    type Response = MessageBoardThreadResponse;
+
+    fn query(thread_id: &str) -> Vec<(&'static str, String)> {
+        vec![("THREAD", encode(thread_id).into_owned())]
+    }
+}
+// end- get_message_board
+
 #[derive(Error, Debug)]
 pub enum MflError {
-    #[error("Network request failed")]
+    #[error("Network request to MFL failed: {0}")]
     Network(#[from] reqwest::Error), // Use #[from] to auto-generate From impl
 
-    #[error("Failed to parse JSON response")]
+    #[error("Failed to parse MFL's JSON response: {0}")]
     JsonParse(#[from] serde_json::Error),
 
     #[error("Failed to parse using regex")]
@@ -102,197 +452,828 @@ pub enum MflError {
 
     #[error("The Request Client initialization failed: {0}")]
     ClientInitializationFailed(String),
+
+    #[error("MFL rejected the import: {0}")]
+    ImportRejected(String),
+
+    #[error("MFL refused the request for this account: {0}")]
+    PermissionDenied(String),
+
+    #[error("MFL sent a reply that could not be read: {0}")]
+    UnexpectedReply(String),
+
+    #[error("MFL session expired and could not be renewed: {0}")]
+    ReauthenticationRequired(String),
+
+    #[error("MFL rate limit reached, retry after {}s", .retry_after.as_secs().max(1))]
+    RateLimited { retry_after: Duration },
+
+    #[error("MFL is unavailable after repeated failures, retry after {}s", .retry_after.as_secs().max(1))]
+    Unavailable { retry_after: Duration },
+
+    #[error("No recorded fixture matches {0}")]
+    FixtureNotFound(String),
     // Add other specific errors as needed
 }
 
+impl MflError {
+    /// The variant name, used as a metric label.
+    pub fn variant_name(&self) -> &'static str {
+        match self {
+            MflError::Network(_) => "Network",
+            MflError::JsonParse(_) => "JsonParse",
+            MflError::Regex(_) => "Regex",
+            MflError::LoginCookieNotFound => "LoginCookieNotFound",
+            MflError::LoginFailed(_) => "LoginFailed",
+            MflError::LeagueHostNotFound => "LeagueHostNotFound",
+            MflError::InvalidHeaderValue(_) => "InvalidHeaderValue",
+            MflError::ApiStatusError { .. } => "ApiStatusError",
+            MflError::RequestFailed(_) => "RequestFailed",
+            MflError::ClientInitializationFailed(_) => "ClientInitializationFailed",
+            MflError::ImportRejected(_) => "ImportRejected",
+            MflError::PermissionDenied(_) => "PermissionDenied",
+            MflError::UnexpectedReply(_) => "UnexpectedReply",
+            MflError::ReauthenticationRequired(_) => "ReauthenticationRequired",
+            MflError::RateLimited { .. } => "RateLimited",
+            MflError::Unavailable { .. } => "Unavailable",
+            MflError::FixtureNotFound(_) => "FixtureNotFound",
+        }
+    }
+}
+
+/// A successful XML reply to an import or login, `<status ...>OK</status>`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ImportResult {
+    /// The text of the `<status>` element
+    pub status: String,
+    /// Its attributes, e.g. MFL_USER_ID after logging in
+    pub attributes: HashMap<String, String>,
+}
 
-// Compile the regex once using once_cell/lazy_static for efficiency
-static MFL_USER_ID_REGEX: Lazy<Regex> = Lazy::new(|| {
-    // Using expect here because if this fails, the program can't function correctly anyway.
-    Regex::new(r#"MFL_USER_ID="([^"]*)">OK"#).expect("Invalid MFL_USER_ID regex")
+impl ImportResult {
+    pub fn attribute(&self, name: &str) -> Option<&str> {
+        self.attributes.get(name).map(String::as_str)
+    }
+}
+
+// Error replies MFL sends when the MFL_USER_ID cookie is missing, expired or revoked
+static MFL_LOGIN_REQUIRED_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(?i)(requires? (a )?logged[- ]in|login required|not logged in|must (be )?logged in|permission denied|(do not|don't) have (the )?permission)")
+        .expect("Invalid MFL login required regex")
 });
 
-static MFL_API_URL: Lazy<String> = Lazy::new(|| {
-    "https://api.myfantasyleague.com".to_string()
+// Error replies MFL sends when it is throttling this client
+static MFL_THROTTLED_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(?i)(too many requests|rate limit|request limit|throttl)").expect("Invalid MFL throttled regex")
 });
 
+// Back-off used when MFL throttles us without saying for how long
+const DEFAULT_THROTTLE_BACKOFF: Duration = Duration::from_secs(30);
+
+// Export the readiness probe asks for: small, public and answered by the API host
+const PING_EXPORT: &str = "nflByeWeeks";
+
+/// Where MFL's API lives unless `MflClientConfig::base_url` says otherwise.
+pub const DEFAULT_MFL_BASE_URL: &str = "https://api.myfantasyleague.com";
+
 impl MflApi {
     pub fn new(year: String) -> Result<Self, MflError> {
+        Self::with_config(year, &MflClientConfig::default())
+    }
+
+    pub fn with_config(year: String, config: &MflClientConfig) -> Result<Self, MflError> {
 
         let client = reqwest::ClientBuilder::new()
-            // Add any required configurations like user agent, timeouts etc.
+            .connect_timeout(config.connect_timeout)
+            .read_timeout(config.read_timeout)
             // .cookie_store(true) // If using the cookie store feature
             .build().map_err(|e| ClientInitializationFailed(e.to_string()))?; // Adapt error mapping
 
         Ok(MflApi {
             client,
             year,
-            mfl_user_id_cookie: None,
+            mfl_user_id_cookie: Arc::new(RwLock::new(MflCookie::default())),
+            mfl_username: Arc::new(RwLock::new(None)),
+            credential_vault: None,
+            league_hosts: Arc::new(RwLock::new(HashMap::new())),
+            config: config.clone(),
         })
     }
 
-    pub async fn login(&mut self, username: &str, password: &str) -> Result<(), MflError> {
-        let login_url = format!("https://api.myfantasyleague.com/{}/login?USERNAME={}&PASSWORD={}&XML=1",
-                                self.year,
-                                encode(username),
-                                encode(password)); // Ensure proper encoding
+    pub fn config(&self) -> &MflClientConfig {
+        &self.config
+    }
 
-        log::info!("Making request to get cookie: {}", login_url);
-        let login_response = self.client.get(&login_url)
-            // Add any necessary headers
-            .send()
-            .await // Await the send operation
-            .map_err(|e| RequestFailed(e.to_string()))?; // Adapt error
+    pub fn mfl_user_id_cookie(&self) -> Option<String> {
+        self.mfl_user_id_cookie.read().ok()?.value.clone()
+    }
 
-        let status = login_response.status(); // *** Get status ***
-        let body_text = login_response.text() // *** Read body regardless of status ***
-            .await
-            .map_err(|e| RequestFailed(format!("Failed to read response body: {}", e)))?;
+    /// When the current cookie was issued, in milliseconds since the Unix epoch. 0 if never set.
+    pub fn mfl_user_id_cookie_issued_at(&self) -> u64 {
+        self.mfl_user_id_cookie.read().map(|cookie| cookie.issued_at).unwrap_or_default()
+    }
+
+    /// Sets a cookie issued just now.
+    pub fn set_mfl_user_id_cookie(&self, cookie: Option<String>) {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_millis() as u64).unwrap_or_default();
+        self.restore_mfl_user_id_cookie(cookie, now);
+    }
+
+    /// Sets a cookie issued earlier, e.g. one read back from the session store.
+    pub fn restore_mfl_user_id_cookie(&self, cookie: Option<String>, issued_at: u64) {
+        if let Ok(mut current) = self.mfl_user_id_cookie.write() {
+            *current = MflCookie { value: cookie, issued_at };
+        }
+    }
+
+    /// The MFL user this client last logged in as.
+    pub fn mfl_username(&self) -> Option<String> {
+        self.mfl_username.read().ok()?.clone()
+    }
+
+    pub fn set_mfl_username(&self, username: Option<String>) {
+        if let Ok(mut current) = self.mfl_username.write() {
+            *current = username;
+        }
+    }
+
+    pub fn credential_vault(&self) -> Option<&CredentialVault> {
+        self.credential_vault.as_ref()
+    }
+
+    /// Keeps the user's credentials (encrypted) so an invalidated cookie can be renewed transparently.
+    pub fn set_credential_vault(&mut self, vault: Option<CredentialVault>) {
+        self.credential_vault = vault;
+    }
+
+    pub async fn login(&self, username: &str, password: &str) -> Result<(), MflError> {
+        let login_url = format!("{}/{}/login", self.config.base_url, self.year);
+        // Credentials go in a form body: URLs end up in proxy logs, server logs and error messages
+        let credentials = [("USERNAME", username), ("PASSWORD", password), ("XML", "1")];
+
+        tracing::info!("Logging in to MFL for {}", self.year);
+        // Through execute so login shares the rate limiter, breaker and fixtures
+        let login_response = self.execute(&|client: &reqwest::Client| client.post(&login_url).form(&credentials)).await?;
+
+        let status = login_response.status; // *** Get status ***
+        let body_text = login_response.body; // *** Read body regardless of status ***
 
         if !status.is_success() { // *** Check status ***
             // Consider specific handling for common MFL login errors if possible
             return Err(ApiStatusError { status, body: body_text });
         }
 
-        // Apply the regex to the body text
-        // Use the pre-compiled regex
-        let cookie_value = MFL_USER_ID_REGEX.captures(&body_text)
-            .and_then(|captures| captures.get(1)) // Get the first capture group
-            .map(|m| m.as_str().to_string());     // Convert the match to a String
-
+        // <status MFL_USER_ID="...">OK</status>, or <error>Invalid Password</error>
+        let result = parse_xml_reply(&body_text).map_err(|e| match e {
+            MflError::ImportRejected(message) => MflError::LoginFailed(message),
+            e => e,
+        })?;
 
         // Store the cookie or return an error if not found
-        match cookie_value {
+        match result.attribute("MFL_USER_ID") {
             Some(cookie) => {
-                self.mfl_user_id_cookie = Some(cookie);
-                log::info!("Got cookie {}", self.mfl_user_id_cookie.as_ref().unwrap());
-                log::info!("Successfully extracted MFL_USER_ID cookie."); // Optional logging
+                self.set_mfl_user_id_cookie(Some(cookie.to_string()));
+                self.set_mfl_username(Some(username.to_string()));
+                tracing::info!("Successfully extracted MFL_USER_ID cookie."); // Optional logging
                 Ok(())
             }
             None => {
-                log::error!("Could not find MFL_USER_ID cookie in login response body: {}", body_text); // Optional logging
+                tracing::error!("Could not find MFL_USER_ID cookie in login response body: {}", body_text); // Redacted by the log writer
                 Err(LoginCookieNotFound)
             }
         }
     }
 
-    // pub async fn get_league_host(&mut self) -> Result<String, MflError> {
-    //     let ml_args = "TYPE=myleagues&JSON=1".to_string();
-    //     let ml_url = format!(
-    //         "{}://{}/{}/export?{}",
-    //         self.proto, self.api_host, self.year, ml_args
-    //     );
-    //     println!("Making request to get league host: {}", ml_url);
-    //
-    //     let ml_resp = self.send_request(&ml_url).await?;
-    //     let ml_body = ml_resp.text().await?;
-    //
-    //     let league_host_regex = Regex::new(&format!(
-    //         r#"url="(https?)://([a-z0-9]+.myfantasyleague.com)/{}/home/([^"]+)"#,
-    //         self.year
-    //     ))?;
-    //     if let Some(captures) = league_host_regex.captures(&ml_body) {
-    //         self.proto = captures.get(1).map(|m| m.as_str()).unwrap_or("https").to_string(); // Defaulting 'https' is likely safe
-    //
-    //         // Use ok_or to handle potential missing capture group cleanly
-    //         let league_host = captures.get(2)
-    //             .map(|m| m.as_str().to_string())
-    //             .ok_or(MflError::LeagueHostNotFound)?; // *** FIXED: Return error if capture group missing ***
-    //
-    //         println!("Got league host {}", league_host);
-    //         Ok(league_host)
-    //     } else {
-    //         eprintln!("Can't find league host. Response: {}", ml_body);
-    //         Err(MflError::LeagueHostNotFound) // *** FIXED: Return error, don't exit ***
-    //     }
-    // }
-
-    async fn send_request(&self, url: &str) -> Result<Response, MflError> {
+    /// Checks that MFL answers at all, using an export that needs no login or league.
+    /// Goes through the rate limiter and circuit breaker like any other call.
+    pub async fn ping(&self) -> Result<(), MflError> {
+        let url = format!("{}/{}/export?TYPE={}&JSON=1", self.config.base_url, self.year, PING_EXPORT);
+        let reply = self.execute(&|client: &reqwest::Client| client.get(&url)).await?;
+        if reply.status.is_server_error() {
+            return Err(ApiStatusError { status: reply.status, body: reply.body });
+        }
+        Ok(())
+    }
+
+    /// Returns the base URL of the host MFL serves `league_id` from (e.g.
+    /// `https://www48.myfantasyleague.com`), asking the `league` export on first use.
+    pub async fn get_league_host(&self, league_id: &str) -> Result<String, MflError> {
+        if let Some(host) = self.cached_league_host(league_id) {
+            return Ok(host);
+        }
+
+        let response = self.api_host_export::<LeagueExport>(Some(league_id), &()).await?.data;
+
+        let host = response.league.base_url
+            .as_deref()
+            .and_then(base_url_of)
+            .ok_or(MflError::LeagueHostNotFound)?;
+
+        tracing::info!("Got league host {} for league {}", host, league_id);
+        self.cache_league_host(league_id, &host);
+        Ok(host)
+    }
+
+    pub fn cached_league_host(&self, league_id: &str) -> Option<String> {
+        self.league_hosts.read().ok()?.get(league_id).cloned()
+    }
+
+    pub fn cache_league_host(&self, league_id: &str, host: &str) {
+        if let Ok(mut hosts) = self.league_hosts.write() {
+            hosts.insert(league_id.to_string(), host.to_string());
+        }
+    }
+
+    fn forget_league_host(&self, league_id: &str) {
+        if let Ok(mut hosts) = self.league_hosts.write() {
+            hosts.remove(league_id);
+        }
+    }
+
+    /// Sends a league request to the league's own host, or to the api host when the
+    /// league host can't be resolved. Idempotent exports are sent again to the api host
+    /// when the league host fails; imports are never sent twice.
+    /// `send` receives the base URL to use.
+    async fn send_to_league_host<F, Fut>(&self, league_id: &str, idempotent: bool, send: F) -> Result<MflReply, MflError>
+    where
+        F: Fn(String) -> Fut,
+        Fut: Future<Output = Result<MflReply, MflError>>,
+    {
+        let host = match self.get_league_host(league_id).await {
+            Ok(host) => host,
+            Err(e) => {
+                tracing::warn!("Could not resolve host for league {}, using api host: {}", league_id, e);
+                return send(self.config.base_url.clone()).await;
+            }
+        };
+
+        if host == self.config.base_url {
+            return send(host).await;
+        }
+
+        let result = send(host.clone()).await;
+        if let Ok(reply) = &result {
+            self.follow_league_redirect(league_id, &host, reply);
+        }
+        if !idempotent {
+            return result;
+        }
+        match &result {
+            Ok(reply) if !reply.status.is_redirection() && !reply.status.is_server_error() => return result,
+            Ok(reply) => tracing::warn!("League host {} answered {}, retrying on api host", host, reply.status),
+            Err(e) => tracing::warn!("Request to league host {} failed, retrying on api host: {}", host, e),
+        }
+
+        self.forget_league_host(league_id);
+        send(self.config.base_url.clone()).await
+    }
+
+    // reqwest has already followed any redirect, so the reply is good to use. If MFL
+    // moved the league to another host, go there directly from now on.
+    fn follow_league_redirect(&self, league_id: &str, host: &str, reply: &MflReply) {
+        if let Some(final_host) = base_url_of(reply.url.as_str()).filter(|final_host| final_host != host) {
+            tracing::info!("League {} moved from {} to {}", league_id, host, final_host);
+            self.cache_league_host(league_id, &final_host);
+        }
+    }
+
+    // League exports are answered from the export cache while fresh. When MFL is
+    // down we fall back to the last good reply on disk and refresh it in the background.
+    async fn send_league_request(&self, league_id: &str, args: &str) -> Result<MflReply, MflError> {
+        let key = CacheKey::from_args(league_id, &self.year, args).for_user(self.mfl_username().as_deref());
+        let result = self.fetch_export(&key, league_id, args).await;
+
+        let Some(store) = &self.config.offline_store else {
+            return result;
+        };
+        if !upstream_unavailable(&result) {
+            return result;
+        }
+        let stored_key = key.clone();
+        match offline_store::blocking(store, move |store| store.load(&stored_key)).await {
+            Ok(Some(reply)) => {
+                tracing::warn!("MFL unavailable, serving {} for league {} from the offline store ({}s old)",
+                           key.export, league_id, reply.freshness.age_secs);
+                self.schedule_revalidation(store.clone(), key, league_id, args);
+                Ok(reply)
+            }
+            Ok(None) => result,
+            Err(e) => {
+                tracing::error!("Could not read the offline store: {}", e);
+                result
+            }
+        }
+    }
+
+    async fn fetch_export(&self, key: &CacheKey, league_id: &str, args: &str) -> Result<MflReply, MflError> {
+        self.config.export_cache.get_or_fetch(key, || async {
+            let reply = self.send_to_league_host(league_id, true, |host| {
+                let url = format!("{}/{}/export?{}", host, self.year, args);
+                async move { self.send_request(&url).await }
+            }).await?;
+            // Never keep an error MFL sent as a successful reply
+            if let Some(error) = export_error(reply.status, &reply.body) {
+                return Err(error);
+            }
+            if let (Some(store), true) = (&self.config.offline_store, reply.status.is_success()) {
+                let (key, saved) = (key.clone(), reply.clone());
+                if let Err(e) = offline_store::blocking(store, move |store| store.save(&key, &saved)).await {
+                    tracing::error!("Could not write to the offline store: {}", e);
+                }
+            }
+            Ok(reply)
+        }).await
+    }
+
+    // Keeps retrying a stale export until MFL answers again, so the cache and the
+    // offline store are fresh again without waiting for the next request.
+    fn schedule_revalidation(&self, store: Arc<OfflineStore>, key: CacheKey, league_id: &str, args: &str) {
+        if !store.begin_revalidation(&key) {
+            return;
+        }
+        let api = self.clone();
+        let (league_id, args) = (league_id.to_string(), args.to_string());
+        actix_web::rt::spawn(async move {
+            for _ in 0..store.revalidate_attempts() {
+                // No point asking before the breaker lets requests through again
+                let wait = api.config.circuit_breaker.snapshot().retry_after_secs
+                    .map(Duration::from_secs)
+                    .unwrap_or_default()
+                    .max(store.revalidate_every());
+                actix_web::rt::time::sleep(wait).await;
+
+                match api.fetch_export(&key, &league_id, &args).await {
+                    Ok(reply) if reply.status.is_success() => {
+                        tracing::info!("Refreshed stale {} for league {}", key.export, league_id);
+                        break;
+                    }
+                    Ok(reply) => tracing::debug!("Refreshing stale {} failed with status {}", key.export, reply.status),
+                    Err(e) => tracing::debug!("Refreshing stale {} failed: {}", key.export, e),
+                }
+            }
+            store.end_revalidation(&key);
+        });
+    }
+
+    async fn send_league_post_request(&self, league_id: &str, form: &[(&str, &str)]) -> Result<MflReply, MflError> {
+        self.send_to_league_host(league_id, false, |host| {
+            let url = format!("{}/{}/import", host, self.year);
+            async move { self.send_post_request(&url, form).await }
+        }).await
+    }
+
+    fn cookie_headers(cookie: Option<&str>) -> Result<HeaderMap, MflError> {
         let mut headers = HeaderMap::new();
-        if let Some(cookie) = &self.mfl_user_id_cookie {
+        if let Some(cookie) = cookie {
             headers.insert(COOKIE, HeaderValue::from_str(&format!("MFL_USER_ID={}", cookie))?);
         }
-        // Await the send call
-        let response = self.client.get(url).headers(headers).send().await?;
-        Ok(response)
+        Ok(headers)
+    }
+
+    // Sends the request once with the current cookie and reads the whole body.
+    // Every request waits on the rate limiter first, and MFL's throttle replies
+    // block further requests for as long as MFL asks. While the circuit breaker
+    // is open we fail fast without calling MFL at all.
+    // With fixtures configured, replay answers from disk and record saves each reply.
+    async fn execute<F>(&self, build: &F) -> Result<MflReply, MflError>
+    where
+        F: Fn(&reqwest::Client) -> reqwest::RequestBuilder,
+    {
+        let cookie = self.mfl_user_id_cookie();
+        let request = build(&self.client).headers(Self::cookie_headers(cookie.as_deref())?).build()?;
+        let host = request.url().host_str().unwrap_or_default().to_string();
+        let export = export_type(&request);
+
+        let fixtures = self.config.fixtures.as_deref();
+        let fixture_request = fixtures.map(|_| FixtureRequest::from_request(&request));
+        if let (Some(fixtures), Some(fixture_request)) = (fixtures, &fixture_request) {
+            if fixtures.mode() == FixtureMode::Replay {
+                return replay(fixtures, fixture_request, request.url());
+            }
+        }
+
+        // Child of the handler span, so each inbound request shows the MFL calls it caused
+        let span = tracing::info_span!(
+            "mfl_call",
+            export = %export,
+            host = %host,
+            status = tracing::field::Empty,
+            duration_ms = tracing::field::Empty,
+        );
+        async {
+            self.config.rate_limiter.acquire(&host, cookie.as_deref()).await
+                .map_err(|retry_after| MflError::RateLimited { retry_after })?;
+
+            // From here on every path must report back to the breaker
+            let permit = self.config.circuit_breaker.allow_request()
+                .map_err(|retry_after| MflError::Unavailable { retry_after })?;
+
+            let started = std::time::Instant::now();
+            let response = match self.client.execute(request).await {
+                Ok(response) => response,
+                Err(e) => {
+                    permit.failure();
+                    self.config.metrics.observe_mfl_call(&export, None, started.elapsed());
+                    tracing::warn!(duration_ms = started.elapsed().as_millis() as u64, error = %e, "MFL call failed");
+                    return Err(e.into());
+                }
+            };
+            let status = response.status();
+            let retry_after = response.headers().get(RETRY_AFTER).and_then(parse_retry_after);
+            let url = response.url().clone();
+            let body = match response.text().await {
+                Ok(body) => body,
+                Err(e) => {
+                    permit.failure();
+                    self.config.metrics.observe_mfl_call(&export, None, started.elapsed());
+                    tracing::warn!(duration_ms = started.elapsed().as_millis() as u64, error = %e, "MFL call failed");
+                    return Err(RequestFailed(format!("Failed to read response body: {}", e)));
+                }
+            };
+            let elapsed = started.elapsed();
+            self.config.metrics.observe_mfl_call(&export, Some(status), elapsed);
+            let span = tracing::Span::current();
+            span.record("status", status.as_u16());
+            span.record("duration_ms", elapsed.as_millis() as u64);
+            tracing::info!("MFL replied");
+
+            if let (Some(fixtures), Some(fixture_request)) = (fixtures, &fixture_request) {
+                if let Err(e) = fixtures.save(fixture_request, status.as_u16(), &body, cookie.as_deref()) {
+                    tracing::error!("Could not record MFL fixture: {}", e);
+                }
+            }
+
+            if status.is_server_error() {
+                permit.failure();
+            } else {
+                permit.success();
+            }
+
+            if status == StatusCode::TOO_MANY_REQUESTS
+                || (body.contains("error") && MFL_THROTTLED_REGEX.is_match(&body)) {
+                let retry_after = retry_after.unwrap_or(DEFAULT_THROTTLE_BACKOFF);
+                tracing::warn!("MFL throttled requests to {}, backing off for {:?}", host, retry_after);
+                self.config.rate_limiter.throttled(&host, cookie.as_deref(), retry_after);
+                return Err(MflError::RateLimited { retry_after });
+            }
+
+            Ok(MflReply { status, url, body, freshness: Freshness::live() })
+        }
+        .instrument(span)
+        .await
+    }
+
+    /// Sends the request and, if MFL says the cookie is no longer valid, logs in
+    /// again from the credential vault and retries it once.
+    async fn execute_authenticated<F>(&self, build: F) -> Result<MflReply, MflError>
+    where
+        F: Fn(&reqwest::Client) -> reqwest::RequestBuilder,
+    {
+        let reply = self.execute(&build).await?;
+        if !requires_login(&reply) {
+            return Ok(reply);
+        }
+
+        tracing::warn!("MFL rejected the session cookie (status {}), logging in again", reply.status);
+        self.reauthenticate().await?;
+
+        let reply = self.execute(&build).await?;
+        if requires_login(&reply) {
+            // A fresh cookie didn't help, so this is a genuine permission problem; let the caller report it
+            tracing::warn!("MFL still refuses the request after logging in again (status {})", reply.status);
+        }
+        Ok(reply)
+    }
+
+    async fn reauthenticate(&self) -> Result<(), MflError> {
+        let vault = self.credential_vault.as_ref().ok_or_else(|| {
+            MflError::ReauthenticationRequired("no stored credentials for this session, please log in again".to_string())
+        })?;
+        let (username, password) = vault.open().map_err(|e| {
+            MflError::ReauthenticationRequired(format!("stored credentials are unreadable: {}", e))
+        })?;
+        self.login(&username, &password).await.map_err(|e| {
+            MflError::ReauthenticationRequired(format!("logging in again failed: {}", e))
+        })
+    }
+
+    // Exports are idempotent, so timeouts, connection failures and 5xx replies are retried
+    async fn send_request(&self, url: &str) -> Result<MflReply, MflError> {
+        let mut attempt = 0;
+        loop {
+            let result = self.execute_authenticated(|client| client.get(url)).await;
+            let retryable = match &result {
+                Ok(reply) => reply.status.is_server_error(),
+                Err(MflError::Network(e)) => e.is_timeout() || e.is_connect(),
+                Err(_) => false,
+            };
+            if !retryable || attempt >= self.config.max_retries {
+                return result;
+            }
+
+            let delay = self.config.retry_delay(attempt);
+            attempt += 1;
+            tracing::warn!("MFL request failed, retry {} of {} in {:?}", attempt, self.config.max_retries, delay);
+            actix_web::rt::time::sleep(delay).await;
+        }
+    }
+
+    // Imports are not idempotent and are never retried
+    async fn send_post_request(&self, url: &str, form: &[(&str, &str)]) -> Result<MflReply, MflError> {
+        self.execute_authenticated(|client| client.post(url).form(form)).await
     }
 
     pub async fn get_league_info(
         &self,
         league_id: &str
     ) -> Result<String, Box<dyn std::error::Error>> {
-        let url = format!("{}/{}/export", MFL_API_URL.to_string(), self.year);
         let args = format!("TYPE=league&L={}&JSON=1", league_id);
-        let req_url = format!("{}?{}", url, args);
-        log::info!("Making request to get league info {}", req_url);
-        let resp = self.send_request(&req_url).await?;
-        Ok(resp.text().await?)
+        tracing::info!("Making request to get league info {}", args);
+        let resp = self.send_league_request(league_id, &args).await?;
+        Ok(resp.body)
+    }
+
+    /// Fetches a league export from the league's host, through the export cache
+    /// and, when MFL is down, the offline store.
+    pub async fn export<E: MflExport>(
+        &self,
+        league_id: &str,
+        params: &E::Params
+    ) -> Result<Fetched<E::Response>, MflError> {
+        let args = export_args::<E>(Some(league_id), params);
+        tracing::info!("Making request to get {} {}", E::TYPE, args);
+        let reply = self.send_league_request(league_id, &args).await?;
+        read_export::<E>(reply)
+    }
+
+    // Exports that only the api host answers, never cached
+    async fn api_host_export<E: MflExport>(
+        &self,
+        league_id: Option<&str>,
+        params: &E::Params
+    ) -> Result<Fetched<E::Response>, MflError> {
+        let req_url = format!("{}/{}/export?{}", self.config.base_url, self.year, export_args::<E>(league_id, params));
+        tracing::info!("Making request to get {} {}", E::TYPE, req_url);
+        let reply = self.send_request(&req_url).await?;
+        read_export::<E>(reply)
     }
 
     pub async fn get_free_agents(
         &self,
         league_id: &str,
         position: Option<&str>
-    ) -> Result<Vec<FreeAgentPlayer>, MflError> {
-        let url = format!("{}/{}/export?TYPE=freeAgents", MFL_API_URL.to_string(), self.year);
-
-        let args = match position {
-            Some(pos) => format!("&L={}&POSITION={}&JSON=1", league_id, pos),
-            None => format!("&L={}&JSON=1", league_id),
-        };
+    ) -> Result<Fetched<Vec<FreeAgentPlayer>>, MflError> {
+        let fetched = self.export::<FreeAgentsExport>(league_id, &position.map(str::to_string)).await?;
+        Ok(fetched.map(|response| response.free_agents.league_unit.into_iter().flat_map(|unit| unit.player).collect()))
+    }
 
-        let req_url = format!("{}{}", url, args);
-        log::info!("Making request to get free agents {}", req_url);
-        let resp = self.send_request(&req_url).await?; // Get the Response from the Result
+    pub async fn get_players(
+        &self,
+        league_id: &str,
+        player_ids: &str // can be single player_id or list separated by commas
+    ) -> Result<Fetched<PlayersPlayers>, MflError> {
+        let fetched = self.export::<PlayersExport>(league_id, player_ids).await?;
+        Ok(fetched.map(|response| response.players))
+    }
 
-        let status = resp.status(); // *** Get status code ***
-        let resp_body = resp.text().await.map_err(|e| RequestFailed(format!("Failed to read free agents response body: {}", e)))?; // *** Read body text ***
+    /// Lists the leagues (and the franchise owned in each) for the logged in user.
+    pub async fn get_my_leagues(&self) -> Result<Vec<MyLeague>, MflError> {
+        let response = self.api_host_export::<MyLeaguesExport>(None, &()).await?.data;
 
-        if !status.is_success() { // *** Check status code ***
-            log::info!("MFL API error fetching free agents. Status: {}, Body: {}", status, resp_body);
-            return Err(ApiStatusError { status, body: resp_body });
+        // myleagues tells us each league's home URL, so remember the hosts while we have them
+        for league in &response.leagues.league {
+            if let Some(host) = league.url.as_deref().and_then(base_url_of) {
+                self.cache_league_host(&league.league_id, &host);
+            }
         }
 
-        // *** Deserialize only if status was success ***
-        let response: FreeAgentResponse = serde_json::from_str(&resp_body)
-            .map_err(|e| {
-                // Add context if JSON parsing fails even on success status
-                log::info!("Failed to parse successful MFL free agents response. Status: {}, Body: {}, Error: {}", status, resp_body, e);
-                MflError::JsonParse(e)
-            })?;
+        Ok(response.leagues.league)
+    }
 
-        Ok(response.freeAgents.leagueUnit.player)
+    pub async fn get_abilities(&self, league_id: &str) -> Result<Abilities, MflError> {
+        Ok(self.export::<AbilitiesExport>(league_id, &()).await?.data.abilities)
     }
 
-    pub async fn get_players(
+    pub async fn get_message_board(
+        &self,
+        league_id: &str
+    ) -> Result<Fetched<MessageBoard>, MflError> {
+        let fetched = self.export::<MessageBoardExport>(league_id, &()).await?;
+        Ok(fetched.map(|response| response.message_board))
+    }
+
+    pub async fn get_message_board_thread(
         &self,
         league_id: &str,
-        player_ids: &str // can be single player_id or list separated by commas
-    ) -> Result<PlayersPlayers, MflError> {
-        let url = format!("{}/{}/export?TYPE=players", MFL_API_URL.to_string(), self.year);
+        thread_id: &str
+    ) -> Result<Fetched<MessageBoardThread>, MflError> {
+        let fetched = self.export::<MessageBoardThreadExport>(league_id, thread_id).await?;
+        Ok(fetched.map(|response| response.message_board_thread))
+    }
 
-        let args = format!("&L={}&PLAYERS={}&JSON=1", league_id, player_ids);
+    /// Posts to the league message board. Replies to `thread_id` when given,
+    /// otherwise MFL starts a new thread with `subject`. `franchise_id` posts as
+    /// that franchise, which MFL only allows the commissioner.
+    pub async fn post_message(
+        &self,
+        league_id: &str,
+        thread_id: Option<&str>,
+        subject: &str,
+        body: &str,
+        franchise_id: Option<&str>
+    ) -> Result<(), MflError> {
+        let mut form = vec![("TYPE", "messageBoard"), ("L", league_id), ("SUBJECT", subject), ("BODY", body)];
+        if let Some(thread) = thread_id {
+            form.push(("THREAD", thread));
+        }
+        if let Some(franchise_id) = franchise_id {
+            form.push(("FRANCHISE_ID", franchise_id));
+        }
 
-        let req_url = format!("{}{}", url, args);
-        log::info!("Making request to get player info {}", req_url);
-        let resp = self.send_request(&req_url).await?; // Get the Response from the Result
+        tracing::info!("Posting message board import for league {} (thread: {:?})", league_id, thread_id);
+        let resp = self.send_league_post_request(league_id, &form).await?;
 
-        let status = resp.status(); // *** Get status code ***
-        let resp_body = resp.text().await.map_err(|e| RequestFailed(format!("Failed to read players response body: {}", e)))?; // *** Read body text ***
+        let status = resp.status;
+        let resp_body = resp.body;
 
-        if !status.is_success() { // *** Check status code ***
-            log::info!("MFL API error fetching players. Status: {}, Body: {}", status, resp_body);
+        if !status.is_success() {
+            tracing::info!("MFL API error posting message. Status: {}, Body: {}", status, resp_body);
             return Err(ApiStatusError { status, body: resp_body });
         }
 
-        // *** Deserialize only if status was success ***
-        let response: PlayersStatusResponse = serde_json::from_str(&resp_body)
-            .map_err(|e| {
-                // Add context if JSON parsing fails even on success status
-                log::info!("Failed to parse successful MFL players response. Status: {}, Body: {}, Error: {}", status, resp_body, e);
-                MflError::JsonParse(e)
-            })?;
+        if let Err(e) = parse_xml_reply(&resp_body) {
+            tracing::info!("MFL rejected message board import: {}", e);
+            return Err(e);
+        }
+
+        // Let readers see the new post right away
+        self.config.export_cache.invalidate(Some("messageBoard"), Some(league_id));
+        self.config.export_cache.invalidate(Some("messageBoardThread"), Some(league_id));
+
+        Ok(())
+    }
+}
+
+/// Reduces a URL such as `https://www48.myfantasyleague.com/2025/home/12345`
+/// to its base URL, `https://www48.myfantasyleague.com`.
+fn base_url_of(url: &str) -> Option<String> {
+    let parsed = reqwest::Url::parse(url).ok()?;
+    parsed.host_str()?;
+    Some(parsed.origin().ascii_serialization())
+}
+
+// TYPE=...&L=...&<params>&JSON=1
+fn export_args<E: MflExport>(league_id: Option<&str>, params: &E::Params) -> String {
+    let mut args = vec![format!("TYPE={}", E::TYPE)];
+    args.extend(league_id.map(|id| format!("L={}", id)));
+    args.extend(E::query(params).into_iter().map(|(name, value)| format!("{}={}", name, value)));
+    args.push("JSON=1".to_string());
+    args.join("&")
+}
+
+// Checks the status and decodes the JSON of an export reply
+fn read_export<E: MflExport>(reply: MflReply) -> Result<Fetched<E::Response>, MflError> {
+    let MflReply { status, body, freshness, .. } = reply;
+    if !status.is_success() {
+        tracing::info!("MFL API error fetching {}. Status: {}, Body: {}", E::TYPE, status, body);
+        return Err(ApiStatusError { status, body });
+    }
+    if let Some(error) = export_error(status, &body) {
+        return Err(error);
+    }
+    let data = serde_json::from_str(&body).map_err(|e| {
+        tracing::info!("Failed to parse successful MFL {} response. Status: {}, Body: {}, Error: {}", E::TYPE, status, body, e);
+        MflError::JsonParse(e)
+    })?;
+    Ok(Fetched { data, freshness })
+}
+
+fn replay(fixtures: &HttpFixtures, request: &FixtureRequest, url: &reqwest::Url) -> Result<MflReply, MflError> {
+    let describe = || format!("{} {}?{}", request.method, request.path, url.query().unwrap_or_default());
+    let response = fixtures.find(request).ok_or_else(|| MflError::FixtureNotFound(describe()))?;
+    let status = StatusCode::from_u16(response.status).map_err(|_| MflError::FixtureNotFound(describe()))?;
+    Ok(MflReply { status, url: url.clone(), body: response.body, freshness: Freshness::live() })
+}
+
+// Metric label for a request: the export/import TYPE, or the last path segment (e.g. "login")
+fn export_type(request: &reqwest::Request) -> String {
+    let from_query = request.url().query_pairs()
+        .find(|(key, _)| key == "TYPE")
+        .map(|(_, value)| value.into_owned());
+    let from_form = || request.body()
+        .and_then(|body| body.as_bytes())
+        .and_then(|bytes| serde_urlencoded::from_bytes::<Vec<(String, String)>>(bytes).ok())
+        .and_then(|pairs| pairs.into_iter().find(|(key, _)| key == "TYPE"))
+        .map(|(_, value)| value);
+    from_query.or_else(from_form)
+        .or_else(|| request.url().path_segments().and_then(|mut segments| segments.next_back()).map(String::from))
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+// Failures that mean MFL itself is down or refusing us, rather than a bad request
+fn upstream_unavailable(result: &Result<MflReply, MflError>) -> bool {
+    match result {
+        Ok(reply) => reply.status.is_server_error(),
+        Err(MflError::Network(_))
+        | Err(MflError::RequestFailed(_))
+        | Err(MflError::RateLimited { .. })
+        | Err(MflError::Unavailable { .. }) => true,
+        Err(_) => false,
+    }
+}
+
+// MFL answers some failed exports with 200 and an error document instead of the
+// export: `{"error":{"$t":"..."}}` with JSON=1, or `<error>...</error>`
+fn export_error(status: StatusCode, body: &str) -> Option<MflError> {
+    if !body.contains("error") {
+        return None;
+    }
+    let message = match serde_json::from_str::<serde_json::Value>(body) {
+        Ok(json) => {
+            let error = json.get("error")?;
+            error.get("$t").unwrap_or(error).as_str().unwrap_or_default().to_string()
+        }
+        Err(_) => match parse_xml_reply(body) {
+            Err(MflError::ImportRejected(message)) => message,
+            Err(e @ (MflError::RateLimited { .. } | MflError::PermissionDenied(_))) => return Some(e),
+            _ => return None,
+        },
+    };
+    Some(if MFL_THROTTLED_REGEX.is_match(&message) {
+        MflError::RateLimited { retry_after: DEFAULT_THROTTLE_BACKOFF }
+    } else if MFL_LOGIN_REQUIRED_REGEX.is_match(&message) {
+        MflError::PermissionDenied(message)
+    } else {
+        ApiStatusError { status, body: body.to_string() }
+    })
+}
+
+fn requires_login(reply: &MflReply) -> bool {
+    matches!(reply.status, StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN)
+        || (reply.body.contains("error") && MFL_LOGIN_REQUIRED_REGEX.is_match(&reply.body))
+}
+
+/// Reads an XML reply from an MFL import or login. `<status>OK</status>` becomes an
+/// [`ImportResult`]; `<error>` becomes the [`MflError`] matching MFL's message, which it carries.
+pub fn parse_xml_reply(body: &str) -> Result<ImportResult, MflError> {
+    let unreadable = |e: quick_xml::Error| MflError::UnexpectedReply(format!("invalid XML: {}", e));
+    let mut reader = Reader::from_str(body);
+    reader.config_mut().trim_text(true);
+
+    // Skip the declaration, comments and whitespace; the root element says what this is
+    let (root, attributes, empty) = loop {
+        match reader.read_event().map_err(unreadable)? {
+            Event::Start(element) => break (element_name(&element), xml_attributes(&element)?, false),
+            Event::Empty(element) => break (element_name(&element), xml_attributes(&element)?, true),
+            Event::Eof => return Err(MflError::UnexpectedReply("no XML element in the reply".to_string())),
+            _ => {}
+        }
+    };
+
+    // The root's text, ignoring any markup nested inside it
+    let mut text = String::new();
+    let mut depth = 0;
+    if !empty {
+        loop {
+            match reader.read_event().map_err(unreadable)? {
+                Event::Text(chunk) => text.push_str(&chunk.unescape().map_err(unreadable)?),
+                Event::CData(chunk) => text.push_str(&String::from_utf8_lossy(&chunk)),
+                Event::Start(_) => depth += 1,
+                Event::End(_) if depth == 0 => break,
+                Event::End(_) => depth -= 1,
+                Event::Eof => return Err(MflError::UnexpectedReply(format!("<{}> is never closed", root))),
+                _ => {}
+            }
+        }
+    }
+    let text = text.trim().to_string();
 
-        Ok(response.players)
+    match root.as_str() {
+        "status" if text.eq_ignore_ascii_case("OK") => Ok(ImportResult { status: text, attributes }),
+        "status" => Err(MflError::ImportRejected(text)),
+        "error" if MFL_THROTTLED_REGEX.is_match(&text) => Err(MflError::RateLimited { retry_after: DEFAULT_THROTTLE_BACKOFF }),
+        "error" if MFL_LOGIN_REQUIRED_REGEX.is_match(&text) => Err(MflError::PermissionDenied(text)),
+        "error" => Err(MflError::ImportRejected(text)),
+        other => Err(MflError::UnexpectedReply(format!("expected <status> or <error>, got <{}>", other))),
     }
-}
\ No newline at end of file
+}
+
+fn element_name(element: &BytesStart) -> String {
+    String::from_utf8_lossy(element.local_name().as_ref()).into_owned()
+}
+
+fn xml_attributes(element: &BytesStart) -> Result<HashMap<String, String>, MflError> {
+    element.attributes()
+        .map(|attribute| {
+            let attribute = attribute.map_err(|e| MflError::UnexpectedReply(format!("invalid XML attribute: {}", e)))?;
+            let value = attribute.unescape_value()
+                .map_err(|e| MflError::UnexpectedReply(format!("invalid XML attribute: {}", e)))?;
+            Ok((String::from_utf8_lossy(attribute.key.local_name().as_ref()).into_owned(), value.into_owned()))
+        })
+        .collect()
+}
+
+// Retry-After is either delay-seconds or an HTTP date; MFL sends seconds
+fn parse_retry_after(value: &HeaderValue) -> Option<Duration> {
+    value.to_str().ok()?.trim().parse::<u64>().ok().map(Duration::from_secs)
+}
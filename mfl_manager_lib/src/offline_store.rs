@@ -0,0 +1,189 @@
+// src/offline_store.rs
+use std::collections::HashSet;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use reqwest::StatusCode;
+use rusqlite::{params, Connection, OptionalExtension};
+use thiserror::Error;
+use crate::app_state::unix_now;
+use crate::export_cache::{CacheKey, DataSource, Freshness};
+use crate::mfl_api::MflReply;
+
+#[derive(Error, Debug)]
+pub enum OfflineStoreError {
+    #[error("Offline store lock was poisoned")]
+    LockPoisoned,
+
+    #[error("Offline store database error: {0}")]
+    Database(#[from] rusqlite::Error),
+
+    #[error("Stored reply is unreadable: {0}")]
+    Corrupt(String),
+
+    #[error("Offline store call did not complete")]
+    Blocking,
+}
+
+/// Runs `call` against `store` on Actix's blocking thread pool, so SQLite never
+/// stalls the worker serving other requests.
+pub async fn blocking<T, F>(store: &Arc<OfflineStore>, call: F) -> Result<T, OfflineStoreError>
+where
+    F: FnOnce(&OfflineStore) -> Result<T, OfflineStoreError> + Send + 'static,
+    T: Send + 'static,
+{
+    let store = store.clone();
+    actix_web::web::block(move || call(&store)).await
+        .map_err(|_| OfflineStoreError::Blocking)?
+}
+
+/// Periodically removes replies fetched more than `retention` ago. Must be called from within the Actix runtime.
+pub fn spawn_offline_store_sweeper(store: Arc<OfflineStore>, retention: Duration, every: Duration) {
+    actix_web::rt::spawn(async move {
+        let mut interval = actix_web::rt::time::interval(every);
+        loop {
+            interval.tick().await;
+            let cutoff = unix_now().saturating_sub(retention.as_secs());
+            match blocking(&store, move |store| store.remove_older_than(cutoff)).await {
+                Ok(0) => {}
+                Ok(removed) => tracing::info!("Offline store sweeper removed {} old replies", removed),
+                Err(e) => tracing::error!("Offline store sweeper failed: {}", e),
+            }
+        }
+    });
+}
+
+/// Keeps the last successful reply of every league export on disk, so reads can
+/// still be answered (marked stale) while MFL is unreachable.
+pub struct OfflineStore {
+    conn: Mutex<Connection>,
+    // Keys with a background refresh already scheduled
+    revalidating: Mutex<HashSet<CacheKey>>,
+    revalidate_every: Duration,
+    revalidate_attempts: u32,
+}
+
+impl std::fmt::Debug for OfflineStore {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("OfflineStore")
+            .field("revalidate_every", &self.revalidate_every)
+            .field("revalidate_attempts", &self.revalidate_attempts)
+            .finish_non_exhaustive()
+    }
+}
+
+impl OfflineStore {
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, OfflineStoreError> {
+        Self::with_connection(Connection::open(path)?)
+    }
+
+    pub fn with_connection(conn: Connection) -> Result<Self, OfflineStoreError> {
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS export_replies (
+                export     TEXT NOT NULL,
+                league_id  TEXT NOT NULL,
+                year       TEXT NOT NULL,
+                params     TEXT NOT NULL,
+                user       TEXT NOT NULL,
+                status     INTEGER NOT NULL,
+                url        TEXT NOT NULL,
+                body       TEXT NOT NULL,
+                fetched_at INTEGER NOT NULL,
+                PRIMARY KEY (export, league_id, year, params, user)
+            );",
+        )?;
+        Ok(OfflineStore {
+            conn: Mutex::new(conn),
+            revalidating: Mutex::new(HashSet::new()),
+            revalidate_every: Duration::from_secs(30),
+            revalidate_attempts: 20,
+        })
+    }
+
+    /// How often a stale entry is retried in the background, and how many times
+    /// before giving up until the next request serves it again.
+    pub fn with_revalidation(mut self, every: Duration, attempts: u32) -> Self {
+        self.revalidate_every = every;
+        self.revalidate_attempts = attempts.max(1);
+        self
+    }
+
+    pub fn revalidate_every(&self) -> Duration {
+        self.revalidate_every
+    }
+
+    pub fn revalidate_attempts(&self) -> u32 {
+        self.revalidate_attempts
+    }
+
+    /// Records `reply` as the last good reply for `key`.
+    pub fn save(&self, key: &CacheKey, reply: &MflReply) -> Result<(), OfflineStoreError> {
+        self.save_at(key, reply, unix_now())
+    }
+
+    /// Like `save`, with an explicit fetch time (seconds since the Unix epoch).
+    pub fn save_at(&self, key: &CacheKey, reply: &MflReply, fetched_at: u64) -> Result<(), OfflineStoreError> {
+        let conn = self.conn.lock().map_err(|_| OfflineStoreError::LockPoisoned)?;
+        conn.execute(
+            "INSERT OR REPLACE INTO export_replies (export, league_id, year, params, user, status, url, body, fetched_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            params![key.export, key.league_id, key.year, key.params, key.user,
+                    reply.status.as_u16(), reply.url.as_str(), reply.body, fetched_at],
+        )?;
+        Ok(())
+    }
+
+    /// How many replies are stored; fails if the database can't be read.
+    pub fn count(&self) -> Result<usize, OfflineStoreError> {
+        let conn = self.conn.lock().map_err(|_| OfflineStoreError::LockPoisoned)?;
+        let count: i64 = conn.query_row("SELECT COUNT(*) FROM export_replies", [], |row| row.get(0))?;
+        Ok(count as usize)
+    }
+
+    /// Deletes replies fetched before `cutoff` (seconds since the Unix epoch), returning how many.
+    pub fn remove_older_than(&self, cutoff: u64) -> Result<usize, OfflineStoreError> {
+        let conn = self.conn.lock().map_err(|_| OfflineStoreError::LockPoisoned)?;
+        Ok(conn.execute("DELETE FROM export_replies WHERE fetched_at < ?1", params![cutoff])?)
+    }
+
+    /// The last good reply for `key`, marked stale with its age.
+    pub fn load(&self, key: &CacheKey) -> Result<Option<MflReply>, OfflineStoreError> {
+        let conn = self.conn.lock().map_err(|_| OfflineStoreError::LockPoisoned)?;
+        let row = conn.query_row(
+            "SELECT status, url, body, fetched_at FROM export_replies
+             WHERE export = ?1 AND league_id = ?2 AND year = ?3 AND params = ?4 AND user = ?5",
+            params![key.export, key.league_id, key.year, key.params, key.user],
+            |row| Ok((
+                row.get::<_, u16>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, String>(2)?,
+                row.get::<_, u64>(3)?,
+            )),
+        ).optional()?;
+        drop(conn);
+
+        let Some((status, url, body, fetched_at)) = row else {
+            return Ok(None);
+        };
+        Ok(Some(MflReply {
+            status: StatusCode::from_u16(status).map_err(|e| OfflineStoreError::Corrupt(e.to_string()))?,
+            url: reqwest::Url::parse(&url).map_err(|e| OfflineStoreError::Corrupt(e.to_string()))?,
+            body,
+            freshness: Freshness {
+                source: DataSource::Stale,
+                age_secs: unix_now().saturating_sub(fetched_at),
+            },
+        }))
+    }
+
+    /// Marks `key` as being refreshed in the background. Returns false if it already is.
+    pub fn begin_revalidation(&self, key: &CacheKey) -> bool {
+        self.revalidating.lock().map(|mut keys| keys.insert(key.clone())).unwrap_or(false)
+    }
+
+    pub fn end_revalidation(&self, key: &CacheKey) {
+        if let Ok(mut keys) = self.revalidating.lock() {
+            keys.remove(key);
+        }
+    }
+}
@@ -0,0 +1,68 @@
+// src/openapi.rs
+use utoipa::openapi::security::{HttpAuthScheme, HttpBuilder, SecurityScheme};
+use utoipa::{Modify, OpenApi};
+use crate::api_keys::{ApiKeyInfo, Permission};
+use crate::circuit_breaker::{CircuitSnapshot, CircuitState};
+use crate::errors::{ErrorCode, ErrorResponse, UpstreamError};
+use crate::export_cache::{DataSource, Freshness};
+use crate::handler_models::{
+    ActiveLeagueRequest, CacheInvalidationResponse, CreateApiKeyRequest, CreateApiKeyResponse, DependencyCheck, DependencyStatus, FreeAgentsResponse, HealthResponse, LoginRequest,
+    LoginResponse, MeResponse, MessageBoardResponse, MessagePost, MessageThreadResponse, MessageThreadSummary,
+    PlayerResponse, PostMessageRequest, PostMessageResponse, ReadinessResponse, Role, UserLeague,
+};
+use crate::handlers;
+
+/// The OpenAPI document for every handler. Add new handlers to `paths` as well as to main.rs.
+#[derive(OpenApi)]
+#[openapi(
+    info(title = "MFL Manager API", description = "Session-based access to MyFantasyLeague for league tools"),
+    paths(
+        handlers::login_handler,
+        handlers::logout_handler,
+        handlers::refresh_session_handler,
+        handlers::get_me_handler,
+        handlers::set_active_league_handler,
+        handlers::get_free_agents_handler,
+        handlers::get_message_board_handler,
+        handlers::get_message_thread_handler,
+        handlers::post_message_thread_handler,
+        handlers::reply_message_handler,
+        handlers::invalidate_cache_handler,
+        handlers::create_api_key_handler,
+        handlers::list_api_keys_handler,
+        handlers::revoke_api_key_handler,
+        handlers::health_check,
+        handlers::readiness_handler,
+        handlers::metrics_handler,
+    ),
+    components(schemas(
+        LoginRequest, LoginResponse, Role, MeResponse, UserLeague, ActiveLeagueRequest, PlayerResponse,
+        FreeAgentsResponse, MessageBoardResponse, MessageThreadSummary, MessageThreadResponse, MessagePost,
+        PostMessageRequest, PostMessageResponse, CacheInvalidationResponse, CreateApiKeyRequest, CreateApiKeyResponse,
+        ApiKeyInfo, Permission, HealthResponse,
+        ReadinessResponse,
+        DependencyCheck, DependencyStatus, CircuitSnapshot, CircuitState, Freshness, DataSource, ErrorResponse,
+        ErrorCode, UpstreamError,
+    )),
+    modifiers(&BearerAuth)
+)]
+pub struct ApiDoc;
+
+// Access tokens (JWTs) from /login, or API keys, are sent as `Authorization: Bearer <token>`.
+// API keys may also be sent as `X-API-Key: <key>`.
+struct BearerAuth;
+
+impl Modify for BearerAuth {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        let components = openapi.components.get_or_insert_with(Default::default);
+        components.add_security_scheme(
+            "bearer_auth",
+            SecurityScheme::Http(HttpBuilder::new().scheme(HttpAuthScheme::Bearer).bearer_format("JWT or API key").build()),
+        );
+    }
+}
+
+/// The spec served at /openapi.json.
+pub fn api_doc() -> utoipa::openapi::OpenApi {
+    ApiDoc::openapi()
+}
@@ -0,0 +1,168 @@
+// src/rate_limit.rs
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use once_cell::sync::Lazy;
+
+/// Token bucket parameters: up to `burst` requests at once, refilled at `per_second`.
+#[derive(Debug, Clone, Copy)]
+pub struct RateLimit {
+    pub burst: f64,
+    pub per_second: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum BucketKey {
+    Host(String),
+    // Hash of the MFL cookie, so the map never holds the cookie itself
+    User(u64),
+}
+
+#[derive(Debug)]
+struct Bucket {
+    tokens: f64,
+    refreshed: Instant,
+    // Set when MFL told us to back off (429 / Retry-After)
+    blocked_until: Option<Instant>,
+}
+
+impl Bucket {
+    fn full(limit: &RateLimit, now: Instant) -> Self {
+        Bucket { tokens: limit.burst, refreshed: now, blocked_until: None }
+    }
+
+    fn refill(&mut self, limit: &RateLimit, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.refreshed).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * limit.per_second).min(limit.burst);
+        self.refreshed = now;
+    }
+
+    // How long until this bucket lets one more request through
+    fn wait_for_token(&self, limit: &RateLimit, now: Instant) -> Duration {
+        let blocked = self.blocked_until.map(|until| until.saturating_duration_since(now)).unwrap_or_default();
+        let deficit = if self.tokens >= 1.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64((1.0 - self.tokens) / limit.per_second)
+        };
+        blocked.max(deficit)
+    }
+
+    fn is_idle(&self, limit: &RateLimit, now: Instant) -> bool {
+        self.tokens >= limit.burst && self.blocked_until.is_none_or(|until| until <= now)
+    }
+}
+
+/// Outbound limiter for MFL calls, shared by every `MflApi`. Each request takes a
+/// token from its host's bucket and from its user's (cookie's) bucket. Callers wait
+/// for tokens, up to `max_wait`; beyond that the request is refused.
+#[derive(Debug)]
+pub struct RateLimiter {
+    per_host: RateLimit,
+    per_user: RateLimit,
+    max_wait: Duration,
+    buckets: Mutex<HashMap<BucketKey, Bucket>>,
+}
+
+// Buckets are pruned once the map grows past this many entries
+const PRUNE_THRESHOLD: usize = 1024;
+
+static SHARED_LIMITER: Lazy<Arc<RateLimiter>> = Lazy::new(|| Arc::new(RateLimiter::default()));
+
+impl Default for RateLimiter {
+    fn default() -> Self {
+        RateLimiter::new(
+            RateLimit { burst: 10.0, per_second: 5.0 },
+            RateLimit { burst: 5.0, per_second: 2.0 },
+            Duration::from_secs(10),
+        )
+    }
+}
+
+impl RateLimiter {
+    pub fn new(per_host: RateLimit, per_user: RateLimit, max_wait: Duration) -> Self {
+        RateLimiter {
+            per_host,
+            per_user,
+            max_wait,
+            buckets: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// The process-wide limiter used by `MflApi` unless another one is supplied.
+    pub fn shared() -> Arc<RateLimiter> {
+        SHARED_LIMITER.clone()
+    }
+
+    fn keys(&self, host: &str, user: Option<&str>) -> Vec<(BucketKey, RateLimit)> {
+        let mut keys = vec![(BucketKey::Host(host.to_string()), self.per_host)];
+        if let Some(user) = user {
+            let mut hasher = DefaultHasher::new();
+            user.hash(&mut hasher);
+            keys.push((BucketKey::User(hasher.finish()), self.per_user));
+        }
+        keys
+    }
+
+    /// Takes a token for `host` and `user`, returning how long to wait before sending.
+    /// Returns `Err(wait)` without taking anything if the wait would exceed `max_wait`.
+    pub fn reserve(&self, host: &str, user: Option<&str>) -> Result<Duration, Duration> {
+        let now = Instant::now();
+        let keys = self.keys(host, user);
+        let Ok(mut buckets) = self.buckets.lock() else {
+            // A poisoned limiter shouldn't take the whole API down
+            return Ok(Duration::ZERO);
+        };
+
+        if buckets.len() > PRUNE_THRESHOLD {
+            let (per_host, per_user) = (self.per_host, self.per_user);
+            buckets.retain(|key, bucket| {
+                let limit = match key { BucketKey::Host(_) => per_host, BucketKey::User(_) => per_user };
+                bucket.refill(&limit, now);
+                !bucket.is_idle(&limit, now)
+            });
+        }
+
+        let mut wait = Duration::ZERO;
+        for (key, limit) in &keys {
+            let bucket = buckets.entry(key.clone()).or_insert_with(|| Bucket::full(limit, now));
+            bucket.refill(limit, now);
+            wait = wait.max(bucket.wait_for_token(limit, now));
+        }
+        if wait > self.max_wait {
+            return Err(wait);
+        }
+
+        // Going into debt queues later callers behind this one
+        for (key, _) in &keys {
+            if let Some(bucket) = buckets.get_mut(key) {
+                bucket.tokens -= 1.0;
+            }
+        }
+        Ok(wait)
+    }
+
+    /// Waits for a token. Returns `Err(retry_after)` if the wait would be too long.
+    pub async fn acquire(&self, host: &str, user: Option<&str>) -> Result<(), Duration> {
+        let wait = self.reserve(host, user)?;
+        if !wait.is_zero() {
+            tracing::debug!("Rate limiting MFL request to {} for {:?}", host, wait);
+            actix_web::rt::time::sleep(wait).await;
+        }
+        Ok(())
+    }
+
+    /// Records that MFL throttled us: nothing goes to `host` (or from `user`) for `retry_after`.
+    pub fn throttled(&self, host: &str, user: Option<&str>, retry_after: Duration) {
+        let now = Instant::now();
+        let until = now + retry_after;
+        if let Ok(mut buckets) = self.buckets.lock() {
+            for (key, limit) in self.keys(host, user) {
+                let bucket = buckets.entry(key).or_insert_with(|| Bucket::full(&limit, now));
+                bucket.blocked_until = Some(bucket.blocked_until.map_or(until, |current| current.max(until)));
+            }
+        }
+    }
+}
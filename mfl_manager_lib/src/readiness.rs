@@ -0,0 +1,119 @@
+// src/readiness.rs
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use chrono::Datelike;
+use crate::app_state::{self, SessionStore};
+use crate::circuit_breaker::CircuitState;
+use crate::handler_models::{DependencyCheck, DependencyStatus, ReadinessResponse};
+use crate::mfl_api::{MflApi, MflClientConfig};
+use crate::offline_store;
+
+// Probes give up long before MFL's read timeout would
+pub const MFL_PROBE_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Remembers the last MFL probe for a few seconds, so frequent readiness checks don't
+/// each spend a request from the rate limiter every session shares.
+#[derive(Debug)]
+pub struct MflProbeCache {
+    ttl: Duration,
+    last: Mutex<Option<(Instant, DependencyCheck)>>,
+}
+
+impl MflProbeCache {
+    pub fn new(ttl: Duration) -> Self {
+        MflProbeCache { ttl, last: Mutex::new(None) }
+    }
+
+    fn get(&self) -> Option<DependencyCheck> {
+        let last = self.last.lock().ok()?;
+        last.as_ref().filter(|(at, _)| at.elapsed() < self.ttl).map(|(_, check)| check.clone())
+    }
+
+    fn put(&self, check: &DependencyCheck) {
+        if let Ok(mut last) = self.last.lock() {
+            *last = Some((Instant::now(), check.clone()));
+        }
+    }
+}
+
+fn up(critical: bool, detail: Option<String>) -> DependencyCheck {
+    DependencyCheck { status: DependencyStatus::Up, critical, latency_ms: None, detail }
+}
+
+fn down(critical: bool, detail: String) -> DependencyCheck {
+    DependencyCheck { status: DependencyStatus::Down, critical, latency_ms: None, detail: Some(detail) }
+}
+
+/// Checks every dependency the API needs to serve requests.
+/// MFL and the circuit breaker only count as critical without an offline store,
+/// since with one reads are still answered (marked stale) while MFL is down.
+pub async fn check(
+    config: &MflClientConfig,
+    sessions: Option<&SessionStore>,
+    probes: Option<&MflProbeCache>,
+) -> ReadinessResponse {
+    let mut checks = BTreeMap::new();
+    let mfl_critical = config.offline_store.is_none();
+
+    let breaker = config.circuit_breaker.snapshot();
+    let breaker_open = breaker.state == CircuitState::Open;
+    checks.insert("circuit_breaker".to_string(), if breaker_open {
+        down(mfl_critical, format!("open after {} consecutive failures, retrying in {}s",
+                                   breaker.consecutive_failures, breaker.retry_after_secs.unwrap_or_default()))
+    } else {
+        up(mfl_critical, Some(format!("{:?}", breaker.state).to_lowercase()))
+    });
+
+    // An open breaker would refuse the probe anyway; don't count that as another failure
+    let mfl = if breaker_open {
+        down(mfl_critical, "not probed while the circuit breaker is open".to_string())
+    } else if let Some(cached) = probes.and_then(MflProbeCache::get) {
+        cached
+    } else {
+        let check = probe_mfl(config, mfl_critical).await;
+        if let Some(probes) = probes {
+            probes.put(&check);
+        }
+        check
+    };
+    checks.insert("mfl".to_string(), mfl);
+
+    let session_count = match sessions {
+        Some(store) => Some(app_state::blocking(store, |store| store.count()).await),
+        None => None,
+    };
+    checks.insert("session_store".to_string(), match session_count {
+        Some(Ok(count)) => up(true, Some(format!("{} sessions", count))),
+        Some(Err(e)) => down(true, e.to_string()),
+        None => down(true, "no session store configured".to_string()),
+    });
+
+    let cache = config.export_cache.stats();
+    checks.insert("export_cache".to_string(), up(false, Some(format!("{} entries", cache.entries))));
+
+    if let Some(store) = &config.offline_store {
+        checks.insert("offline_store".to_string(), match offline_store::blocking(store, |store| store.count()).await {
+            Ok(count) => up(false, Some(format!("{} stored replies", count))),
+            Err(e) => down(false, e.to_string()),
+        });
+    }
+
+    let ready = checks.values().all(|check| !check.critical || check.status == DependencyStatus::Up);
+    ReadinessResponse { ready, checks }
+}
+
+async fn probe_mfl(config: &MflClientConfig, critical: bool) -> DependencyCheck {
+    let api = match MflApi::with_config(chrono::Utc::now().year().to_string(), config) {
+        Ok(api) => api,
+        Err(e) => return down(critical, e.to_string()),
+    };
+    let started = Instant::now();
+    let mut check = match actix_web::rt::time::timeout(MFL_PROBE_TIMEOUT, api.ping()).await {
+        Ok(Ok(())) => up(critical, None),
+        Ok(Err(e)) => down(critical, e.to_string()),
+        Err(_) => down(critical, format!("no reply within {}s", MFL_PROBE_TIMEOUT.as_secs())),
+    };
+    check.latency_ms = Some(started.elapsed().as_millis() as u64);
+    check
+}
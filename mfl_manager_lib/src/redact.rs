@@ -0,0 +1,97 @@
+// src/redact.rs
+use std::borrow::Cow;
+use std::io::{self, Write};
+use once_cell::sync::Lazy;
+use regex::{Captures, Regex};
+use tracing_subscriber::fmt::MakeWriter;
+
+pub const REDACTED: &str = "[REDACTED]";
+
+// Each pattern keeps the name (group 1) and replaces the value (group 2)
+static SECRET_PATTERNS: Lazy<Vec<Regex>> = Lazy::new(|| {
+    [
+        // Query strings and form bodies: PASSWORD=hunter2&..., APIKEY=...
+        r#"(?i)\b((?:password|passwd|pwd|apikey|api_key|api-key|token|access_token|cookie|mfl_user_id)=)([^&\s;,"'\\<>]+)"#,
+        // XML attributes and debug output: MFL_USER_ID="abc", cookie_value="abc", password: "abc".
+        // Quotes may arrive escaped (\") when the line is JSON.
+        r#"(?i)\b((?:password|apikey|api_key|token|cookie_value|mfl_user_id)\\?"?\s*[:=]\s*\\?")([^"\\]*)"#,
+        // Headers, as `name: value` or in a debug-printed map
+        r#"(?i)\b((?:authorization|x-api-key)\\?"?\s*:\s*\\?"?)((?:bearer\s+)?[^\s",;}\\]+)"#,
+        r#"(?i)\b((?:set-cookie|cookie)\\?"?\s*:\s*\\?"?)([^\s",;}\\]+)"#,
+        r"(?i)\b(bearer\s+)([A-Za-z0-9\-._~+/]+=*)",
+        // API keys wherever they appear; the id half is kept so the key can still be identified
+        r"\b(mflk_[0-9a-f]{32}_)([A-Za-z0-9_-]+)",
+    ]
+    .iter()
+    .map(|pattern| Regex::new(pattern).expect("Invalid redaction regex"))
+    .collect()
+});
+
+/// Replaces anything that looks like a password, cookie, bearer token or API key in `text`.
+pub fn redact(text: &str) -> Cow<'_, str> {
+    let mut text = Cow::Borrowed(text);
+    for pattern in SECRET_PATTERNS.iter() {
+        if pattern.is_match(&text) {
+            let replaced = pattern.replace_all(&text, |caps: &Captures| format!("{}{}", &caps[1], REDACTED)).into_owned();
+            text = Cow::Owned(replaced);
+        }
+    }
+    text
+}
+
+/// Wraps the writer of a `tracing_subscriber::fmt` subscriber so every formatted
+/// line goes through [`redact`] first, whichever module (or dependency) logged it.
+#[derive(Debug, Clone)]
+pub struct RedactingMakeWriter<M> {
+    inner: M,
+}
+
+impl<M> RedactingMakeWriter<M> {
+    pub fn new(inner: M) -> Self {
+        RedactingMakeWriter { inner }
+    }
+}
+
+impl<'a, M: MakeWriter<'a>> MakeWriter<'a> for RedactingMakeWriter<M> {
+    type Writer = RedactingWriter<M::Writer>;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        RedactingWriter { inner: self.inner.make_writer(), buffer: Vec::new() }
+    }
+}
+
+/// Holds one event until it is complete, since a secret may span several `write` calls.
+pub struct RedactingWriter<W: Write> {
+    inner: W,
+    buffer: Vec<u8>,
+}
+
+impl<W: Write> RedactingWriter<W> {
+    fn write_redacted(&mut self) -> io::Result<()> {
+        if self.buffer.is_empty() {
+            return Ok(());
+        }
+        let text = String::from_utf8_lossy(&self.buffer);
+        self.inner.write_all(redact(&text).as_bytes())?;
+        self.buffer.clear();
+        self.inner.flush()
+    }
+}
+
+impl<W: Write> Write for RedactingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.buffer.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.write_redacted()
+    }
+}
+
+impl<W: Write> Drop for RedactingWriter<W> {
+    // The fmt layer drops the writer once the event is written
+    fn drop(&mut self) {
+        let _ = self.write_redacted();
+    }
+}
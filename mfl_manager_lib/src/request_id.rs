@@ -0,0 +1,53 @@
+// src/request_id.rs
+use std::future::Future;
+
+/// Header a request id is accepted from and returned in.
+pub const REQUEST_ID_HEADER: &str = "X-Request-Id";
+
+// Longest client supplied id we pass on; anything else gets a fresh one
+const MAX_REQUEST_ID_LEN: usize = 128;
+
+/// The id of the current request, inserted into request extensions by `RequestTracing`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RequestId(pub String);
+
+impl RequestId {
+    /// Keeps a well-formed id from the client (so callers can correlate their own logs), otherwise makes one up.
+    pub fn accept_or_generate(header: Option<&str>) -> Self {
+        match header.map(str::trim) {
+            Some(id) if is_acceptable(id) => RequestId(id.to_string()),
+            _ => RequestId(uuid::Uuid::new_v4().to_string()),
+        }
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+// Ids end up in logs and headers, so only allow a conservative character set
+fn is_acceptable(id: &str) -> bool {
+    !id.is_empty()
+        && id.len() <= MAX_REQUEST_ID_LEN
+        && id.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | ':'))
+}
+
+tokio::task_local! {
+    static CURRENT_REQUEST_ID: String;
+}
+
+/// Runs `future` with `id` as the current request id.
+pub async fn scope<F: Future>(id: &RequestId, future: F) -> F::Output {
+    CURRENT_REQUEST_ID.scope(id.0.clone(), future).await
+}
+
+/// Runs `f` with `id` as the current request id.
+pub fn sync_scope<R>(id: &RequestId, f: impl FnOnce() -> R) -> R {
+    CURRENT_REQUEST_ID.sync_scope(id.0.clone(), f)
+}
+
+/// The id of the request being handled, if `RequestTracing` is installed.
+/// Lets error bodies carry the id without threading the request through.
+pub fn current() -> Option<String> {
+    CURRENT_REQUEST_ID.try_with(String::clone).ok()
+}
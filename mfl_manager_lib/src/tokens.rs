@@ -0,0 +1,180 @@
+// src/tokens.rs
+use std::time::Duration;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use jsonwebtoken::errors::ErrorKind;
+use jsonwebtoken::jwk::{AlgorithmParameters, JwkSet};
+use jsonwebtoken::{Algorithm, DecodingKey, EncodingKey, Header, Validation};
+use once_cell::sync::Lazy;
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use crate::handler_models::{Role, SessionData};
+
+/// The `iss` claim of every access token we issue.
+pub const TOKEN_ISSUER: &str = "mfl_manager";
+
+// HS256 keys shorter than the hash output are rejected
+const MIN_KEY_LEN: usize = 32;
+
+#[derive(Error, Debug)]
+pub enum TokenError {
+    #[error("Token key set is not a valid JWKS document: {0}")]
+    KeySet(#[from] serde_json::Error),
+
+    #[error("Token key set has no keys")]
+    NoKeys,
+
+    #[error("Token key {kid:?} is not usable: {reason}")]
+    BadKey { kid: Option<String>, reason: String },
+
+    #[error("Token has expired")]
+    Expired,
+
+    #[error("Token was signed with an unknown key {0:?}")]
+    UnknownKey(Option<String>),
+
+    #[error("Token is invalid: {0}")]
+    Invalid(jsonwebtoken::errors::Error),
+
+    #[error("Token could not be signed: {0}")]
+    Signing(jsonwebtoken::errors::Error),
+}
+
+/// The claims of an access token. `sid` names the session holding the MFL cookie;
+/// everything else describes what the token may act on.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct AccessClaims {
+    pub sid: String,
+    pub league_id: String,
+    pub year: String,
+    pub franchise_id: Option<String>,
+    pub role: Role,
+    pub iss: String,
+    pub iat: u64,
+    pub exp: u64,
+}
+
+impl AccessClaims {
+    /// Claims for session `sid` in its active league, valid for `ttl` from `now`.
+    pub fn for_session(sid: &str, session: &SessionData, now: u64, ttl: Duration) -> Self {
+        AccessClaims {
+            sid: sid.to_string(),
+            league_id: session.league_id.clone(),
+            year: session.year.clone(),
+            franchise_id: session.franchise_id.clone(),
+            role: session.role,
+            iss: TOKEN_ISSUER.to_string(),
+            iat: now,
+            exp: now + ttl.as_secs(),
+        }
+    }
+
+    /// Scopes a stored session to the league the token was issued for.
+    pub fn apply_to(&self, session: &mut SessionData) {
+        session.league_id = self.league_id.clone();
+        session.year = self.year.clone();
+        session.franchise_id = self.franchise_id.clone();
+        session.role = self.role;
+    }
+}
+
+struct TokenKey {
+    kid: String,
+    encoding: EncodingKey,
+    decoding: DecodingKey,
+}
+
+/// HS256 keys for access tokens, loaded from a JWKS-style document of `oct` keys:
+/// `{"keys": [{"kty": "oct", "kid": "2025-10", "k": "<base64url secret>"}]}`.
+///
+/// The first key signs; every key verifies. To rotate, put a new key first and
+/// drop the old one once the tokens it signed have expired.
+pub struct TokenKeys {
+    keys: Vec<TokenKey>,
+}
+
+impl std::fmt::Debug for TokenKeys {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let kids: Vec<&str> = self.keys.iter().map(|key| key.kid.as_str()).collect();
+        f.debug_struct("TokenKeys").field("kids", &kids).finish_non_exhaustive()
+    }
+}
+
+// Used when the app doesn't register any keys; only valid inside this process
+static PROCESS_KEYS: Lazy<TokenKeys> = Lazy::new(TokenKeys::generate);
+
+impl TokenKeys {
+    pub fn from_jwks(json: &str) -> Result<Self, TokenError> {
+        let set: JwkSet = serde_json::from_str(json)?;
+        let keys = set.keys.iter()
+            .map(|jwk| {
+                let kid = jwk.common.key_id.clone();
+                let bad_key = |reason: &str| TokenError::BadKey { kid: kid.clone(), reason: reason.to_string() };
+                let AlgorithmParameters::OctetKey(params) = &jwk.algorithm else {
+                    return Err(bad_key("only symmetric (kty \"oct\") keys are supported"));
+                };
+                let kid = kid.clone().ok_or_else(|| bad_key("every key needs a kid"))?;
+                let secret = URL_SAFE_NO_PAD.decode(params.value.trim_end_matches('='))
+                    .map_err(|_| bad_key("k is not base64url"))?;
+                Self::key(kid, &secret).ok_or_else(|| bad_key("k must be at least 32 bytes"))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        if keys.is_empty() {
+            return Err(TokenError::NoKeys);
+        }
+        Ok(TokenKeys { keys })
+    }
+
+    /// A single random key. Tokens it signs die with the process.
+    pub fn generate() -> Self {
+        let mut secret = [0u8; MIN_KEY_LEN];
+        rand::rng().fill_bytes(&mut secret);
+        let kid = format!("process-{}", uuid::Uuid::new_v4().simple());
+        TokenKeys { keys: Self::key(kid, &secret).into_iter().collect() }
+    }
+
+    /// The keys to use when the app didn't register any.
+    pub fn process_default() -> &'static TokenKeys {
+        &PROCESS_KEYS
+    }
+
+    fn key(kid: String, secret: &[u8]) -> Option<TokenKey> {
+        (secret.len() >= MIN_KEY_LEN).then(|| TokenKey {
+            kid,
+            encoding: EncodingKey::from_secret(secret),
+            decoding: DecodingKey::from_secret(secret),
+        })
+    }
+
+    /// The kid new tokens are signed with.
+    pub fn signing_kid(&self) -> &str {
+        &self.keys[0].kid
+    }
+
+    pub fn issue(&self, claims: &AccessClaims) -> Result<String, TokenError> {
+        let key = &self.keys[0];
+        let mut header = Header::new(Algorithm::HS256);
+        header.kid = Some(key.kid.clone());
+        jsonwebtoken::encode(&header, claims, &key.encoding).map_err(TokenError::Signing)
+    }
+
+    /// Checks the signature, issuer and expiry of `token` and returns its claims.
+    pub fn verify(&self, token: &str) -> Result<AccessClaims, TokenError> {
+        let header = jsonwebtoken::decode_header(token).map_err(TokenError::Invalid)?;
+        let key = self.keys.iter()
+            .find(|key| header.kid.as_deref() == Some(key.kid.as_str()))
+            .ok_or(TokenError::UnknownKey(header.kid))?;
+
+        let mut validation = Validation::new(Algorithm::HS256);
+        validation.set_issuer(&[TOKEN_ISSUER]);
+        validation.set_required_spec_claims(&["exp", "iss"]);
+        validation.leeway = 0;
+        jsonwebtoken::decode::<AccessClaims>(token, &key.decoding, &validation)
+            .map(|data| data.claims)
+            .map_err(|e| match e.kind() {
+                ErrorKind::ExpiredSignature => TokenError::Expired,
+                _ => TokenError::Invalid(e),
+            })
+    }
+}
@@ -0,0 +1,289 @@
+// mfl_mock/src/lib.rs
+//! A small stand-in for the MFL API. It answers `login`, `export` and `import`
+//! requests the way MFL does, using the JSON fixtures in `fixtures/`.
+//! `{{base_url}}`, `{{year}}` and `{{league_id}}` in a fixture are filled in per request.
+use std::collections::HashMap;
+use std::net::TcpListener;
+use std::sync::Mutex;
+use actix_web::dev::ServerHandle;
+use actix_web::http::header::{COOKIE, LOCATION, SET_COOKIE};
+use actix_web::{web, App, HttpRequest, HttpResponse, HttpServer};
+
+/// Credentials the mock accepts, and the cookie it hands out for them.
+pub const MOCK_USERNAME: &str = "mock-user";
+pub const MOCK_PASSWORD: &str = "mock-password";
+pub const MOCK_COOKIE: &str = "mock-mfl-user-id";
+/// The league the fixtures describe.
+pub const MOCK_LEAGUE_ID: &str = "12345";
+
+// Exports MFL only answers for a logged in user
+const AUTHENTICATED_EXPORTS: &[&str] = &["myleagues", "abilities"];
+
+fn default_fixtures() -> HashMap<String, String> {
+    [
+        ("league", include_str!("../fixtures/league.json")),
+        ("myleagues", include_str!("../fixtures/myleagues.json")),
+        ("abilities", include_str!("../fixtures/abilities.json")),
+        ("freeAgents", include_str!("../fixtures/freeAgents.json")),
+        ("players", include_str!("../fixtures/players.json")),
+        ("messageBoard", include_str!("../fixtures/messageBoard.json")),
+        ("messageBoardThread", include_str!("../fixtures/messageBoardThread.json")),
+        ("nflByeWeeks", include_str!("../fixtures/nflByeWeeks.json")),
+    ]
+    .into_iter()
+    .map(|(export, body)| (export.to_string(), body.to_string()))
+    .collect()
+}
+
+/// One request the mock received. `params` holds the query string and any form fields.
+#[derive(Debug, Clone)]
+pub struct RecordedRequest {
+    pub method: String,
+    pub path: String,
+    pub params: HashMap<String, String>,
+    pub cookie: Option<String>,
+}
+
+impl RecordedRequest {
+    pub fn param(&self, name: &str) -> Option<&str> {
+        self.params.get(name).map(String::as_str)
+    }
+}
+
+/// Mutable behaviour of a running mock, shared with the request handlers.
+#[derive(Debug)]
+pub struct MockState {
+    base_url: String,
+    fixtures: Mutex<HashMap<String, String>>,
+    requests: Mutex<Vec<RecordedRequest>>,
+    // When set every request gets a 503, as during an MFL outage
+    down: Mutex<bool>,
+    // When set every request is redirected to this base URL, as when MFL moves a league
+    redirect_to: Mutex<Option<String>>,
+}
+
+impl MockState {
+    fn new(base_url: String) -> Self {
+        MockState {
+            base_url,
+            fixtures: Mutex::new(default_fixtures()),
+            requests: Mutex::new(Vec::new()),
+            down: Mutex::new(false),
+            redirect_to: Mutex::new(None),
+        }
+    }
+
+    fn record(&self, req: &HttpRequest, params: &HashMap<String, String>) {
+        let cookie = req.headers().get(COOKIE)
+            .and_then(|value| value.to_str().ok())
+            .and_then(cookie_value);
+        if let Ok(mut requests) = self.requests.lock() {
+            requests.push(RecordedRequest {
+                method: req.method().to_string(),
+                path: req.path().to_string(),
+                params: params.clone(),
+                cookie,
+            });
+        }
+    }
+
+    fn is_down(&self) -> bool {
+        self.down.lock().map(|down| *down).unwrap_or(false)
+    }
+
+    // Same path and query on the other host. 307 so a POST stays a POST
+    fn redirect(&self, req: &HttpRequest) -> Option<HttpResponse> {
+        let target = self.redirect_to.lock().ok()?.clone()?;
+        let location = match req.query_string() {
+            "" => format!("{}{}", target, req.path()),
+            query => format!("{}{}?{}", target, req.path(), query),
+        };
+        Some(HttpResponse::TemporaryRedirect().insert_header((LOCATION, location)).finish())
+    }
+
+    fn fixture(&self, export: &str, year: &str, league_id: &str) -> Option<String> {
+        let fixtures = self.fixtures.lock().ok()?;
+        let body = fixtures.get(export)?;
+        Some(body
+            .replace("{{base_url}}", &self.base_url)
+            .replace("{{year}}", year)
+            .replace("{{league_id}}", league_id))
+    }
+}
+
+// Pulls MFL_USER_ID out of a Cookie header
+fn cookie_value(header: &str) -> Option<String> {
+    header.split(';')
+        .filter_map(|pair| pair.trim().split_once('='))
+        .find(|(name, _)| *name == "MFL_USER_ID")
+        .map(|(_, value)| value.to_string())
+}
+
+fn has_session(req: &HttpRequest) -> bool {
+    req.headers().get(COOKIE)
+        .and_then(|value| value.to_str().ok())
+        .and_then(cookie_value)
+        .is_some_and(|cookie| cookie == MOCK_COOKIE)
+}
+
+fn xml(body: String) -> HttpResponse {
+    HttpResponse::Ok().content_type("application/xml").body(body)
+}
+
+fn unavailable() -> HttpResponse {
+    HttpResponse::ServiceUnavailable().content_type("text/html").body("<html><body>Service Unavailable</body></html>")
+}
+
+fn login(req: &HttpRequest, params: &HashMap<String, String>, state: &MockState) -> HttpResponse {
+    state.record(req, params);
+    if state.is_down() {
+        return unavailable();
+    }
+    let username = params.get("USERNAME").map(String::as_str);
+    let password = params.get("PASSWORD").map(String::as_str);
+    if username != Some(MOCK_USERNAME) || password != Some(MOCK_PASSWORD) {
+        return xml("<error>Invalid Password</error>".to_string());
+    }
+    HttpResponse::Ok()
+        .content_type("application/xml")
+        .insert_header((SET_COOKIE, format!("MFL_USER_ID={}; path=/", MOCK_COOKIE)))
+        .body(format!(
+            r#"<status cookie_name="MFL_USER_ID" cookie_value="{0}" MFL_USER_ID="{0}">OK</status>"#,
+            MOCK_COOKIE
+        ))
+}
+
+async fn login_get(req: HttpRequest, query: web::Query<HashMap<String, String>>, state: web::Data<MockState>) -> HttpResponse {
+    login(&req, &query, &state)
+}
+
+async fn login_post(req: HttpRequest, form: web::Form<HashMap<String, String>>, state: web::Data<MockState>) -> HttpResponse {
+    login(&req, &form, &state)
+}
+
+async fn export(
+    req: HttpRequest,
+    year: web::Path<String>,
+    query: web::Query<HashMap<String, String>>,
+    state: web::Data<MockState>,
+) -> HttpResponse {
+    state.record(&req, &query);
+    if state.is_down() {
+        return unavailable();
+    }
+    if let Some(redirect) = state.redirect(&req) {
+        return redirect;
+    }
+    let export = query.get("TYPE").map(String::as_str).unwrap_or_default();
+    if AUTHENTICATED_EXPORTS.contains(&export) && !has_session(&req) {
+        return HttpResponse::Ok().json(serde_json::json!({ "error": { "$t": "API requires logged in user" } }));
+    }
+    let league_id = query.get("L").map(String::as_str).unwrap_or(MOCK_LEAGUE_ID);
+    match state.fixture(export, &year, league_id) {
+        Some(body) => HttpResponse::Ok().content_type("application/json").body(body),
+        None => HttpResponse::Ok().json(serde_json::json!({ "error": { "$t": format!("Invalid export type: {}", export) } })),
+    }
+}
+
+async fn import(req: HttpRequest, form: web::Form<HashMap<String, String>>, state: web::Data<MockState>) -> HttpResponse {
+    state.record(&req, &form);
+    if state.is_down() {
+        return unavailable();
+    }
+    if let Some(redirect) = state.redirect(&req) {
+        return redirect;
+    }
+    if !has_session(&req) {
+        return xml("<error>API requires logged in user</error>".to_string());
+    }
+    match form.get("TYPE").map(String::as_str) {
+        Some("messageBoard") if form.get("BODY").is_none_or(|body| body.trim().is_empty()) => {
+            xml("<error>Message body is required</error>".to_string())
+        }
+        Some("messageBoard") => xml("<status>OK</status>".to_string()),
+        other => xml(format!("<error>Unsupported import type: {}</error>", other.unwrap_or_default())),
+    }
+}
+
+fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.route("/{year}/login", web::get().to(login_get))
+        .route("/{year}/login", web::post().to(login_post))
+        .route("/{year}/export", web::get().to(export))
+        .route("/{year}/import", web::post().to(import));
+}
+
+/// A mock MFL server listening on a local port.
+pub struct MockMflServer {
+    state: web::Data<MockState>,
+    handle: ServerHandle,
+}
+
+impl MockMflServer {
+    /// Starts the mock on a free port. Must be called from within the Actix runtime.
+    pub fn start() -> std::io::Result<Self> {
+        Self::bind("127.0.0.1:0")
+    }
+
+    pub fn bind(addr: &str) -> std::io::Result<Self> {
+        let listener = TcpListener::bind(addr)?;
+        let base_url = format!("http://{}", listener.local_addr()?);
+        let state = web::Data::new(MockState::new(base_url));
+
+        let app_state = state.clone();
+        let server = HttpServer::new(move || {
+            App::new()
+                .app_data(app_state.clone())
+                .configure(configure)
+        })
+        .workers(1)
+        .disable_signals()
+        .listen(listener)?
+        .run();
+        let handle = server.handle();
+        actix_web::rt::spawn(server);
+
+        Ok(MockMflServer { state, handle })
+    }
+
+    /// Base URL to hand to `MflClientConfig::base_url`.
+    pub fn base_url(&self) -> &str {
+        &self.state.base_url
+    }
+
+    /// Replaces the reply for one export `TYPE`.
+    pub fn set_fixture(&self, export: &str, body: &str) {
+        if let Ok(mut fixtures) = self.state.fixtures.lock() {
+            fixtures.insert(export.to_string(), body.to_string());
+        }
+    }
+
+    /// Simulates an MFL outage: every request gets a 503 until set back to false.
+    pub fn set_down(&self, down: bool) {
+        if let Ok(mut current) = self.state.down.lock() {
+            *current = down;
+        }
+    }
+
+    /// Redirects every export and import to `base_url` until set back to `None`.
+    pub fn set_redirect(&self, base_url: Option<&str>) {
+        if let Ok(mut current) = self.state.redirect_to.lock() {
+            *current = base_url.map(str::to_string);
+        }
+    }
+
+    /// Every request received so far, oldest first.
+    pub fn requests(&self) -> Vec<RecordedRequest> {
+        self.state.requests.lock().map(|requests| requests.clone()).unwrap_or_default()
+    }
+
+    /// How many exports of the given `TYPE` were requested.
+    pub fn export_count(&self, export: &str) -> usize {
+        self.requests().iter()
+            .filter(|request| request.path.ends_with("/export") && request.param("TYPE") == Some(export))
+            .count()
+    }
+
+    pub async fn stop(self) {
+        self.handle.stop(true).await;
+    }
+}
@@ -0,0 +1,18 @@
+// mfl_mock/src/main.rs
+// Runs the mock on its own, e.g. to point a local mfl_manager at it with
+// MFL_BASE_URL=http://127.0.0.1:8099
+use mfl_mock::{MockMflServer, MOCK_LEAGUE_ID, MOCK_PASSWORD, MOCK_USERNAME};
+
+#[actix_web::main]
+async fn main() -> std::io::Result<()> {
+    env_logger::init();
+
+    let addr = std::env::var("MFL_MOCK_ADDR").unwrap_or_else(|_| "127.0.0.1:8099".to_string());
+    let server = MockMflServer::bind(&addr)?;
+    log::info!("Mock MFL listening on {}", server.base_url());
+    log::info!("Log in as {} / {}, league {}", MOCK_USERNAME, MOCK_PASSWORD, MOCK_LEAGUE_ID);
+
+    actix_web::rt::signal::ctrl_c().await?;
+    server.stop().await;
+    Ok(())
+}